@@ -1,40 +1,81 @@
-use crossterm::event::{read, Event, KeyEvent, KeyEventKind};
+use crossterm::event::{poll, read, Event, KeyEvent, KeyEventKind};
 use std::{
-    env, io::Error, panic::{set_hook, take_hook}
+    env, io::Error, panic::{set_hook, take_hook},
+    fs::canonicalize,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 mod annotatedstring;
+mod cargocheck;
 mod command;
+mod config;
+mod git;
 mod uicomponents;
 mod documentstatus;
+mod json;
+mod lsp;
+mod layout;
 mod terminal;
 mod line;
 mod position;
 mod size;
+mod tags;
 
 use annotatedstring::{AnnotatedString, AnnotationType};
-use uicomponents::{CommandBar,MessageBar,View, StatusBar, UIComponent};
+use cargocheck::CargoCheck;
+use config::{ColorSupport, Config};
+use lsp::{Answer, CompletionItem, LanguageServers};
+use uicomponents::{
+    BufferHandle, CommandBar, CompletionPopup, GotoTarget, Highlight, MessageBar, Picker, Severity,
+    StatusBar, TabBar, TabInfo, TextEdit, UIComponent, View, WordRanking,
+};
 use documentstatus::DocumentStatus;
+use layout::{Area, Direction, Layout};
 use line::Line;
 use position::{Col, Position, Row};
 use size::Size;
-use terminal::Terminal;
+use tags::Tag;
+use terminal::{Terminal, Theme};
 
 use self::command::{
-    Command::{self, Edit, Move, System},
-    Edit::InsertNewline,
+    Bookmark,
+    Diagnostics,
+    Display,
+    Fold,
+    Hunk,
+    Jump,
+    Language,
+    Mouse,
+    Tab,
+    Window,
+    Command::{self, Edit, Move, Select, System},
+    Edit::{DeleteBackward, Insert, InsertNewline},
     Move::{Down, Left, Right, Up},
-    System::{Dismiss, Quit, Resize, Save, Search},
+    System::{Complete, Dismiss, GotoLine, Quit, Resize, Save, Search},
 };
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const QUIT_TIMES: u8 = 3;
+// How many definition jumps can be jumped back from
+const MAX_TAG_STACK: usize = 100;
+// How often to look for messages from the language servers while no key is pressed
+const LANGUAGE_SERVER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Two clicks on the same spot within this interval make a double click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+const WHEEL_SCROLL_LINES: usize = 3;
+const PANE_SEPARATOR: &str = "│";
 
 #[derive(Eq, PartialEq, Default)]
 enum PromptType {
     Search,
     Save,
+    Open,
+    GotoLine,
+    SetBookmark,
+    JumpToBookmark,
+    Rename,
     #[default]
     None,
 }
@@ -45,17 +86,90 @@ impl PromptType {
     }
 }
 
+// What the entries of the picker stand for
+#[derive(Eq, PartialEq, Default)]
+enum PickerType {
+    Bookmarks,
+    // Documentation, only to be read
+    Hover,
+    // The definitions of a symbol, see tag_matches
+    Tags,
+    #[default]
+    None,
+}
+
+// A view with its own status bar on its last row
 #[derive(Default)]
-pub struct Editor {
-    should_quit: bool,
+struct Pane {
     view: View,
     status_bar: StatusBar,
+    area: Area,
+}
+
+impl Pane {
+    fn set_area(&mut self, area: Area) {
+        self.area = area;
+        self.view.resize(Size {
+            height: area.size.height.saturating_sub(1),
+            width: area.size.width,
+        });
+        self.status_bar.resize(Size {
+            height: 1,
+            width: area.size.width,
+        });
+    }
+    fn render(&mut self) {
+        let Area { origin, size } = self.area;
+        if size.height == 0 || size.width == 0 {
+            return;
+        }
+        let status_bar_row = origin.row.saturating_add(size.height).saturating_sub(1);
+        self.view.render(origin);
+        self.status_bar.render(Position {
+            row: status_bar_row,
+            col: origin.col,
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct Editor {
+    should_quit: bool,
+    // Never empty
+    panes: Vec<Pane>,
+    focused: usize,
+    layout: Layout,
+    // Columns between side by side panes
+    separators: Vec<Area>,
+    // In tab bar order
+    buffers: Vec<BufferHandle>,
+    tab_bar: TabBar,
     title: String,
     message_bar: MessageBar,
     command_bar: CommandBar,
+    completion_popup: CompletionPopup,
+    picker: Picker,
+    picker_type: PickerType,
     prompt_type: PromptType,
     terminal_size: Size,
     quit_times: u8,
+    // When and where the left button was last pressed, to detect double clicks
+    last_click: Option<(Instant, Position)>,
+    // The message of the diagnostic the caret was last on, so it's only shown once
+    shown_diagnostic: Option<String>,
+    // The same for the commit of the caret's line while the blame is shown
+    shown_blame: Option<String>,
+    language_servers: LanguageServers,
+    // The buffer, its revision and the caret location the language server request being answered was made at
+    language_request: Option<(BufferHandle, usize, (usize, usize))>,
+    // The cargo check running in the background, if any
+    cargo_check: Option<CargoCheck>,
+    // The candidates while the completion popup shows language server completions
+    language_completions: Option<Vec<CompletionItem>>,
+    // The locations definition jumps started from, the last one on top
+    tag_stack: Vec<(BufferHandle, (usize, usize))>,
+    // The tags shown in the picker
+    tag_matches: Vec<Tag>,
 }
 
 impl Editor {
@@ -70,17 +184,32 @@ impl Editor {
         Terminal::initialize()?;
 
         let mut editor = Self::default();
+        editor.panes.push(Pane::default());
+        editor.focus_pane(0);
+        editor.buffers.push(editor.view().buffer().clone());
         let size = Terminal::size().unwrap_or_default();
         
         editor.handle_resize_command(size);
         editor.update_message("HELP: Ctrl-F = Search | Ctrl-S = save | Ctrl-Q = quit");
 
-        let args: Vec<String> = env::args().collect();
-        if let Some(file_name) = args.get(1) {
+        let (config, config_error) = Config::load();
+        editor.view_mut().configure(&config);
+        editor.language_servers = LanguageServers::new(config.language_servers.clone());
+        Terminal::set_color_support(config.colors.unwrap_or_else(ColorSupport::detect));
+        let theme = config.theme.as_deref().map_or_else(|| Ok(Theme::default()), Theme::load);
+        let theme_error = theme.as_ref().err().cloned();
+        editor.set_theme(theme.unwrap_or_default());
+        if let Some(err) = config_error.or(theme_error) {
+            editor.update_message(&format!("ERR: {err}"));
+        }
+
+        for file_name in env::args().skip(1) {
             debug_assert!(!file_name.is_empty());
-            if editor.view.load(file_name).is_err() {
-                editor.update_message(&format!("ERR: Could not open file: {file_name}"));
-            }
+            editor.open_file(&file_name);
+        }
+        // Start out with the first file
+        if let Some(buffer) = editor.buffers.first().cloned() {
+            editor.view_mut().show_buffer(buffer);
         }
         editor.refresh_status();
         Ok(editor)
@@ -90,10 +219,20 @@ impl Editor {
     // region: Event loop
     pub fn run(&mut self) {
         loop {
+            self.sync_language_servers();
+            self.apply_language_server_answer();
+            self.apply_cargo_check_reports();
             self.refresh_screen();
             if self.should_quit {
                 break;
             }
+            // Language servers and cargo report diagnostics whenever they're done, not only in reaction to a key
+            if (self.language_servers.is_running() || self.cargo_check.is_some())
+                && !poll(LANGUAGE_SERVER_POLL_INTERVAL).unwrap_or(true)
+            {
+                self.apply_language_server_diagnostics();
+                continue;
+            }
             match read() {
                 Ok(event) => self.evaluate_event(event),
                 Err(err) => {
@@ -119,18 +258,38 @@ impl Editor {
         let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
         let _ = Terminal::hide_caret();
         
+        let bottom_bar = Position {
+            row: bottom_bar_row,
+            col: 0,
+        };
         if self.in_prompt() {
-            self.command_bar.render(bottom_bar_row);
+            self.command_bar.render(bottom_bar);
         } else {
-            self.message_bar.render(bottom_bar_row);
+            self.message_bar.render(bottom_bar);
         }
 
         if self.terminal_size.height > 1 {
-            self.status_bar.render(self.terminal_size.height.saturating_sub(2));
-        }
-
-        if self.terminal_size.height > 2 {
-            self.view.render(0);
+            // Popups are drawn on top of the panes, which therefore need to be redrawn beneath them
+            if self.completion_popup.needs_redraw() || self.picker.needs_redraw() {
+                self.redraw_panes();
+                self.tab_bar.set_needs_redraw(true);
+            }
+            if self.terminal_size.height > 2 {
+                self.tab_bar.render(Position::default());
+            }
+            let redraw_separators = self.panes.iter().any(|pane| pane.view.needs_redraw());
+            for pane in &mut self.panes {
+                pane.render();
+            }
+            if redraw_separators {
+                self.render_separators();
+            }
+            if self.completion_popup.is_visible() {
+                self.completion_popup.render(Position::default());
+            }
+            if self.picker.is_visible() {
+                self.picker.render(Position::default());
+            }
         }
 
         let new_caret_pos = if self.in_prompt() {
@@ -139,7 +298,7 @@ impl Editor {
                 col: self.command_bar.caret_position_col(),
             }
         } else {
-            self.view.caret_position()
+            self.caret_position()
         };
         debug_assert!(new_caret_pos.col <= self.terminal_size.width);
         debug_assert!(new_caret_pos.row <= self.terminal_size.height);
@@ -148,10 +307,36 @@ impl Editor {
         let _ = Terminal::show_caret();
         let _ = Terminal::execute();
     }
+    fn render_separators(&self) {
+        for separator in &self.separators {
+            let Area { origin, size } = *separator;
+            for row in origin.row..origin.row.saturating_add(size.height) {
+                let _ = Terminal::print_styled_at(
+                    Position {
+                        row,
+                        col: origin.col,
+                    },
+                    PANE_SEPARATOR,
+                    AnnotationType::PaneSeparator,
+                );
+            }
+        }
+    }
     pub fn refresh_status(&mut self) {
-        let status = self.view.get_status();
-        let title = format!("{} - {NAME}", status.file_name);
-        self.status_bar.update_status(status);
+        for pane in &mut self.panes {
+            let status = pane.view.get_status();
+            pane.status_bar.update_status(status);
+        }
+        let tabs = self
+            .buffers
+            .iter()
+            .map(|buffer| TabInfo {
+                name: buffer.name(),
+                is_modified: buffer.is_modified(),
+            })
+            .collect();
+        self.tab_bar.set_tabs(tabs, self.active_tab_idx());
+        let title = format!("{} - {NAME}", self.view().get_status().file_name);
 
         if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
             self.title = title;
@@ -160,7 +345,7 @@ impl Editor {
     fn evaluate_event(&mut self, event: Event) {
         let should_process = match &event {
             Event::Key(KeyEvent {kind, ..}) => kind == &KeyEventKind::Press,
-            Event::Resize(_, _) => true,
+            Event::Resize(_, _) | Event::Mouse(_) => true,
             _ => false,
         };
 
@@ -168,6 +353,9 @@ impl Editor {
             // Ici, seulement traiter les touches avec des commandes. Les touches sans commande associée (Err{}) ne feront rien
             if let Ok(command) = Command::try_from(event) {
                 self.process_command(command);
+                self.sync_panes();
+                self.show_diagnostic_at_caret();
+                self.show_blame_at_caret();
             }
         }
         
@@ -185,9 +373,23 @@ impl Editor {
             self.handle_resize_command(size);
             return;
         }
+        if self.completion_popup.is_visible() {
+            self.process_command_during_completion(command);
+            return;
+        }
+        if self.picker.is_visible() {
+            self.process_command_during_picker(command);
+            return;
+        }
         match self.prompt_type {
             PromptType::Search => self.process_command_during_search(command),
             PromptType::Save => self.process_command_during_save(command),
+            PromptType::Open => self.process_command_during_open(command),
+            PromptType::GotoLine => self.process_command_during_goto_line(command),
+            PromptType::SetBookmark | PromptType::JumpToBookmark => {
+                self.process_command_during_bookmark_prompt(command);
+            }
+            PromptType::Rename => self.process_command_during_rename(command),
             PromptType::None => self.process_command_no_prompt(command),
         }
     }
@@ -201,11 +403,39 @@ impl Editor {
         self.reset_quit_times();
         
         match command {
-            System(Quit | Resize(_) | Dismiss) => {}
+            System(Quit | Resize(_)) => {}
+            System(Dismiss) => {
+                self.cancel_language_request();
+                self.view_mut().exit_snippet();
+            }
             System(Search) => self.set_prompt(PromptType::Search),
             System(Save) => self.handle_save_command(),
-            Edit(edit_command) => self.view.handle_edit_command(edit_command),
-            Move(move_command) => self.view.handle_move_command(move_command),
+            System(Complete) => self.start_completion(),
+            System(GotoLine) => self.set_prompt(PromptType::GotoLine),
+            Edit(edit_command) => self.view_mut().handle_edit_command(edit_command),
+            Move(move_command) => self.view_mut().handle_move_command(move_command),
+            Select(select_command) => self.view_mut().handle_select_command(select_command),
+            Command::Bookmark(bookmark_command) => self.handle_bookmark_command(bookmark_command),
+            Command::Jump(Jump::Back) => {
+                if !self.view_mut().jump_back() {
+                    self.update_message("Already at the oldest jump.");
+                }
+            }
+            Command::Jump(Jump::Forward) => {
+                if !self.view_mut().jump_forward() {
+                    self.update_message("Already at the newest jump.");
+                }
+            }
+            Command::Mouse(mouse_command) => self.handle_mouse_command(mouse_command),
+            Command::Display(display_command) => self.handle_display_command(display_command),
+            Command::Fold(fold_command) => self.handle_fold_command(fold_command),
+            Command::Window(window_command) => self.handle_window_command(window_command),
+            Command::Tab(tab_command) => self.handle_tab_command(tab_command),
+            Command::Diagnostics(diagnostics_command) => {
+                self.handle_diagnostics_command(diagnostics_command);
+            }
+            Command::Language(language_command) => self.handle_language_command(language_command),
+            Command::Hunk(hunk_command) => self.handle_hunk_command(hunk_command),
         }
 
     }
@@ -214,17 +444,21 @@ impl Editor {
     // region: resize command handling
     pub fn handle_resize_command(&mut self, size: Size) {
         self.terminal_size = size;
-        self.view.resize(Size {
-            height: size.height.saturating_sub(2), // status bar & message_bar heights combined
+        self.arrange_panes();
+        // Popups may cover anything above the message bar
+        let popup_size = Size {
+            height: size.height.saturating_sub(1),
             width: size.width,
-        });
+        };
+        self.completion_popup.resize(popup_size);
+        self.picker.resize(popup_size);
         let bar_size = Size {
             height: 1,
             width: size.width,
         };
         self.message_bar.resize(bar_size);
-        self.status_bar.resize(bar_size);
         self.command_bar.resize(bar_size);
+        self.tab_bar.resize(bar_size);
     }
     // endregion
 
@@ -232,7 +466,8 @@ impl Editor {
     #[allow(clippy::arithmetic_side_effects)]
     fn handle_quit_command(&mut self) {
         self.quit_times += 1;
-        if !self.view.get_status().is_modified || self.quit_times == QUIT_TIMES {
+        let is_modified = self.buffers.iter().any(BufferHandle::is_modified);
+        if !is_modified || self.quit_times == QUIT_TIMES {
             self.should_quit = true;
         }
         else {
@@ -252,7 +487,7 @@ impl Editor {
 
     // region save command & prompt handling
     fn handle_save_command(&mut self) {
-        if self.view.is_file_loaded() {
+        if self.view().is_file_loaded() {
             self.save(None);
         } else {
             self.set_prompt(PromptType::Save);
@@ -260,7 +495,7 @@ impl Editor {
     }
     fn process_command_during_save(&mut self, command: Command) {
         match command {
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {} // Not applicable during save, Resize already handled at this stage
+            System(Quit | Resize(_) | Search | Save | Complete | GotoLine) | Move(_) | Select(_) | Command::Bookmark(_) | Command::Jump(_) | Command::Mouse(_) | Command::Display(_) | Command::Fold(_) | Command::Window(_) | Command::Tab(_) | Command::Diagnostics(_) | Command::Language(_) | Command::Hunk(_) => {} // Not applicable during save, Resize already handled at this stage
             System(Dismiss) => {
                 self.set_prompt(PromptType::None);
                 self.update_message("Save aborted.");
@@ -275,12 +510,16 @@ impl Editor {
     }
     fn save(&mut self, file_name: Option<&str>) {
         let result = if let Some(name) = file_name {
-            self.view.save_as(name)
+            self.view_mut().save_as(name)
         } else {
-            self.view.save()
+            self.view_mut().save()
         };
     
         if result.is_ok() {
+            let buffer = self.view().buffer().clone();
+            self.language_servers.did_save(&buffer);
+            // Saving under another name, or after committing, changes what to compare with
+            Self::load_git_base(&buffer);
             self.update_message("Fichier sauvegardé correctement.");
         } else {
             self.update_message("Impossible de sauvegarder le fichier.");
@@ -293,20 +532,967 @@ impl Editor {
         match command {
             System(Dismiss) => {
                 self.set_prompt(PromptType::None);
-                self.view.dismiss_search();
+                self.view_mut().dismiss_search();
             }
             Edit(InsertNewline) => {
                 self.set_prompt(PromptType::None);
-                self.view.exit_search();
+                self.view_mut().exit_search();
             }
             Edit(edit_command) => {
                 self.command_bar.handle_edit_command(edit_command);
                 let query = self.command_bar.value();
-                self.view.search(&query);
+                self.view_mut().search(&query);
+            }
+            Move(Right | Down) => self.view_mut().search_next(),
+            Move(Up | Left) => self.view_mut().search_prev(),
+            System(Quit | Resize(_) | Search | Save | Complete | GotoLine) | Move(_) | Select(_) | Command::Bookmark(_) | Command::Jump(_) | Command::Mouse(_) | Command::Display(_) | Command::Fold(_) | Command::Window(_) | Command::Tab(_) | Command::Diagnostics(_) | Command::Language(_) | Command::Hunk(_) => {} // Not applicable during save, Resize already handled at this stage
+        }
+    }
+    // endregion
+
+    // region: go to line prompt handling
+    fn process_command_during_goto_line(&mut self, command: Command) {
+        match command {
+            System(Quit | Resize(_) | Search | Save | Complete | GotoLine) | Move(_) | Select(_) | Command::Bookmark(_) | Command::Jump(_) | Command::Mouse(_) | Command::Display(_) | Command::Fold(_) | Command::Window(_) | Command::Tab(_) | Command::Diagnostics(_) | Command::Language(_) | Command::Hunk(_) => {} // Not applicable during go to line, Resize already handled at this stage
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let input = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                match GotoTarget::parse(&input) {
+                    Ok(target) => self.view_mut().goto(target),
+                    Err(err) => self.update_message(&err),
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+        }
+    }
+    // endregion
+
+    // region: bookmark command & prompt handling
+    fn handle_bookmark_command(&mut self, command: Bookmark) {
+        match command {
+            Bookmark::Toggle => {
+                if self.view_mut().toggle_bookmark() {
+                    self.update_message("Bookmark set.");
+                } else {
+                    self.update_message("Bookmark removed.");
+                }
+            }
+            Bookmark::SetNamed => self.set_prompt(PromptType::SetBookmark),
+            Bookmark::JumpToNamed => self.set_prompt(PromptType::JumpToBookmark),
+            Bookmark::Next | Bookmark::Prev => {
+                if !self.view_mut().cycle_bookmarks(matches!(command, Bookmark::Next)) {
+                    self.update_message("No bookmarks.");
+                }
+            }
+            Bookmark::List => {
+                let bookmarks = self.view().bookmark_list();
+                if bookmarks.is_empty() {
+                    self.update_message("No bookmarks.");
+                } else {
+                    self.show_picker(PickerType::Bookmarks, "Bookmarks (Enter to jump, Esc to close)", bookmarks);
+                }
+            }
+        }
+    }
+    // Bookmark names are a single letter, the prompt closes as soon as it's typed.
+    fn process_command_during_bookmark_prompt(&mut self, command: Command) {
+        match command {
+            Edit(Insert(name)) => {
+                let prompt_type = std::mem::take(&mut self.prompt_type);
+                self.set_prompt(PromptType::None);
+                if !name.is_ascii_alphabetic() {
+                    self.update_message(&format!("Invalid bookmark name '{name}', expected a letter."));
+                } else if prompt_type == PromptType::SetBookmark {
+                    self.view_mut().set_bookmark(name);
+                    self.update_message(&format!("Bookmark '{name}' set."));
+                } else if !self.view_mut().jump_to_bookmark(name) {
+                    self.update_message(&format!("No bookmark named '{name}'."));
+                }
+            }
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            _ => {}
+        }
+    }
+    // endregion
+
+    // region: display command handling
+    fn handle_display_command(&mut self, command: Display) {
+        match command {
+            Display::ToggleSoftWrap => {
+                if self.view_mut().toggle_soft_wrap() {
+                    self.update_message("Soft wrap on.");
+                } else {
+                    self.update_message("Soft wrap off.");
+                }
+            }
+            Display::CycleLineNumbers => {
+                let line_numbers = self.view_mut().cycle_line_numbers();
+                self.update_message(&format!("Line numbers: {line_numbers}."));
+            }
+            Display::ToggleWhitespace => {
+                if self.view_mut().toggle_whitespace() {
+                    self.update_message("Whitespace shown.");
+                } else {
+                    self.update_message("Whitespace hidden.");
+                }
+            }
+            Display::CycleTheme => {
+                let names = Theme::available();
+                let current = Terminal::theme_name();
+                let next = names
+                    .iter()
+                    .position(|name| *name == current)
+                    .and_then(|idx| names.get(idx.saturating_add(1)))
+                    .or_else(|| names.first());
+                let Some(next) = next else {
+                    return;
+                };
+                match Theme::load(next) {
+                    Ok(theme) => {
+                        self.set_theme(theme);
+                        self.update_message(&format!("Theme: {next}."));
+                    }
+                    Err(err) => self.update_message(&format!("ERR: {err}")),
+                }
+            }
+            Display::ToggleBlame => self.toggle_blame(),
+        }
+    }
+    // Everything gets drawn again in the colors of the new theme
+    fn set_theme(&mut self, theme: Theme) {
+        Terminal::set_theme(theme);
+        let _ = Terminal::clear_screen();
+        self.redraw_panes();
+        self.tab_bar.set_needs_redraw(true);
+        self.message_bar.set_needs_redraw(true);
+        self.command_bar.set_needs_redraw(true);
+    }
+    // endregion
+
+    // region: fold command handling
+    fn handle_fold_command(&mut self, command: Fold) {
+        match command {
+            Fold::Close => {
+                if !self.view_mut().fold_at_caret() {
+                    self.update_message("Nothing to fold here.");
+                }
+            }
+            Fold::Open => {
+                if !self.view_mut().unfold_at_caret() {
+                    self.update_message("No fold here.");
+                }
+            }
+            Fold::ToggleAll => {
+                if !self.view_mut().toggle_all_folds() {
+                    self.update_message("All folds opened.");
+                }
+            }
+        }
+    }
+    // endregion
+
+    // region: mouse
+    // The tab bar is on top of the panes, each with its status bar at the bottom, followed by the message/command bar.
+    // Clicking into a pane focuses it.
+    fn handle_mouse_command(&mut self, command: Mouse) {
+        let message_bar_row = self.terminal_size.height.saturating_sub(1);
+        match command {
+            Mouse::Click { position, extend } => {
+                let Some(pane_idx) = self.panes.iter().position(|pane| pane.area.contains(position)) else {
+                    if position.row == message_bar_row {
+                        self.set_prompt(PromptType::Search);
+                    } else if let Some(idx) = self.tab_bar.tab_at(position.col).filter(|_| position.row == 0) {
+                        self.show_tab(idx);
+                    }
+                    return;
+                };
+                if pane_idx != self.focused {
+                    self.focus_pane(pane_idx);
+                }
+                let view_position = self.view_position(position);
+                // The status bar shows the caret position: clicking it asks where to go
+                if view_position.row >= self.view_height() {
+                    self.set_prompt(PromptType::GotoLine);
+                    return;
+                }
+                let is_double_click = !extend
+                    && self.last_click.is_some_and(|(time, last_position)| {
+                        last_position == position && time.elapsed() <= DOUBLE_CLICK_INTERVAL
+                    });
+                if is_double_click {
+                    self.view_mut().select_word_at(view_position);
+                    self.last_click = None;
+                } else {
+                    self.view_mut().click(view_position, extend);
+                    self.last_click = Some((Instant::now(), position));
+                }
+            }
+            Mouse::Drag(position) => {
+                let view_position = self.view_position(position);
+                self.view_mut().drag(view_position);
+            }
+            Mouse::ScrollUp => self.view_mut().scroll(true, WHEEL_SCROLL_LINES),
+            Mouse::ScrollDown => self.view_mut().scroll(false, WHEEL_SCROLL_LINES),
+        }
+    }
+    // endregion
+
+    // region: panes
+    fn focused_pane(&self) -> &Pane {
+        // clippy::indexing_slicing: panes is never empty, the last pane can't be closed, and focused is always in range
+        #[allow(clippy::indexing_slicing)]
+        &self.panes[self.focused]
+    }
+    fn focused_pane_mut(&mut self) -> &mut Pane {
+        // clippy::indexing_slicing: panes is never empty, the last pane can't be closed, and focused is always in range
+        #[allow(clippy::indexing_slicing)]
+        &mut self.panes[self.focused]
+    }
+    fn view(&self) -> &View {
+        &self.focused_pane().view
+    }
+    fn view_mut(&mut self) -> &mut View {
+        &mut self.focused_pane_mut().view
+    }
+    fn view_height(&self) -> usize {
+        self.focused_pane().area.size.height.saturating_sub(1)
+    }
+    // Converts a terminal position to one relative to the focused view.
+    fn view_position(&self, position: Position) -> Position {
+        position.saturating_sub(self.focused_pane().area.origin)
+    }
+    // Where the caret of the focused view is on the terminal
+    fn caret_position(&self) -> Position {
+        let origin = self.focused_pane().area.origin;
+        let caret = self.view().caret_position();
+        Position {
+            row: origin.row.saturating_add(caret.row),
+            col: origin.col.saturating_add(caret.col),
+        }
+    }
+    // Everything between the tab bar and the message bar
+    fn panes_area(&self) -> Area {
+        Area {
+            origin: Position { row: 1, col: 0 },
+            size: Size {
+                height: self.terminal_size.height.saturating_sub(2),
+                width: self.terminal_size.width,
+            },
+        }
+    }
+    fn arrange_panes(&mut self) {
+        let arrangement = self.layout.arrange(self.panes_area());
+        for (idx, area) in arrangement.panes {
+            if let Some(pane) = self.panes.get_mut(idx) {
+                pane.set_area(area);
+            }
+        }
+        self.separators = arrangement.separators;
+    }
+    // Pane indices, from the top left to the bottom right
+    fn pane_order(&self) -> Vec<usize> {
+        self.layout
+            .arrange(self.panes_area())
+            .panes
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+    fn focus_pane(&mut self, idx: usize) {
+        self.focused = idx;
+        for (pane_idx, pane) in self.panes.iter_mut().enumerate() {
+            pane.status_bar.set_focused(pane_idx == idx);
+        }
+    }
+    fn redraw_panes(&mut self) {
+        for pane in &mut self.panes {
+            pane.view.set_needs_redraw(true);
+            pane.status_bar.set_needs_redraw(true);
+        }
+    }
+    // Lets the other panes follow the changes the focused pane made to a buffer they share.
+    fn sync_panes(&mut self) {
+        let focused = self.view_mut();
+        focused.acknowledge_buffer_edits();
+        let focused_needs_redraw = focused.needs_redraw();
+        let (before, rest) = self.panes.split_at_mut(self.focused);
+        let Some((focused, after)) = rest.split_first_mut() else {
+            return;
+        };
+        for pane in before.iter_mut().chain(after.iter_mut()) {
+            pane.view.sync_with_buffer();
+            // Bookmarks are shared too
+            if focused_needs_redraw && pane.view.shares_buffer_with(&focused.view) {
+                pane.view.set_needs_redraw(true);
+            }
+        }
+    }
+    fn handle_window_command(&mut self, command: Window) {
+        match command {
+            Window::SplitHorizontal | Window::SplitVertical => {
+                let direction = if matches!(command, Window::SplitHorizontal) {
+                    Direction::Horizontal
+                } else {
+                    Direction::Vertical
+                };
+                let new_idx = self.panes.len();
+                let view = self.view().split();
+                self.panes.push(Pane {
+                    view,
+                    ..Pane::default()
+                });
+                self.layout.split(self.focused, new_idx, direction);
+                self.arrange_panes();
+                self.focus_pane(new_idx);
+                self.redraw_panes();
+            }
+            Window::Close => self.close_pane(),
+            Window::FocusNext | Window::FocusPrev => {
+                let order = self.pane_order();
+                let Some(pos) = order.iter().position(|idx| *idx == self.focused) else {
+                    return;
+                };
+                let new_pos = if matches!(command, Window::FocusNext) {
+                    pos.saturating_add(1).checked_rem(order.len()).unwrap_or(0)
+                } else {
+                    pos.checked_sub(1).unwrap_or(order.len().saturating_sub(1))
+                };
+                if let Some(idx) = order.get(new_pos) {
+                    self.focus_pane(*idx);
+                }
+            }
+            Window::Grow | Window::Shrink => {
+                if self.layout.resize(self.focused, matches!(command, Window::Grow)) {
+                    self.arrange_panes();
+                    self.redraw_panes();
+                } else {
+                    self.update_message("Can't resize this pane any further.");
+                }
+            }
+        }
+    }
+    fn close_pane(&mut self) {
+        if self.panes.len() == 1 {
+            self.update_message("Can't close the last pane, use Ctrl-W to quit.");
+            return;
+        }
+        // Its buffer stays open in the tab bar
+        let closing = self.focused;
+        // The pane before it in reading order takes over the focus
+        let order = self.pane_order();
+        let next_focus = order
+            .iter()
+            .position(|idx| *idx == closing)
+            .and_then(|pos| order.get(pos.checked_sub(1).unwrap_or(1)))
+            .copied()
+            .unwrap_or(0);
+        self.panes.remove(closing);
+        self.layout.remove(closing);
+        self.arrange_panes();
+        self.focus_pane(if next_focus > closing {
+            next_focus.saturating_sub(1)
+        } else {
+            next_focus
+        });
+        self.redraw_panes();
+    }
+    // endregion
+
+    // region: tabs
+    // The tab of the buffer shown in the focused pane
+    fn active_tab_idx(&self) -> usize {
+        self.buffers
+            .iter()
+            .position(|buffer| buffer.is_same(self.view().buffer()))
+            .unwrap_or(0)
+    }
+    fn show_tab(&mut self, idx: usize) {
+        if let Some(buffer) = self.buffers.get(idx).cloned() {
+            if !buffer.is_same(self.view().buffer()) {
+                self.view_mut().show_buffer(buffer);
+            }
+        }
+    }
+    fn handle_tab_command(&mut self, command: Tab) {
+        let count = self.buffers.len();
+        let active = self.active_tab_idx();
+        match command {
+            Tab::Next => self.show_tab(active.saturating_add(1).checked_rem(count).unwrap_or(0)),
+            Tab::Prev => self.show_tab(active.checked_sub(1).unwrap_or(count.saturating_sub(1))),
+            Tab::Open => self.set_prompt(PromptType::Open),
+        }
+    }
+    fn process_command_during_open(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let file_name = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                if !file_name.is_empty() {
+                    self.open_file(&file_name);
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            _ => {}
+        }
+    }
+    // Shows the file in the focused pane, switching to its tab if it's open already.
+    fn open_file(&mut self, file_name: &str) {
+        let path = Path::new(file_name);
+        if let Some(idx) = self.buffers.iter().position(|buffer| buffer.has_path(path)) {
+            self.show_tab(idx);
+            return;
+        }
+        let Ok(buffer) = BufferHandle::load(file_name) else {
+            self.update_message(&format!("ERR: Could not open file: {file_name}"));
+            return;
+        };
+        Self::load_git_base(&buffer);
+        // An untouched empty buffer, like the one the editor starts with, makes room for the file
+        let current = self.view().buffer();
+        let replaced = current.is_pristine()
+            && !self
+                .panes
+                .iter()
+                .enumerate()
+                .any(|(idx, pane)| idx != self.focused && pane.view.buffer().is_same(current));
+        let pristine_idx = self.active_tab_idx();
+        if replaced {
+            if let Some(slot) = self.buffers.get_mut(pristine_idx) {
+                *slot = buffer.clone();
+            }
+        } else {
+            self.buffers.push(buffer.clone());
+        }
+        self.view_mut().show_buffer(buffer);
+    }
+    // endregion
+
+    // region: diagnostics
+    fn handle_diagnostics_command(&mut self, command: Diagnostics) {
+        match command {
+            Diagnostics::Check => self.run_cargo_check(),
+            Diagnostics::Next | Diagnostics::Prev => {
+                if !self.view_mut().cycle_diagnostics(matches!(command, Diagnostics::Next)) {
+                    self.update_message("No diagnostics.");
+                }
+            }
+        }
+    }
+    // Checks the workspace of the current file in the background, see apply_cargo_check_reports.
+    // Checking again while a check is running cancels it.
+    fn run_cargo_check(&mut self) {
+        if self.cargo_check.take().is_some() {
+            self.update_message("cargo check cancelled.");
+            return;
+        }
+        let Some(path) = self.view().buffer().path() else {
+            self.update_message("Save the file to check it.");
+            return;
+        };
+        match CargoCheck::start(&path) {
+            Ok(check) => {
+                self.cargo_check = Some(check);
+                self.update_message("Running cargo check... (F9 to cancel)");
+            }
+            Err(err) => self.update_message(&format!("ERR: {err}")),
+        }
+    }
+    // Shows the results of the running cargo check in every open buffer once it's done.
+    fn apply_cargo_check_reports(&mut self) {
+        let Some(result) = self.cargo_check.as_mut().and_then(CargoCheck::poll) else {
+            return;
+        };
+        self.cargo_check = None;
+        match result {
+            Ok(reports) => {
+                for buffer in &self.buffers {
+                    buffer.set_diagnostics(&reports);
+                }
+                let count = |severity| reports.iter().filter(|report| report.severity == severity).count();
+                self.update_message(&format!(
+                    "cargo check: {} errors, {} warnings.",
+                    count(Severity::Error),
+                    count(Severity::Warning)
+                ));
+                // The summary stays until the caret moves to another diagnostic
+                self.shown_diagnostic = self.view().diagnostic_at_caret();
+                self.redraw_panes();
+            }
+            Err(err) => self.update_message(&format!("ERR: {err}")),
+        }
+    }
+    // Shows the message of the diagnostic under the caret when it gets there
+    fn show_diagnostic_at_caret(&mut self) {
+        if self.in_prompt() || self.picker.is_visible() || self.completion_popup.is_visible() {
+            return;
+        }
+        let diagnostic = self.view().diagnostic_at_caret();
+        if diagnostic != self.shown_diagnostic {
+            if let Some(message) = &diagnostic {
+                self.update_message(message);
+            }
+            self.shown_diagnostic = diagnostic;
+        }
+    }
+    // endregion
+
+    // region: git changes
+    fn handle_hunk_command(&mut self, command: Hunk) {
+        match command {
+            Hunk::Next | Hunk::Prev => {
+                if !self.view_mut().cycle_hunks(matches!(command, Hunk::Next)) {
+                    self.update_message("No changes since the last commit.");
+                }
+            }
+            Hunk::Revert => {
+                if self.view_mut().revert_hunk_at_caret() {
+                    self.redraw_panes();
+                    self.update_message("Changes reverted.");
+                } else {
+                    self.update_message("No changes here.");
+                }
+            }
+        }
+    }
+    fn load_git_base(buffer: &BufferHandle) {
+        buffer.set_git_base(buffer.path().and_then(|path| git::head_version(&path)));
+    }
+    fn toggle_blame(&mut self) {
+        self.shown_blame = None;
+        if self.view().is_blame_shown() {
+            self.view_mut().hide_blame();
+            self.update_message("Blame hidden.");
+            return;
+        }
+        let Some(path) = self.view().buffer().path() else {
+            self.update_message("Save the file to blame it.");
+            return;
+        };
+        match git::blame(&path) {
+            // The commit of the caret's line shows right after
+            Ok(lines) => self.view_mut().show_blame(lines),
+            Err(err) => self.update_message(&format!("No blame: {err}")),
+        }
+    }
+    // Shows the commit of the caret's line when it gets to another one, unless there's a diagnostic to show
+    fn show_blame_at_caret(&mut self) {
+        if self.in_prompt() || self.picker.is_visible() || self.completion_popup.is_visible() {
+            return;
+        }
+        let summary = self.view_mut().blame_at_caret();
+        if summary != self.shown_blame {
+            if let Some(message) = &summary {
+                if self.shown_diagnostic.is_none() {
+                    self.update_message(message);
+                }
+            }
+            self.shown_blame = summary;
+        }
+    }
+    // endregion
+
+    // region: language servers
+    fn sync_language_servers(&mut self) {
+        if let Some(err) = self.language_servers.sync(&self.buffers) {
+            self.update_message(&format!("ERR: {err}"));
+        }
+    }
+    fn apply_language_server_diagnostics(&mut self) {
+        let reports = self.language_servers.poll();
+        if reports.is_empty() {
+            return;
+        }
+        for (path, reports) in reports {
+            let buffers = self.buffers.iter().filter(|buffer| {
+                buffer.path().and_then(|path| canonicalize(path).ok()).as_ref() == Some(&path)
+            });
+            for buffer in buffers {
+                buffer.set_diagnostics(&reports);
+            }
+        }
+        self.redraw_panes();
+        self.show_diagnostic_at_caret();
+    }
+    fn handle_language_command(&mut self, command: Language) {
+        self.sync_language_servers();
+        let buffer = self.view().buffer().clone();
+        let location = self.view().caret_char_location();
+        let sent = match command {
+            Language::Hover => self.language_servers.hover(&buffer, location),
+            // Without a language server, definitions are looked up in the tags file
+            Language::GotoDefinition if !self.language_servers.handles(buffer.file_type()) => {
+                self.goto_tag();
+                return;
+            }
+            Language::GotoDefinition => self.language_servers.definition(&buffer, location),
+            Language::PopTag => {
+                self.pop_tag_stack();
+                return;
+            }
+            Language::Complete => self.language_servers.completion(&buffer, location),
+            Language::Rename => {
+                self.set_prompt(PromptType::Rename);
+                return;
+            }
+        };
+        self.wait_for_language_server(buffer, location, sent);
+    }
+    // The answer comes in later, see apply_language_server_answer
+    fn wait_for_language_server(&mut self, buffer: BufferHandle, location: (usize, usize), sent: Result<(), String>) {
+        match sent {
+            Ok(()) => {
+                let revision = buffer.revision();
+                self.language_request = Some((buffer, revision, location));
+                self.update_message("Waiting for the language server... (Esc to cancel)");
+            }
+            Err(err) => {
+                self.language_request = None;
+                self.update_message(&format!("ERR: {err}"));
+            }
+        }
+    }
+    fn cancel_language_request(&mut self) {
+        if self.language_request.take().is_some() {
+            self.language_servers.cancel();
+            self.update_message("Cancelled.");
+        }
+    }
+    // Acts on the answer to the last request once it's there. Answers about text which changed or a caret
+    // which moved in the meantime are dropped.
+    fn apply_language_server_answer(&mut self) {
+        if !self.language_servers.is_waiting() {
+            return;
+        }
+        let Some(answer) = self.language_servers.take_answer() else {
+            return;
+        };
+        let Some((buffer, revision, location)) = self.language_request.take() else {
+            return;
+        };
+        if !buffer.is_same(self.view().buffer())
+            || buffer.revision() != revision
+            || self.view().caret_char_location() != location
+        {
+            self.update_message("");
+            return;
+        }
+        match answer {
+            Ok(Answer::Hover(Some(text))) => {
+                self.update_message("");
+                let lines = text.lines().map(String::from).collect();
+                self.show_picker(PickerType::Hover, "Hover (Esc to close)", lines);
+            }
+            Ok(Answer::Hover(None)) => self.update_message("Nothing to show here."),
+            Ok(Answer::Definition(Some((path, location)))) => {
+                self.update_message("");
+                self.push_tag_stack();
+                if self.show_file(&path) {
+                    self.view_mut().goto_char_location(location);
+                }
+            }
+            Ok(Answer::Definition(None)) => self.update_message("No definition found."),
+            Ok(Answer::Completion(items)) => {
+                self.language_completions = Some(items);
+                let prefix = self.view().completion_prefix();
+                let labels = self.language_completion_labels(&prefix);
+                if labels.is_empty() {
+                    self.language_completions = None;
+                    self.update_message("No completion found.");
+                } else {
+                    self.update_message("");
+                    self.show_completion_popup(&prefix, labels);
+                }
+            }
+            Ok(Answer::Rename(file_edits)) => self.apply_rename(file_edits),
+            Err(err) => self.update_message(&format!("ERR: {err}")),
+        }
+    }
+    // The labels of the language server completions matching the prefix, ignoring case like the server does
+    fn language_completion_labels(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        self.language_completions
+            .iter()
+            .flatten()
+            .filter(|item| item.filter_text.to_lowercase().starts_with(&prefix))
+            .map(|item| item.label.clone())
+            .collect()
+    }
+    fn process_command_during_rename(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let new_name = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                if !new_name.is_empty() {
+                    self.rename(&new_name);
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            _ => {}
+        }
+    }
+    // Renames the symbol under the caret everywhere the server knows of, see apply_rename.
+    fn rename(&mut self, new_name: &str) {
+        let buffer = self.view().buffer().clone();
+        let location = self.view().caret_char_location();
+        let sent = self.language_servers.rename(&buffer, location, new_name);
+        self.wait_for_language_server(buffer, location, sent);
+    }
+    // Applies the edits of a rename, opening the files the symbol is used in. All of them are opened before
+    // any is edited, so that a file which can't be leaves the others as they were.
+    fn apply_rename(&mut self, file_edits: Vec<(PathBuf, Vec<TextEdit>)>) {
+        let mut buffer_edits = Vec::with_capacity(file_edits.len());
+        for (path, edits) in file_edits {
+            match self.buffer_for(&path) {
+                Ok(buffer) => buffer_edits.push((buffer, edits)),
+                Err(err) => {
+                    self.update_message(&format!("ERR: {}: {err}", path.display()));
+                    return;
+                }
+            }
+        }
+        let mut edit_count: usize = 0;
+        for (buffer, edits) in &buffer_edits {
+            buffer.apply_text_edits(edits);
+            edit_count = edit_count.saturating_add(edits.len());
+        }
+        let file_count = buffer_edits.len();
+        // The carets have to follow the edits, even though they weren't made by typing. Panes on other
+        // buffers are left as they are.
+        for pane in &mut self.panes {
+            pane.view.sync_with_buffer();
+        }
+        self.redraw_panes();
+        if edit_count == 0 {
+            self.update_message("Nothing to rename here.");
+        } else {
+            self.update_message(&format!("Renamed {edit_count} occurrences in {file_count} files."));
+        }
+    }
+    // The open buffer of the file, or a new tab with it
+    fn buffer_for(&mut self, path: &Path) -> Result<BufferHandle, Error> {
+        let open = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.path().and_then(|path| canonicalize(path).ok()).as_deref() == Some(path));
+        if let Some(buffer) = open {
+            return Ok(buffer.clone());
+        }
+        let buffer = BufferHandle::load(&path.to_string_lossy())?;
+        Self::load_git_base(&buffer);
+        self.buffers.push(buffer.clone());
+        Ok(buffer)
+    }
+    // Shows the file in the focused pane, unless it's shown already. Returns false if it couldn't be opened.
+    fn show_file(&mut self, path: &Path) -> bool {
+        let is_shown = self
+            .view()
+            .buffer()
+            .path()
+            .and_then(|path| canonicalize(path).ok())
+            .as_deref()
+            == Some(path);
+        if is_shown {
+            return true;
+        }
+        match self.buffer_for(path) {
+            Ok(buffer) => {
+                self.view_mut().show_buffer(buffer);
+                true
+            }
+            Err(err) => {
+                self.update_message(&format!("ERR: {}: {err}", path.display()));
+                false
+            }
+        }
+    }
+    // endregion
+
+    // region: tags
+    // Jumps to the definition of the identifier under the caret, letting the user choose if there are several.
+    fn goto_tag(&mut self) {
+        let Some(name) = self.view().word_at_caret() else {
+            self.update_message("No identifier under the caret.");
+            return;
+        };
+        let tags = match Tag::find(self.view().buffer().path().as_deref(), &name) {
+            Ok(tags) => tags,
+            Err(err) => {
+                self.update_message(&err);
+                return;
+            }
+        };
+        match tags.as_slice() {
+            [] => self.update_message(&format!("No tag found for '{name}'.")),
+            [tag] => self.jump_to_tag(&tag.clone()),
+            _ => {
+                let items = tags.iter().map(ToString::to_string).collect();
+                self.tag_matches = tags;
+                self.show_picker(
+                    PickerType::Tags,
+                    &format!("Definitions of {name} (Enter to jump, Esc to close)"),
+                    items,
+                );
+            }
+        }
+    }
+    fn jump_to_tag(&mut self, tag: &Tag) {
+        let path = canonicalize(&tag.path).unwrap_or_else(|_| tag.path.clone());
+        self.push_tag_stack();
+        if self.show_file(&path) {
+            let location = tag.locate(&self.view().buffer().line_strings());
+            self.view_mut().goto_char_location(location);
+        }
+    }
+    // Remembers the caret location before a definition jump
+    fn push_tag_stack(&mut self) {
+        let entry = (self.view().buffer().clone(), self.view().caret_char_location());
+        self.tag_stack.push(entry);
+        if self.tag_stack.len() > MAX_TAG_STACK {
+            self.tag_stack.remove(0);
+        }
+    }
+    fn pop_tag_stack(&mut self) {
+        let Some((buffer, location)) = self.tag_stack.pop() else {
+            self.update_message("Tag stack is empty.");
+            return;
+        };
+        if !self.view().buffer().is_same(&buffer) {
+            self.view_mut().show_buffer(buffer);
+        }
+        self.view_mut().goto_char_location(location);
+    }
+    // endregion
+
+    // region: picker
+    fn show_picker(&mut self, picker_type: PickerType, title: &str, items: Vec<String>) {
+        self.picker.show(title, items);
+        self.picker_type = picker_type;
+    }
+    fn close_picker(&mut self) {
+        self.picker.hide();
+        self.picker_type = PickerType::None;
+        self.redraw_panes();
+    }
+    fn process_command_during_picker(&mut self, command: Command) {
+        let page = self.picker.visible_item_count();
+        match command {
+            Move(Down) | Command::Mouse(Mouse::ScrollDown) => self.picker.select_next(1),
+            Move(Up) | Command::Mouse(Mouse::ScrollUp) => self.picker.select_prev(1),
+            Move(command::Move::PageDown) => self.picker.select_next(page),
+            Move(command::Move::PageUp) => self.picker.select_prev(page),
+            Edit(InsertNewline) => {
+                let idx = self.picker.selected_idx();
+                match self.picker_type {
+                    PickerType::Bookmarks => self.view_mut().jump_to_bookmark_at(idx),
+                    PickerType::Tags => {
+                        if let Some(tag) = self.tag_matches.get(idx).cloned() {
+                            self.jump_to_tag(&tag);
+                        }
+                    }
+                    PickerType::Hover | PickerType::None => {}
+                }
+                self.close_picker();
+            }
+            System(Dismiss) => self.close_picker(),
+            _ => {}
+        }
+    }
+    // endregion
+
+    // region: completion
+    fn start_completion(&mut self) {
+        let prefix = self.view().completion_prefix();
+        if prefix.is_empty() {
+            self.update_message("Nothing to complete.");
+            return;
+        }
+        let mut words = self.rank_completion_words(&prefix);
+        if words.len() == 1 {
+            // A single candidate doesn't need a choice
+            if let Some(word) = words.pop() {
+                self.view_mut().complete_word(&prefix, &word);
+            }
+        } else if words.is_empty() {
+            self.update_message(&format!("No completion found for '{prefix}'."));
+        } else {
+            self.show_completion_popup(&prefix, words);
+        }
+    }
+    // Refreshes the candidates after the prefix changed, closing the popup if there are none left.
+    fn update_completion(&mut self) {
+        let prefix = self.view().completion_prefix();
+        let words = if self.language_completions.is_some() {
+            self.language_completion_labels(&prefix)
+        } else if prefix.is_empty() {
+            Vec::new()
+        } else {
+            self.rank_completion_words(&prefix)
+        };
+        if words.is_empty() {
+            self.close_completion();
+        } else {
+            self.show_completion_popup(&prefix, words);
+        }
+    }
+    fn rank_completion_words(&self, prefix: &str) -> Vec<String> {
+        let mut ranking = WordRanking::new(prefix);
+        let current = self.view().buffer();
+        self.view().add_completion_words(&mut ranking);
+        for buffer in self.buffers.iter().filter(|buffer| !buffer.is_same(current)) {
+            buffer.add_completion_words(&mut ranking);
+        }
+        ranking.into_ranked()
+    }
+    fn show_completion_popup(&mut self, prefix: &str, words: Vec<String>) {
+        let caret = self.caret_position();
+        let anchor = Position {
+            row: caret.row,
+            col: caret.col.saturating_sub(Line::from(prefix).width()),
+        };
+        self.completion_popup.show(words, anchor);
+    }
+    fn close_completion(&mut self) {
+        self.completion_popup.hide();
+        self.language_completions = None;
+        self.redraw_panes();
+    }
+    fn accept_completion(&mut self) {
+        let prefix = self.view().completion_prefix();
+        if let Some(word) = self.completion_popup.selected_word().map(String::from) {
+            let item = self
+                .language_completions
+                .as_ref()
+                .and_then(|items| items.iter().find(|item| item.label == word).cloned());
+            if let Some(item) = item {
+                self.view_mut().complete_with(&prefix, &item.text, item.is_snippet);
+            } else {
+                self.view_mut().complete_word(&prefix, &word);
+            }
+        }
+        self.close_completion();
+    }
+    fn process_command_during_completion(&mut self, command: Command) {
+        match command {
+            Move(Down) => self.completion_popup.select_next(),
+            Move(Up) => self.completion_popup.select_prev(),
+            Edit(InsertNewline | Insert('\t')) => self.accept_completion(),
+            System(Dismiss) => self.close_completion(),
+            Edit(Insert(character)) if WordRanking::is_word_char(character) => {
+                self.view_mut().handle_edit_command(Insert(character));
+                self.update_completion();
+            }
+            Edit(DeleteBackward) => {
+                self.view_mut().handle_edit_command(DeleteBackward);
+                self.update_completion();
+            }
+            // Anything else ends the completion and is processed as usual
+            _ => {
+                self.close_completion();
+                self.process_command(command);
             }
-            Move(Right | Down) => self.view.search_next(),
-            Move(Up | Left) => self.view.search_prev(),
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {} // Not applicable during save, Resize already handled at this stage
         }
     }
     // endregion
@@ -325,8 +1511,13 @@ impl Editor {
         match prompt_type {
             PromptType::None => self.message_bar.set_needs_redraw(true), // Prompt closed, needs to redraw
             PromptType::Save => self.command_bar.set_prompt("Enregistrer sous : "),
+            PromptType::Open => self.command_bar.set_prompt("Ouvrir : "),
+            PromptType::GotoLine => self.command_bar.set_prompt("Aller a la ligne (N, N:C, +N, -N, N%) : "),
+            PromptType::SetBookmark => self.command_bar.set_prompt("Nom du signet (a-z) : "),
+            PromptType::JumpToBookmark => self.command_bar.set_prompt("Aller au signet (a-z) : "),
+            PromptType::Rename => self.command_bar.set_prompt("Renommer en : "),
             PromptType::Search => {
-                self.view.enter_search();
+                self.view_mut().enter_search();
                 self.command_bar.set_prompt("Rechercher (Esc pour annuler, fleches pour naviguer) : ");
             }
         }
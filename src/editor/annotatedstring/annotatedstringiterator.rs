@@ -15,31 +15,16 @@ impl<'a> Iterator for AnnotatedStringIterator<'a> {
             return None;
         }
 
-        // Find the current active location
-        if let Some(annotation) = self
-            .annotated_string
-            .annotations
-            .iter()
-            .filter(|annotation| {
-                annotation.start_byte_idx <= self.current_idx && annotation.end_byte_idx > self.current_idx
-            })
-            .last() // override overlapping annotations
-            {
-                let end_idx = min(annotation.end_byte_idx, self.annotated_string.string.len());
-                let start_idx = self.current_idx;
-                self.current_idx = end_idx;
-
-                return Some(AnnotatedStringPart {
-                    string: &self.annotated_string.string[start_idx..end_idx],
-                    annotation_type: Some(annotation.annotation_type),
-                });
-            }
-
-        // Find the boundary of the nearest annotation
+        // The part goes on until any annotation starts or ends, so the same annotations cover all of it.
+        // They stack in the order they were added: the last one added ends up on top.
         let mut end_idx = self.annotated_string.string.len();
+        let mut annotation_types = Vec::new();
         for annotation in &self.annotated_string.annotations {
-            if annotation.start_byte_idx > self.current_idx && annotation.start_byte_idx < end_idx {
-                end_idx = annotation.start_byte_idx;
+            if annotation.start_byte_idx > self.current_idx {
+                end_idx = min(end_idx, annotation.start_byte_idx);
+            } else if annotation.end_byte_idx > self.current_idx {
+                end_idx = min(end_idx, annotation.end_byte_idx);
+                annotation_types.push(annotation.annotation_type);
             }
         }
         let start_idx = self.current_idx;
@@ -47,7 +32,7 @@ impl<'a> Iterator for AnnotatedStringIterator<'a> {
 
         Some(AnnotatedStringPart {
             string: &self.annotated_string.string[start_idx..end_idx],
-            annotation_type: None,
+            annotation_types,
         })
     }
-}
\ No newline at end of file
+}
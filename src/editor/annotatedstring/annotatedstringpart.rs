@@ -3,5 +3,6 @@ use super::AnnotationType;
 #[derive(Debug)]
 pub struct AnnotatedStringPart<'a> {
     pub string: &'a str,
-    pub annotation_type: Option<AnnotationType>,
+    // Every annotation covering the part, from the one beneath the others to the topmost
+    pub annotation_types: Vec<AnnotationType>,
 }
@@ -1,5 +1,40 @@
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum AnnotationType {
     Match,
     SelectedMatch,
+    Selection,
+    LineNumber,
+    FoldSummary,
+    // Visible tabs, spaces and line ends
+    Whitespace,
+    TrailingWhitespace,
+    // Syntax elements
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Punctuation,
+    // Diagnostics, underlining the code they're about
+    Error,
+    Warning,
+    Info,
+    // Lines changed since the last commit, in the gutter
+    GitAdded,
+    GitModified,
+    GitDeleted,
+    // Background tints, beneath everything else
+    CurrentLine,
+    Ruler,
+    // Parts of the user interface around the text
+    Gutter,
+    StatusBar,
+    InactiveStatusBar,
+    MessageBar,
+    TabBar,
+    ActiveTab,
+    Popup,
+    PopupSelection,
+    PopupTitle,
+    PaneSeparator,
 }
@@ -1,8 +1,12 @@
 use std::{
     cmp::{max, min},
     fmt::{self, Display},
+    ops::Range,
 };
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 pub mod annotationtype;
 pub use annotationtype::AnnotationType;
 mod annotation;
@@ -35,6 +39,45 @@ impl AnnotatedString {
         });
     }
 
+    // Adds an annotation which every other annotation stacks on top of, see AnnotatedStringIterator.
+    pub fn add_annotation_beneath(&mut self, annotation_type: AnnotationType, start_byte_idx: usize, end_byte_idx: usize) {
+        debug_assert!(start_byte_idx <= end_byte_idx);
+        self.annotations.insert(
+            0,
+            Annotation {
+                annotation_type,
+                start_byte_idx,
+                end_byte_idx,
+            },
+        );
+    }
+
+    // Appends spaces until the string covers the given number of columns.
+    pub fn pad_to_width(&mut self, width: usize) {
+        let padding = width.saturating_sub(self.string.width());
+        self.string.push_str(&" ".repeat(padding));
+    }
+
+    // Returns the bytes of the grapheme covering the given column, if any.
+    pub fn byte_range_at_col(&self, col: usize) -> Option<Range<usize>> {
+        let mut current_col: usize = 0;
+        for (byte_idx, grapheme) in self.string.grapheme_indices(true) {
+            let next_col = current_col.saturating_add(grapheme.width());
+            if col < next_col {
+                return Some(byte_idx..byte_idx.saturating_add(grapheme.len()));
+            }
+            current_col = next_col;
+        }
+        None
+    }
+
+    // Appends the string, annotated as a whole.
+    pub fn push_annotated(&mut self, string: &str, annotation_type: AnnotationType) {
+        let start_byte_idx = self.string.len();
+        self.string.push_str(string);
+        self.add_annotation(annotation_type, start_byte_idx, self.string.len());
+    }
+
     pub fn replace(&mut self, start_byte_idx: usize, end_byte_idx: usize, new_string: &str) {
         debug_assert!(start_byte_idx <= end_byte_idx);
 
@@ -84,7 +127,7 @@ impl AnnotatedString {
                 } else {
                     annotation.end_byte_idx.saturating_add(len_difference)
                 }
-            } else if annotation.end_byte_idx >= start_byte_idx {
+            } else if annotation.end_byte_idx > start_byte_idx {
                 // For annotations ending within the replaced range, we move the end index by the difference in length, constrained to the beginning or end of the replaced range.
                 if shortened {
                     max(
@@ -0,0 +1,140 @@
+use std::{
+    fs::canonicalize,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{channel, Receiver, TryRecvError},
+    thread,
+};
+
+use super::json::JsonValue;
+use super::uicomponents::{DiagnosticReport, Severity};
+
+// A `cargo check` of the workspace a file belongs to, running in the background. A thread collects what
+// cargo prints, so that the editor keeps responding; poll hands over the reports once cargo is done.
+pub struct CargoCheck {
+    process: Child,
+    root: PathBuf,
+    output: Receiver<String>,
+}
+
+impl CargoCheck {
+    pub fn start(file: &Path) -> Result<Self, String> {
+        let root = workspace_root(file)?;
+        let mut process = Command::new("cargo")
+            .args(["check", "--message-format=json"])
+            .current_dir(&root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("could not run cargo: {err}"))?;
+        let Some(mut stdout) = process.stdout.take() else {
+            let _ = process.kill();
+            return Err(String::from("could not read from cargo"));
+        };
+        let (sender, output) = channel();
+        thread::spawn(move || {
+            let mut text = String::new();
+            // Whatever was read is still worth reporting
+            let _ = stdout.read_to_string(&mut text);
+            let _ = sender.send(text);
+        });
+        Ok(Self { process, root, output })
+    }
+
+    // Returns what the compiler reported once cargo is done, None while it's still running.
+    pub fn poll(&mut self) -> Option<Result<Vec<DiagnosticReport>, String>> {
+        let result = match self.output.try_recv() {
+            Ok(text) => Ok(parse_output(&text, &self.root)),
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(String::from("cargo check stopped")),
+        };
+        let _ = self.process.wait();
+        Some(result)
+    }
+}
+
+// Dropping a check cancels it
+impl Drop for CargoCheck {
+    fn drop(&mut self) {
+        if matches!(self.process.try_wait(), Ok(None)) {
+            let _ = self.process.kill();
+        }
+        let _ = self.process.wait();
+    }
+}
+
+// Cargo fails when the code doesn't compile, but still reports why
+fn parse_output(stdout: &str, root: &Path) -> Vec<DiagnosticReport> {
+    let mut reports: Vec<DiagnosticReport> = Vec::new();
+    for line in stdout.lines() {
+        let Ok(message) = JsonValue::parse(line) else {
+            continue;
+        };
+        if message.get("reason").and_then(JsonValue::as_str) != Some("compiler-message") {
+            continue;
+        }
+        if let Some(report) = message.get("message").and_then(|message| parse_diagnostic(message, root)) {
+            // Diagnostics show up once per target the file is part of
+            let is_duplicate = reports.iter().any(|other| {
+                other.path == report.path && other.start == report.start && other.message == report.message
+            });
+            if !is_duplicate {
+                reports.push(report);
+            }
+        }
+    }
+    reports
+}
+
+// The directory of the workspace's Cargo.toml, which the compiler reports paths relative to
+fn workspace_root(file: &Path) -> Result<PathBuf, String> {
+    let file = canonicalize(file).map_err(|err| format!("{}: {err}", file.display()))?;
+    let dir = file.parent().unwrap_or(&file);
+    let output = Command::new("cargo")
+        .args(["locate-project", "--workspace", "--message-format", "plain"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| format!("could not run cargo: {err}"))?;
+    if !output.status.success() {
+        return Err(format!("{} is not part of a cargo workspace", file.display()));
+    }
+    let manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    manifest
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| String::from("cargo returned no workspace"))
+}
+
+// Turns a compiler message into a report at its primary span. Messages without one, like the
+// summary at the end, are left out.
+fn parse_diagnostic(message: &JsonValue, root: &Path) -> Option<DiagnosticReport> {
+    let severity = match message.get("level")?.as_str()? {
+        "error" | "error: internal compiler error" => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => Severity::Info,
+    };
+    let span = message
+        .get("spans")?
+        .as_array()
+        .iter()
+        .find(|span| span.get("is_primary").and_then(JsonValue::as_bool) == Some(true))?;
+    let path = root.join(span.get("file_name")?.as_str()?);
+    // Lines and columns count from 1
+    let position = |line_key: &str, column_key: &str| -> Option<(usize, usize)> {
+        Some((
+            span.get(line_key)?.as_usize()?.saturating_sub(1),
+            span.get(column_key)?.as_usize()?.saturating_sub(1),
+        ))
+    };
+    Some(DiagnosticReport {
+        path: canonicalize(&path).unwrap_or(path),
+        severity,
+        message: message.get("message")?.as_str()?.to_string(),
+        start: position("line_start", "column_start")?,
+        end: position("line_end", "column_end")?,
+    })
+}
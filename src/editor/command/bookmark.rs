@@ -0,0 +1,35 @@
+use crossterm::event::{
+    KeyCode::{Char, F},
+    KeyEvent, KeyModifiers,
+};
+
+#[derive(Clone, Copy)]
+pub enum Bookmark {
+    // Adds or removes an anonymous bookmark on the caret line
+    Toggle,
+    // Prompts for a letter to name the bookmark at the caret
+    SetNamed,
+    // Prompts for the letter of the bookmark to jump to
+    JumpToNamed,
+    Next,
+    Prev,
+    List,
+}
+
+impl TryFrom<KeyEvent> for Bookmark {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('b'), KeyModifiers::CONTROL) => Ok(Self::Toggle),
+            (Char('b'), KeyModifiers::ALT) => Ok(Self::SetNamed),
+            (Char('j'), KeyModifiers::ALT) => Ok(Self::JumpToNamed),
+            (Char('k'), KeyModifiers::ALT) => Ok(Self::List),
+            (F(2), KeyModifiers::NONE) => Ok(Self::Next),
+            (F(2), KeyModifiers::SHIFT) => Ok(Self::Prev),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crossterm::event::{KeyCode::F, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy)]
+pub enum Diagnostics {
+    // Runs cargo check and shows what it reports
+    Check,
+    Next,
+    Prev,
+}
+
+impl TryFrom<KeyEvent> for Diagnostics {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (F(9), KeyModifiers::NONE) => Ok(Self::Check),
+            (F(8), KeyModifiers::NONE) => Ok(Self::Next),
+            (F(8), KeyModifiers::SHIFT) => Ok(Self::Prev),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crossterm::event::{
+    KeyCode::{Char, F},
+    KeyEvent, KeyModifiers,
+};
+
+// Changes to how the text is displayed, which leave the text itself alone
+#[derive(Clone, Copy)]
+pub enum Display {
+    ToggleSoftWrap,
+    // Off, absolute, relative
+    CycleLineNumbers,
+    ToggleWhitespace,
+    // Switches to the next of the available themes
+    CycleTheme,
+    // Shows who last changed each line next to it, see git::blame
+    ToggleBlame,
+}
+
+impl TryFrom<KeyEvent> for Display {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('z'), KeyModifiers::ALT) => Ok(Self::ToggleSoftWrap),
+            (Char('n'), KeyModifiers::ALT) => Ok(Self::CycleLineNumbers),
+            (Char('w'), KeyModifiers::ALT) => Ok(Self::ToggleWhitespace),
+            (F(7), KeyModifiers::NONE) => Ok(Self::CycleTheme),
+            (Char('g'), KeyModifiers::ALT) => Ok(Self::ToggleBlame),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crossterm::event::{
-    KeyCode::{Backspace, Char, Delete, Enter, Tab},
+    KeyCode::{BackTab, Backspace, Char, Delete, Enter, Tab},
     KeyEvent, KeyModifiers,
 };
 #[derive(Clone, Copy)]
@@ -8,6 +8,14 @@ pub enum Edit {
     InsertNewline,
     Delete,
     DeleteBackward,
+    BackTab,
+    // Adds the value to the number at or after the caret, or to the first number of each selected line
+    Increment(i64),
+    // Adds the value to the first number of the first selected line, twice the value to the next line, and so on
+    IncrementSequence(i64),
+    // Shifts the caret line, or the selected lines, by one indentation level
+    Indent,
+    Outdent,
 }
 impl TryFrom<KeyEvent> for Edit {
     type Error = String;
@@ -18,9 +26,16 @@ impl TryFrom<KeyEvent> for Edit {
                 Ok(Self::Insert(character))
             }
             (Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
+            (BackTab, KeyModifiers::NONE | KeyModifiers::SHIFT) => Ok(Self::BackTab),
             (Enter, KeyModifiers::NONE) => Ok(Self::InsertNewline),
             (Backspace, KeyModifiers::NONE) => Ok(Self::DeleteBackward),
             (Delete, KeyModifiers::NONE) => Ok(Self::Delete),
+            (Char('a'), KeyModifiers::CONTROL) => Ok(Self::Increment(1)),
+            (Char('x'), KeyModifiers::CONTROL) => Ok(Self::Increment(-1)),
+            (Char('a'), KeyModifiers::ALT) => Ok(Self::IncrementSequence(1)),
+            (Char('x'), KeyModifiers::ALT) => Ok(Self::IncrementSequence(-1)),
+            (Char('>'), modifiers) if modifiers.contains(KeyModifiers::ALT) => Ok(Self::Indent),
+            (Char('<'), modifiers) if modifiers.contains(KeyModifiers::ALT) => Ok(Self::Outdent),
             _ => Err(format!(
                 "Unsupported key code {:?} with modifiers {:?}",
                 event.code, event.modifiers
@@ -0,0 +1,25 @@
+use crossterm::event::{KeyCode::Char, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy)]
+pub enum Fold {
+    // Folds the region around the caret
+    Close,
+    // Unfolds the folds on the caret line
+    Open,
+    ToggleAll,
+}
+
+impl TryFrom<KeyEvent> for Fold {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('f'), KeyModifiers::ALT) => Ok(Self::Close),
+            (Char('u'), KeyModifiers::ALT) => Ok(Self::Open),
+            (Char('t'), KeyModifiers::ALT) => Ok(Self::ToggleAll),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
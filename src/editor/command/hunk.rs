@@ -0,0 +1,25 @@
+use crossterm::event::{KeyCode::Char, KeyEvent, KeyModifiers};
+
+// The lines changed since the last commit, see GitDiff
+#[derive(Clone, Copy)]
+pub enum Hunk {
+    Next,
+    Prev,
+    // Restores the changed lines under the caret as they are in the last commit
+    Revert,
+}
+
+impl TryFrom<KeyEvent> for Hunk {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('.'), KeyModifiers::ALT) => Ok(Self::Next),
+            (Char(','), KeyModifiers::ALT) => Ok(Self::Prev),
+            (Char('r'), KeyModifiers::ALT) => Ok(Self::Revert),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crossterm::event::{
+    KeyCode::{Left, Right},
+    KeyEvent, KeyModifiers,
+};
+
+// Travels through the jump list, i.e. the locations the caret jumped away from
+#[derive(Clone, Copy)]
+pub enum Jump {
+    Back,
+    Forward,
+}
+
+impl TryFrom<KeyEvent> for Jump {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Left, KeyModifiers::ALT) => Ok(Self::Back),
+            (Right, KeyModifiers::ALT) => Ok(Self::Forward),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
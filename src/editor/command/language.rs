@@ -0,0 +1,33 @@
+use crossterm::event::{
+    KeyCode::{Char, F},
+    KeyEvent, KeyModifiers,
+};
+
+// Code navigation and requests to the language server of the file, see LanguageServers
+#[derive(Clone, Copy)]
+pub enum Language {
+    Hover,
+    // Asks the language server, or looks the identifier up in the tags file if there is no server
+    GotoDefinition,
+    // Goes back to where the last definition jump started
+    PopTag,
+    Complete,
+    Rename,
+}
+
+impl TryFrom<KeyEvent> for Language {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('k'), KeyModifiers::CONTROL) => Ok(Self::Hover),
+            (F(12), KeyModifiers::NONE) => Ok(Self::GotoDefinition),
+            (F(12), KeyModifiers::SHIFT) => Ok(Self::PopTag),
+            (Char(' '), KeyModifiers::CONTROL) => Ok(Self::Complete),
+            (Char('r'), KeyModifiers::CONTROL) => Ok(Self::Rename),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -7,14 +7,47 @@ mod system;
 pub use system::System;
 mod edit;
 pub use edit::Edit;
+mod select;
+pub use select::Select;
+mod bookmark;
+pub use bookmark::Bookmark;
+mod jump;
+pub use jump::Jump;
+mod mouse;
+pub use mouse::Mouse;
+mod display;
+pub use display::Display;
+mod fold;
+pub use fold::Fold;
+mod window;
+pub use window::Window;
+mod tab;
+pub use tab::Tab;
+mod diagnostics;
+pub use diagnostics::Diagnostics;
+mod language;
+pub use language::Language;
+mod hunk;
+pub use hunk::Hunk;
 
 use super::Size;
 
 #[derive(Clone, Copy)]
 pub enum Command {
     Move(Move),
+    Select(Select),
     Edit(Edit),
     System(System),
+    Bookmark(Bookmark),
+    Jump(Jump),
+    Mouse(Mouse),
+    Display(Display),
+    Fold(Fold),
+    Window(Window),
+    Tab(Tab),
+    Diagnostics(Diagnostics),
+    Language(Language),
+    Hunk(Hunk),
 }
 
 // clippy::as_conversions: Will run into problems for rare edge case systems where usize < u16
@@ -26,8 +59,19 @@ impl TryFrom<Event> for Command {
             Event::Key(key_event) => Edit::try_from(key_event)
                 .map(Command::Edit)
                 .or_else(|_| Move::try_from(key_event).map(Command::Move))
+                .or_else(|_| Select::try_from(key_event).map(Command::Select))
                 .or_else(|_| System::try_from(key_event).map(Command::System))
+                .or_else(|_| Bookmark::try_from(key_event).map(Command::Bookmark))
+                .or_else(|_| Jump::try_from(key_event).map(Command::Jump))
+                .or_else(|_| Display::try_from(key_event).map(Command::Display))
+                .or_else(|_| Fold::try_from(key_event).map(Command::Fold))
+                .or_else(|_| Window::try_from(key_event).map(Command::Window))
+                .or_else(|_| Tab::try_from(key_event).map(Command::Tab))
+                .or_else(|_| Diagnostics::try_from(key_event).map(Command::Diagnostics))
+                .or_else(|_| Language::try_from(key_event).map(Command::Language))
+                .or_else(|_| Hunk::try_from(key_event).map(Command::Hunk))
                 .map_err(|_err| format!("Event not supported: {key_event:?}")),
+            Event::Mouse(mouse_event) => Mouse::try_from(mouse_event).map(Command::Mouse),
             Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
                 height: height_u16 as usize,
                 width: width_u16 as usize,
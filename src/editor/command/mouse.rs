@@ -0,0 +1,36 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use super::super::Position;
+
+// Mouse actions, with positions relative to the whole terminal
+#[derive(Clone, Copy)]
+pub enum Mouse {
+    // Left button pressed. With Shift held, the selection is extended up to the clicked position
+    Click { position: Position, extend: bool },
+    // Mouse moved with the left button held down
+    Drag(Position),
+    ScrollUp,
+    ScrollDown,
+}
+
+// clippy::as_conversions: Will run into problems for rare edge case systems where usize < u16
+#[allow(clippy::as_conversions)]
+impl TryFrom<MouseEvent> for Mouse {
+    type Error = String;
+    fn try_from(event: MouseEvent) -> Result<Self, Self::Error> {
+        let position = Position {
+            col: event.column as usize,
+            row: event.row as usize,
+        };
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => Ok(Self::Click {
+                position,
+                extend: event.modifiers.contains(KeyModifiers::SHIFT),
+            }),
+            MouseEventKind::Drag(MouseButton::Left) => Ok(Self::Drag(position)),
+            MouseEventKind::ScrollUp => Ok(Self::ScrollUp),
+            MouseEventKind::ScrollDown => Ok(Self::ScrollDown),
+            kind => Err(format!("Unsupported mouse event {kind:?}")),
+        }
+    }
+}
@@ -12,6 +12,15 @@ pub enum Move {
     Left,
     Right,
     Down,
+    StartOfDocument,
+    EndOfDocument,
+    // First, middle and last line visible on screen
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    // Scroll the view by one line, the caret only moves if it would leave the screen
+    ScrollUp,
+    ScrollDown,
 }
 impl TryFrom<KeyEvent> for Move {
     type Error = String;
@@ -32,6 +41,21 @@ impl TryFrom<KeyEvent> for Move {
                 End => Ok(Self::EndOfLine),
                 _ => Err(format!("Unsupported code: {code:?}")),
             }
+        } else if modifiers == KeyModifiers::CONTROL {
+            match code {
+                Home => Ok(Self::StartOfDocument),
+                End => Ok(Self::EndOfDocument),
+                Up => Ok(Self::ScrollUp),
+                Down => Ok(Self::ScrollDown),
+                _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                PageUp => Ok(Self::ScreenTop),
+                Home => Ok(Self::ScreenMiddle),
+                PageDown => Ok(Self::ScreenBottom),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
         } else {
             Err(format!(
                 "Unsupported key code {code:?} or modifier {modifiers:?}"
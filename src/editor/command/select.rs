@@ -0,0 +1,35 @@
+use crossterm::event::{KeyEvent, KeyModifiers};
+
+use super::Move;
+
+// Moves which extend the selection instead of replacing it
+#[derive(Clone, Copy)]
+pub enum Select {
+    // Shift + movement key
+    Linear(Move),
+    // Alt + Shift + movement key
+    Block(Move),
+}
+
+impl TryFrom<KeyEvent> for Select {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        let unmodified = KeyEvent::new(code, KeyModifiers::NONE);
+
+        if modifiers == KeyModifiers::SHIFT {
+            Move::try_from(unmodified).map(Self::Linear)
+        } else if modifiers == KeyModifiers::SHIFT | KeyModifiers::CONTROL {
+            // e.g. Shift+Ctrl+End selects up to the end of the document
+            Move::try_from(KeyEvent::new(code, KeyModifiers::CONTROL)).map(Self::Linear)
+        } else if modifiers == KeyModifiers::SHIFT | KeyModifiers::ALT {
+            Move::try_from(unmodified).map(Self::Block)
+        } else {
+            Err(format!(
+                "Unsupported key code {code:?} or modifier {modifiers:?}"
+            ))
+        }
+    }
+}
@@ -9,6 +9,8 @@ pub enum System {
     Quit,
     Dismiss,
     Search,
+    Complete,
+    GotoLine,
 }
 
 impl TryFrom<KeyEvent> for System {
@@ -23,6 +25,8 @@ impl TryFrom<KeyEvent> for System {
                 Char('w') => Ok(Self::Quit),
                 Char('s') => Ok(Self::Save),
                 Char('t') => Ok(Self::Search),
+                Char('n') => Ok(Self::Complete),
+                Char('g') => Ok(Self::GotoLine),
                 _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
             }
         } else if modifiers == KeyModifiers::NONE && matches!(code, KeyCode::Esc) {
@@ -0,0 +1,28 @@
+use crossterm::event::{
+    KeyCode::{Char, PageDown, PageUp},
+    KeyEvent, KeyModifiers,
+};
+
+// Switches between the open buffers shown in the tab bar
+#[derive(Clone, Copy)]
+pub enum Tab {
+    Next,
+    Prev,
+    // Asks for a file to open in a new tab
+    Open,
+}
+
+impl TryFrom<KeyEvent> for Tab {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (PageDown, KeyModifiers::CONTROL) => Ok(Self::Next),
+            (PageUp, KeyModifiers::CONTROL) => Ok(Self::Prev),
+            (Char('o'), KeyModifiers::CONTROL) => Ok(Self::Open),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crossterm::event::{
+    KeyCode::{Char, F},
+    KeyEvent, KeyModifiers,
+};
+
+// Splits the screen into panes and moves between them
+#[derive(Clone, Copy)]
+pub enum Window {
+    // The new pane goes below the focused one
+    SplitHorizontal,
+    // The new pane goes right of the focused one
+    SplitVertical,
+    Close,
+    FocusNext,
+    FocusPrev,
+    // Gives the focused pane more (or less) room at the expense of its neighbour
+    Grow,
+    Shrink,
+}
+
+impl TryFrom<KeyEvent> for Window {
+    type Error = String;
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        match (event.code, event.modifiers) {
+            (Char('s'), KeyModifiers::ALT) => Ok(Self::SplitHorizontal),
+            (Char('v'), KeyModifiers::ALT) => Ok(Self::SplitVertical),
+            (Char('q'), KeyModifiers::ALT) => Ok(Self::Close),
+            (F(6), KeyModifiers::NONE) => Ok(Self::FocusNext),
+            (F(6), KeyModifiers::SHIFT) => Ok(Self::FocusPrev),
+            (Char('=' | '+'), modifiers) if modifiers.contains(KeyModifiers::ALT) => Ok(Self::Grow),
+            (Char('-'), KeyModifiers::ALT) => Ok(Self::Shrink),
+            _ => Err(format!(
+                "Unsupported key code {:?} with modifiers {:?}",
+                event.code, event.modifiers
+            )),
+        }
+    }
+}
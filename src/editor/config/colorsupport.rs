@@ -0,0 +1,52 @@
+use std::env;
+
+// How many colors the terminal can show. Theme colors are brought down to what it supports.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum ColorSupport {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    // No colors at all: highlighted text is shown reversed instead
+    Monochrome,
+}
+
+impl ColorSupport {
+    pub fn parse(value: &str) -> Result<Option<Self>, String> {
+        match value {
+            "auto" => Ok(None),
+            "truecolor" | "24bit" => Ok(Some(Self::TrueColor)),
+            "256" => Ok(Some(Self::Ansi256)),
+            "16" => Ok(Some(Self::Ansi16)),
+            "mono" | "none" => Ok(Some(Self::Monochrome)),
+            _ => Err(format!("expected auto, truecolor, 256, 16 or mono, got `{value}`")),
+        }
+    }
+
+    // Guesses from the environment, the way most terminal programs do: COLORTERM announces true color,
+    // TERM names the terminal and usually its palette size. NO_COLOR turns colors off.
+    pub fn detect() -> Self {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Self::Monochrome;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return Self::TrueColor;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        if term.is_empty() {
+            // Windows consoles don't set TERM, and crossterm talks to them directly
+            return if cfg!(windows) { Self::TrueColor } else { Self::Ansi16 };
+        }
+        if term == "dumb" {
+            Self::Monochrome
+        } else if term.contains("direct") || term.contains("truecolor") {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            // Including the Linux console
+            Self::Ansi16
+        }
+    }
+}
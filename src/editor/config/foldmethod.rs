@@ -0,0 +1,20 @@
+// How the region to fold around the caret is found
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum FoldMethod {
+    // Braces for languages using them, indentation otherwise
+    #[default]
+    Auto,
+    Indent,
+    Braces,
+}
+
+impl FoldMethod {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "auto" => Ok(Self::Auto),
+            "indent" => Ok(Self::Indent),
+            "braces" => Ok(Self::Braces),
+            _ => Err(format!("expected auto, indent or braces, got `{value}`")),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::{self, Display};
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum LineNumbers {
+    #[default]
+    Off,
+    Absolute,
+    // Distance to the caret line, which shows its absolute number
+    Relative,
+}
+
+impl LineNumbers {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Absolute,
+            Self::Absolute => Self::Relative,
+            Self::Relative => Self::Off,
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "off" => Ok(Self::Off),
+            "absolute" => Ok(Self::Absolute),
+            "relative" => Ok(Self::Relative),
+            _ => Err(format!("expected off, absolute or relative, got `{value}`")),
+        }
+    }
+}
+
+impl Display for LineNumbers {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Off => "off",
+            Self::Absolute => "absolute",
+            Self::Relative => "relative",
+        };
+        write!(formatter, "{name}")
+    }
+}
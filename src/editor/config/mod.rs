@@ -0,0 +1,175 @@
+use std::{collections::HashMap, env, fs::read_to_string, path::PathBuf};
+
+mod colorsupport;
+pub use colorsupport::ColorSupport;
+mod foldmethod;
+pub use foldmethod::FoldMethod;
+mod linenumbers;
+pub use linenumbers::LineNumbers;
+
+const CONFIG_DIR_NAME: &str = "hecto";
+const CONFIG_FILE_NAME: &str = "config";
+// Followed by the file type, e.g. `language_server.rust`
+const LANGUAGE_SERVER_PREFIX: &str = "language_server.";
+
+// Returns the directory holding the user configuration: $XDG_CONFIG_HOME/hecto, falling back to ~/.config/hecto.
+// Returns None if neither variable is set, in which case the editor runs with its defaults.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(CONFIG_DIR_NAME))
+}
+
+// The settings read from <config dir>/config. The file consists of `key = value` lines,
+// empty lines and lines starting with '#' are ignored:
+//
+// # Indent with 2 spaces
+// indent_with_tabs = false
+// indent_width = 2
+// # Keep 3 lines visible above and below the caret
+// scroll_off = 3
+// soft_wrap = true
+// show_whitespace = true
+// # Tint these columns, separated by commas (none by default)
+// rulers = 80, 100
+// highlight_current_line = true
+// # off, absolute or relative
+// line_numbers = relative
+// # auto, indent or braces
+// fold_method = indent
+// # dark, light or the name of a file in <config dir>/themes, see Theme
+// theme = light
+// # auto detects what the terminal supports, or truecolor, 256, 16 or mono
+// colors = 256
+// # The command starting the language server for a file type, see LanguageServers
+// language_server.rust = rust-analyzer
+// clippy::struct_excessive_bools: these are independent on/off settings
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    pub indent_with_tabs: bool,
+    pub indent_width: usize,
+    pub scroll_off: usize,
+    pub soft_wrap: bool,
+    pub show_whitespace: bool,
+    pub rulers: Vec<usize>,
+    pub highlight_current_line: bool,
+    pub line_numbers: LineNumbers,
+    pub fold_method: FoldMethod,
+    // The name of the theme, which Theme::load looks for. None for the default one.
+    pub theme: Option<String>,
+    // None to detect it
+    pub colors: Option<ColorSupport>,
+    // The command line of the language server for each file type
+    pub language_servers: HashMap<String, Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            indent_with_tabs: false,
+            indent_width: 4,
+            scroll_off: 0,
+            soft_wrap: false,
+            show_whitespace: false,
+            rulers: Vec::new(),
+            highlight_current_line: false,
+            line_numbers: LineNumbers::Off,
+            fold_method: FoldMethod::Auto,
+            theme: None,
+            colors: None,
+            language_servers: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    // Loads the configuration file. A missing file yields the defaults.
+    // Returns the configuration, and a description of the first invalid line, if any.
+    pub fn load() -> (Self, Option<String>) {
+        let mut config = Self::default();
+        let contents = config_dir()
+            .and_then(|dir| read_to_string(dir.join(CONFIG_FILE_NAME)).ok())
+            .unwrap_or_default();
+        let mut first_error = None;
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = line
+                .split_once('=')
+                .ok_or_else(|| String::from("expected `key = value`"))
+                .and_then(|(key, value)| config.set(key.trim(), value.trim()));
+            if let Err(err) = result {
+                first_error.get_or_insert_with(|| {
+                    format!("config line {}: {err}", line_idx.saturating_add(1))
+                });
+            }
+        }
+        (config, first_error)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if let Some(file_type) = key.strip_prefix(LANGUAGE_SERVER_PREFIX) {
+            let command: Vec<String> = value.split_whitespace().map(String::from).collect();
+            if command.is_empty() {
+                return Err(format!("expected a command for {file_type} files"));
+            }
+            self.language_servers.insert(file_type.to_string(), command);
+            return Ok(());
+        }
+        match key {
+            "indent_with_tabs" => self.indent_with_tabs = Self::parse_bool(value)?,
+            "indent_width" => {
+                self.indent_width = Self::parse_usize(value).and_then(|width| {
+                    if width == 0 {
+                        Err(String::from("indent_width must be at least 1"))
+                    } else {
+                        Ok(width)
+                    }
+                })?;
+            }
+            "scroll_off" => self.scroll_off = Self::parse_usize(value)?,
+            "soft_wrap" => self.soft_wrap = Self::parse_bool(value)?,
+            "show_whitespace" => self.show_whitespace = Self::parse_bool(value)?,
+            "rulers" => self.rulers = Self::parse_columns(value)?,
+            "highlight_current_line" => self.highlight_current_line = Self::parse_bool(value)?,
+            "line_numbers" => self.line_numbers = LineNumbers::parse(value)?,
+            "fold_method" => self.fold_method = FoldMethod::parse(value)?,
+            "theme" => self.theme = Some(value.to_string()),
+            "colors" => self.colors = ColorSupport::parse(value)?,
+            _ => return Err(format!("unknown setting `{key}`")),
+        }
+        Ok(())
+    }
+
+    fn parse_bool(value: &str) -> Result<bool, String> {
+        match value {
+            "true" | "yes" | "on" => Ok(true),
+            "false" | "no" | "off" => Ok(false),
+            _ => Err(format!("expected true or false, got `{value}`")),
+        }
+    }
+
+    fn parse_usize(value: &str) -> Result<usize, String> {
+        value
+            .parse()
+            .map_err(|_| format!("expected a number, got `{value}`"))
+    }
+
+    // A comma separated list of columns, counting from 1. May be empty.
+    fn parse_columns(value: &str) -> Result<Vec<usize>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|col| !col.is_empty())
+            .map(|col| match Self::parse_usize(col)? {
+                0 => Err(String::from("columns start at 1")),
+                col => Ok(col),
+            })
+            .collect()
+    }
+}
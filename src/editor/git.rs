@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    fs::canonicalize,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use super::uicomponents::{BlameCommit, BlameLine};
+
+// Length of a full commit hash
+const HASH_LEN: usize = 40;
+const SECONDS_PER_DAY: i64 = 86_400;
+const DAYS_PER_ERA: i64 = 146_097;
+// From 0000-03-01 to 1970-01-01
+const DAYS_FROM_ERA_START_TO_EPOCH: i64 = 719_468;
+
+// Returns the lines of the file as committed in HEAD, or None if it isn't in a git repository or not tracked.
+pub fn head_version(file: &Path) -> Option<Vec<String>> {
+    let file = canonicalize(file).ok()?;
+    let dir = file.parent()?;
+    let name = file.file_name()?.to_string_lossy();
+    // Paths starting with ./ are relative to the working directory rather than to the repository root
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("HEAD:./{name}"))
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect()
+    })
+}
+
+// Runs git blame on the file as saved and returns its lines with the commit which last changed each of them.
+pub fn blame(file: &Path) -> Result<Vec<BlameLine>, String> {
+    let file = canonicalize(file).map_err(|err| format!("{}: {err}", file.display()))?;
+    let dir = file.parent().unwrap_or(&file);
+    let output = Command::new("git")
+        .arg("blame")
+        .arg("--porcelain")
+        .arg(&file)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("could not run git: {err}"))?;
+    if !output.status.success() {
+        // Like files outside of a repository or not added to it
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or_default();
+        return Err(reason.strip_prefix("fatal: ").unwrap_or(reason).to_string());
+    }
+    Ok(parse_blame(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// The porcelain format gives each line as a header with the commit hash, the commit's details the first
+// time it comes up, and the line's text behind a tab.
+fn parse_blame(porcelain: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<&str, PartialCommit> = HashMap::new();
+    let mut hash = "";
+    let mut lines = Vec::new();
+    for line in porcelain.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            lines.push(BlameLine {
+                text: text.to_string(),
+                commit: commits.get(hash).and_then(|commit| commit.finish(hash)),
+            });
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key.len() == HASH_LEN && key.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            hash = key;
+            continue;
+        }
+        let commit = commits.entry(hash).or_default();
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-time" => commit.time = value.parse().unwrap_or_default(),
+            "author-tz" => commit.time_zone = value.to_string(),
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines
+}
+
+#[derive(Default)]
+struct PartialCommit {
+    author: String,
+    // Seconds since the epoch
+    time: i64,
+    // Like +0200
+    time_zone: String,
+    summary: String,
+}
+
+impl PartialCommit {
+    // Lines which aren't committed yet have a hash of zeros
+    fn finish(&self, hash: &str) -> Option<BlameCommit> {
+        (!hash.chars().all(|ch| ch == '0')).then(|| BlameCommit {
+            hash: hash.to_string(),
+            author: self.author.clone(),
+            date: date(self.time, &self.time_zone),
+            summary: self.summary.clone(),
+        })
+    }
+}
+
+// Formats the time as YYYY-MM-DD in the time zone
+fn date(time: i64, time_zone: &str) -> String {
+    let (sign, digits) = time_zone.split_at_checked(1).unwrap_or(("+", "0000"));
+    let hours: i64 = digits.get(..2).and_then(|hours| hours.parse().ok()).unwrap_or(0);
+    let minutes: i64 = digits.get(2..).and_then(|minutes| minutes.parse().ok()).unwrap_or(0);
+    let offset = hours.saturating_mul(3600).saturating_add(minutes.saturating_mul(60));
+    let local_time = if sign == "-" {
+        time.saturating_sub(offset)
+    } else {
+        time.saturating_add(offset)
+    };
+    let (year, month, day) = civil_from_days(local_time.div_euclid(SECONDS_PER_DAY));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// The calendar date of a number of days since 1970-01-01, after Howard Hinnant's algorithm:
+// the proleptic Gregorian calendar is split into eras of 400 years, which start on March 1st
+// so that leap days end the year.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days.saturating_add(DAYS_FROM_ERA_START_TO_EPOCH);
+    let era = days.div_euclid(DAYS_PER_ERA);
+    let day_of_era = days.rem_euclid(DAYS_PER_ERA);
+    let year_of_era = day_of_era
+        .saturating_sub(day_of_era.div_euclid(1460))
+        .saturating_add(day_of_era.div_euclid(36524))
+        .saturating_sub(day_of_era.div_euclid(146_096))
+        .div_euclid(365);
+    let day_of_year = day_of_era.saturating_sub(
+        year_of_era
+            .saturating_mul(365)
+            .saturating_add(year_of_era.div_euclid(4))
+            .saturating_sub(year_of_era.div_euclid(100)),
+    );
+    // Counting from March
+    let month_index = day_of_year.saturating_mul(5).saturating_add(2).div_euclid(153);
+    let day = day_of_year
+        .saturating_sub(month_index.saturating_mul(153).saturating_add(2).div_euclid(5))
+        .saturating_add(1);
+    let month = if month_index < 10 {
+        month_index.saturating_add(3)
+    } else {
+        month_index.saturating_sub(9)
+    };
+    let year = year_of_era
+        .saturating_add(era.saturating_mul(400))
+        .saturating_add(i64::from(month <= 2));
+    (year, month, day)
+}
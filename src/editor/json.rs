@@ -0,0 +1,388 @@
+use std::{
+    fmt::{self, Display, Write},
+    iter::Peekable,
+    str::CharIndices,
+};
+
+// Arrays and objects nested deeper than this are refused rather than overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+// A JSON document, as far as the editor needs to talk to other tools. Display writes it out compactly.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    // Kept as written, so that integers don't go through floating point
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    // Members in the order they were written
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: text.char_indices().peekable(),
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some((idx, _)) => Err(format!("unexpected text at byte {idx}")),
+        }
+    }
+
+    pub fn object<const N: usize>(members: [(&str, Self); N]) -> Self {
+        Self::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // The member of an object with the given key
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Self::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> &[Self] {
+        match self {
+            Self::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(string: &str) -> Self {
+        Self::String(string.to_string())
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(number: usize) -> Self {
+        Self::Number(number.to_string())
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => formatter.write_str("null"),
+            Self::Bool(value) => write!(formatter, "{value}"),
+            Self::Number(number) => formatter.write_str(number),
+            Self::String(string) => write_string(formatter, string),
+            Self::Array(items) => {
+                formatter.write_char('[')?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        formatter.write_char(',')?;
+                    }
+                    write!(formatter, "{item}")?;
+                }
+                formatter.write_char(']')
+            }
+            Self::Object(members) => {
+                formatter.write_char('{')?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        formatter.write_char(',')?;
+                    }
+                    write_string(formatter, key)?;
+                    write!(formatter, ":{value}")?;
+                }
+                formatter.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(formatter: &mut fmt::Formatter, string: &str) -> fmt::Result {
+    formatter.write_char('"')?;
+    for character in string.chars() {
+        match character {
+            '"' => formatter.write_str("\\\"")?,
+            '\\' => formatter.write_str("\\\\")?,
+            '\n' => formatter.write_str("\\n")?,
+            '\r' => formatter.write_str("\\r")?,
+            '\t' => formatter.write_str("\\t")?,
+            character if character.is_control() => write!(formatter, "\\u{:04x}", u32::from(character))?,
+            character => formatter.write_char(character)?,
+        }
+    }
+    formatter.write_char('"')
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    // How many arrays and objects the current value is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, character)| character.is_whitespace()).is_some() {}
+    }
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, character)) if character == expected => Ok(()),
+            Some((idx, character)) => Err(format!("expected `{expected}` at byte {idx}, got `{character}`")),
+            None => Err(format!("expected `{expected}`, got the end")),
+        }
+    }
+    fn keyword(&mut self, keyword: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some((_, '{')) => self.nested(Self::object),
+            Some((_, '[')) => self.nested(Self::array),
+            Some((_, '"')) => self.string().map(JsonValue::String),
+            Some((_, 't')) => self.keyword("true", JsonValue::Bool(true)),
+            Some((_, 'f')) => self.keyword("false", JsonValue::Bool(false)),
+            Some((_, 'n')) => self.keyword("null", JsonValue::Null),
+            Some((_, character)) if character == '-' || character.is_ascii_digit() => Ok(self.number()),
+            Some((idx, character)) => Err(format!("unexpected `{character}` at byte {idx}")),
+            None => Err(String::from("unexpected end")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<JsonValue, String>) -> Result<JsonValue, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("nested deeper than {MAX_DEPTH} levels"));
+        }
+        self.depth = self.depth.saturating_add(1);
+        let value = parse(self);
+        self.depth = self.depth.saturating_sub(1);
+        value
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, character)| *character == '}').is_some() {
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, character)| *character == ',').is_none() {
+                self.expect('}')?;
+                return Ok(JsonValue::Object(members));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, character)| *character == ']').is_some() {
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, character)| *character == ',').is_none() {
+                self.expect(']')?;
+                return Ok(JsonValue::Array(items));
+            }
+        }
+    }
+
+    fn number(&mut self) -> JsonValue {
+        let mut number = String::new();
+        while let Some((_, character)) = self
+            .chars
+            .next_if(|(_, character)| character.is_ascii_digit() || matches!(character, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number.push(character);
+        }
+        JsonValue::Number(number)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(string),
+                Some((_, '\\')) => string.push(self.escape()?),
+                Some((_, character)) => string.push(character),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        match self.chars.next() {
+            Some((_, 'n')) => Ok('\n'),
+            Some((_, 't')) => Ok('\t'),
+            Some((_, 'r')) => Ok('\r'),
+            Some((_, 'b')) => Ok('\u{8}'),
+            Some((_, 'f')) => Ok('\u{c}'),
+            Some((_, 'u')) => {
+                let first = self.hex_code()?;
+                // Characters outside the basic plane are written as a pair of surrogates
+                let code = if (0xD800..0xDC00).contains(&first) {
+                    self.expect('\\')?;
+                    self.expect('u')?;
+                    let second = self.hex_code()?;
+                    0x10000_u32
+                        .saturating_add(first.saturating_sub(0xD800).saturating_mul(0x400))
+                        .saturating_add(second.saturating_sub(0xDC00))
+                } else {
+                    first
+                };
+                Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            }
+            Some((_, character)) => Ok(character),
+            None => Err(String::from("unterminated string")),
+        }
+    }
+
+    fn hex_code(&mut self) -> Result<u32, String> {
+        let mut code: u32 = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|(_, character)| character.to_digit(16))
+                .ok_or_else(|| String::from("invalid unicode escape"))?;
+            code = code.saturating_mul(16).saturating_add(digit);
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonValue, MAX_DEPTH};
+
+    fn string(text: &str) -> JsonValue {
+        JsonValue::String(text.to_string())
+    }
+    fn number(text: &str) -> JsonValue {
+        JsonValue::Number(text.to_string())
+    }
+
+    #[test]
+    fn parses_escapes() {
+        assert_eq!(
+            JsonValue::parse(r#""a\"b\\c\/d\n\t\r\b\f""#),
+            Ok(string("a\"b\\c/d\n\t\r\u{8}\u{c}"))
+        );
+        assert_eq!(JsonValue::parse(r#""\u00e9\u20AC""#), Ok(string("é€")));
+        assert_eq!(JsonValue::parse(r#""\ud83d\ude00""#), Ok(string("😀")));
+        assert!(JsonValue::parse(r#""\u12"#).is_err());
+        assert!(JsonValue::parse(r#""\ud83d""#).is_err());
+        assert!(JsonValue::parse(r#""unterminated"#).is_err());
+    }
+
+    #[test]
+    fn keeps_numbers_as_written() {
+        assert_eq!(JsonValue::parse("0"), Ok(number("0")));
+        assert_eq!(JsonValue::parse("-12.5e+3"), Ok(number("-12.5e+3")));
+        assert_eq!(JsonValue::parse("18446744073709551615").ok().and_then(|value| value.as_usize()), Some(usize::MAX));
+        assert_eq!(JsonValue::parse("1.5").ok().and_then(|value| value.as_usize()), None);
+    }
+
+    #[test]
+    fn parses_nested_arrays_and_objects() {
+        let value = JsonValue::parse(r#" { "a" : [1, [], {}, [true, false, null]], "b": {"c": "d"} } "#);
+        assert_eq!(
+            value,
+            Ok(JsonValue::object([
+                (
+                    "a",
+                    JsonValue::Array(vec![
+                        number("1"),
+                        JsonValue::Array(Vec::new()),
+                        JsonValue::Object(Vec::new()),
+                        JsonValue::Array(vec![JsonValue::Bool(true), JsonValue::Bool(false), JsonValue::Null]),
+                    ])
+                ),
+                ("b", JsonValue::object([("c", string("d"))])),
+            ]))
+        );
+        let value = value.unwrap_or(JsonValue::Null);
+        assert_eq!(value.get("b").and_then(|b| b.get("c")).and_then(JsonValue::as_str), Some("d"));
+        assert_eq!(value.get("a").map(|a| a.as_array().len()), Some(4));
+        assert_eq!(value.get("missing"), None);
+    }
+
+    #[test]
+    fn refuses_malformed_documents() {
+        for text in ["", "[1,]", "[1 2]", "{\"a\" 1}", "{1: 2}", "tru", "[1] x", "@"] {
+            assert!(JsonValue::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn caps_the_nesting_depth() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(JsonValue::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(JsonValue::parse(&nested(MAX_DEPTH.saturating_add(1))).is_err());
+        assert!(JsonValue::parse(&"{\"a\":".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let value = JsonValue::object([
+            ("text", string("quote \" backslash \\ line\nend\ttab \u{1} é 😀")),
+            ("number", number("-0.5E-7")),
+            (
+                "items",
+                JsonValue::Array(vec![JsonValue::Null, JsonValue::Bool(true), JsonValue::object([])]),
+            ),
+        ]);
+        let text = value.to_string();
+        assert_eq!(
+            text,
+            r#"{"text":"quote \" backslash \\ line\nend\ttab \u0001 é 😀","number":-0.5E-7,"items":[null,true,{}]}"#
+        );
+        assert_eq!(JsonValue::parse(&text), Ok(value));
+    }
+}
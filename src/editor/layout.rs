@@ -0,0 +1,246 @@
+use super::{Position, Size};
+
+// Share of a split given to its first pane, in percent
+const DEFAULT_RATIO: usize = 50;
+const MIN_RATIO: usize = 10;
+const MAX_RATIO: usize = 90;
+const RESIZE_STEP: usize = 5;
+
+// A rectangle of the screen
+#[derive(Default, Clone, Copy, Eq, PartialEq)]
+pub struct Area {
+    pub origin: Position,
+    pub size: Size,
+}
+
+impl Area {
+    pub const fn contains(&self, position: Position) -> bool {
+        position.row >= self.origin.row
+            && position.row < self.origin.row.saturating_add(self.size.height)
+            && position.col >= self.origin.col
+            && position.col < self.origin.col.saturating_add(self.size.width)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    // One pane above the other
+    Horizontal,
+    // Side by side, with a separator column in between
+    Vertical,
+}
+
+// Where the panes go on screen. Panes are referred to by their index in the editor's pane list.
+pub enum Layout {
+    Pane(usize),
+    Split {
+        direction: Direction,
+        ratio: usize,
+        first: Box<Layout>,
+        second: Box<Layout>,
+    },
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::Pane(0)
+    }
+}
+
+// The result of Layout::arrange
+#[derive(Default)]
+pub struct Arrangement {
+    // Pane index and area, in reading order
+    pub panes: Vec<(usize, Area)>,
+    // Columns between side by side panes
+    pub separators: Vec<Area>,
+}
+
+impl Layout {
+    pub fn arrange(&self, area: Area) -> Arrangement {
+        let mut arrangement = Arrangement::default();
+        self.arrange_into(area, &mut arrangement);
+        arrangement
+    }
+    fn arrange_into(&self, area: Area, arrangement: &mut Arrangement) {
+        match self {
+            Self::Pane(idx) => arrangement.panes.push((*idx, area)),
+            Self::Split {
+                direction: Direction::Horizontal,
+                ratio,
+                first,
+                second,
+            } => {
+                let first_height = Self::share(area.size.height, *ratio);
+                first.arrange_into(
+                    Area {
+                        origin: area.origin,
+                        size: Size {
+                            height: first_height,
+                            width: area.size.width,
+                        },
+                    },
+                    arrangement,
+                );
+                second.arrange_into(
+                    Area {
+                        origin: Position {
+                            row: area.origin.row.saturating_add(first_height),
+                            col: area.origin.col,
+                        },
+                        size: Size {
+                            height: area.size.height.saturating_sub(first_height),
+                            width: area.size.width,
+                        },
+                    },
+                    arrangement,
+                );
+            }
+            Self::Split {
+                direction: Direction::Vertical,
+                ratio,
+                first,
+                second,
+            } => {
+                let available = area.size.width.saturating_sub(1);
+                let first_width = Self::share(available, *ratio);
+                let separator_col = area.origin.col.saturating_add(first_width);
+                first.arrange_into(
+                    Area {
+                        origin: area.origin,
+                        size: Size {
+                            height: area.size.height,
+                            width: first_width,
+                        },
+                    },
+                    arrangement,
+                );
+                arrangement.separators.push(Area {
+                    origin: Position {
+                        row: area.origin.row,
+                        col: separator_col,
+                    },
+                    size: Size {
+                        height: area.size.height,
+                        width: 1,
+                    },
+                });
+                second.arrange_into(
+                    Area {
+                        origin: Position {
+                            row: area.origin.row,
+                            col: separator_col.saturating_add(1),
+                        },
+                        size: Size {
+                            height: area.size.height,
+                            width: available.saturating_sub(first_width),
+                        },
+                    },
+                    arrangement,
+                );
+            }
+        }
+    }
+    // Each side gets at least one row or column, as long as there is room for it.
+    fn share(total: usize, ratio: usize) -> usize {
+        let share = total
+            .saturating_mul(ratio)
+            .checked_div(100)
+            .unwrap_or(0);
+        share.clamp(1, total.saturating_sub(1).max(1)).min(total)
+    }
+
+    // Puts the new pane next to the target pane, splitting its room in half.
+    pub fn split(&mut self, target: usize, new_pane: usize, direction: Direction) {
+        match self {
+            Self::Pane(idx) if *idx == target => {
+                *self = Self::Split {
+                    direction,
+                    ratio: DEFAULT_RATIO,
+                    first: Box::new(Self::Pane(target)),
+                    second: Box::new(Self::Pane(new_pane)),
+                };
+            }
+            Self::Pane(_) => {}
+            Self::Split { first, second, .. } => {
+                first.split(target, new_pane, direction);
+                second.split(target, new_pane, direction);
+            }
+        }
+    }
+
+    // Removes the pane and gives its room to its neighbour. Panes behind it move up one index,
+    // like in the pane list. The last pane can't be removed.
+    pub fn remove(&mut self, target: usize) {
+        self.remove_pane(target);
+        self.renumber(target);
+    }
+    fn remove_pane(&mut self, target: usize) {
+        let Self::Split { first, second, .. } = self else {
+            return;
+        };
+        let remaining = if matches!(**first, Self::Pane(idx) if idx == target) {
+            Some(std::mem::take(second))
+        } else if matches!(**second, Self::Pane(idx) if idx == target) {
+            Some(std::mem::take(first))
+        } else {
+            None
+        };
+        if let Some(remaining) = remaining {
+            *self = *remaining;
+        } else {
+            first.remove_pane(target);
+            second.remove_pane(target);
+        }
+    }
+    fn renumber(&mut self, removed: usize) {
+        match self {
+            Self::Pane(idx) => {
+                if *idx > removed {
+                    *idx = idx.saturating_sub(1);
+                }
+            }
+            Self::Split { first, second, .. } => {
+                first.renumber(removed);
+                second.renumber(removed);
+            }
+        }
+    }
+
+    // Moves the border of the innermost split around the target pane. Returns false if there is none,
+    // or if the border can't move any further.
+    pub fn resize(&mut self, target: usize, grow: bool) -> bool {
+        let Self::Split {
+            ratio,
+            first,
+            second,
+            ..
+        } = self
+        else {
+            return false;
+        };
+        let in_first = first.contains(target);
+        if !in_first && !second.contains(target) {
+            return false;
+        }
+        let inner = if in_first { first } else { second };
+        if matches!(**inner, Self::Split { .. }) {
+            return inner.resize(target, grow);
+        }
+        // Growing the first pane means moving the border away from it
+        let new_ratio = if in_first == grow {
+            ratio.saturating_add(RESIZE_STEP).min(MAX_RATIO)
+        } else {
+            ratio.saturating_sub(RESIZE_STEP).max(MIN_RATIO)
+        };
+        let changed = new_ratio != *ratio;
+        *ratio = new_ratio;
+        changed
+    }
+    fn contains(&self, target: usize) -> bool {
+        match self {
+            Self::Pane(idx) => *idx == target,
+            Self::Split { first, second, .. } => first.contains(target) || second.contains(target),
+        }
+    }
+}
@@ -10,12 +10,18 @@ use textfragment::TextFragment;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-use super::{AnnotatedString, AnnotationType, Col};
+use super::{AnnotatedString, AnnotationType, Col, Highlight};
 
 type GraphemeIdx = usize;
 type ByteIdx = usize;
 type ColIdx = usize;
 
+// Shown instead of whitespace, when it's made visible
+const TAB_SYMBOL: char = '→';
+const SPACE_SYMBOL: char = '·';
+const NO_BREAK_SPACE_SYMBOL: char = '⍽';
+const END_OF_LINE_SYMBOL: &str = "¬";
+
 #[derive(Default, Clone)]
 pub struct Line {
     fragments: Vec<TextFragment>,
@@ -82,11 +88,40 @@ impl Line {
         }
     }
 
+    fn get_whitespace_symbol(for_str: &str) -> Option<char> {
+        match for_str {
+            "\t" => Some(TAB_SYMBOL),
+            " " => Some(SPACE_SYMBOL),
+            "\u{a0}" | "\u{202f}" => Some(NO_BREAK_SPACE_SYMBOL),
+            _ => None,
+        }
+    }
+
+    fn annotate_whitespace(&self, result: &mut AnnotatedString) {
+        for fragment in &self.fragments {
+            if Self::get_whitespace_symbol(&fragment.grapheme).is_some() {
+                result.add_annotation(
+                    AnnotationType::Whitespace,
+                    fragment.start_byte_idx,
+                    fragment.start_byte_idx.saturating_add(fragment.grapheme.len()),
+                );
+            }
+        }
+        let trailing_start = self.string.trim_end().len();
+        if trailing_start < self.string.len() {
+            result.add_annotation(
+                AnnotationType::TrailingWhitespace,
+                trailing_start,
+                self.string.len(),
+            );
+        }
+    }
+
     // Gets the visible graphemes in the given column index.
     // Note that the column index is not the same as the grapheme index:
     // A grapheme can have a width of 2 columns.
     pub fn get_visible_graphemes(&self, range: Range<ColIdx>) -> String {
-        self.get_annotated_visible_substr(range, None, None)
+        self.get_annotated_visible_substr(range, &[], None, None, None, false)
         .to_string()
     }
 
@@ -95,13 +130,19 @@ impl Line {
     // A grapheme can have a width of 2 columns.
     // Parameters:
     // - range: The range of columns to get the annotated string from.
+    // - highlights: The syntax highlights of this line.
     // - query: The query to highlight in the annotated string.
     // - selected_match: The selected match to highlight in the annotated string. This is only applied if the query is not empty.
+    // - selection: The graphemes of this line which are part of the selection.
+    // - show_whitespace: Whether to replace tabs and spaces by symbols, mark trailing whitespace and the end of the line.
     pub fn get_annotated_visible_substr(
         &self,
         range: Range<ColIdx>,
+        highlights: &[Highlight],
         query: Option<&str>,
         selected_match: Option<GraphemeIdx>,
+        selection: Option<Range<GraphemeIdx>>,
+        show_whitespace: bool,
     ) -> AnnotatedString {
 
         if range.start >= range.end {
@@ -110,6 +151,20 @@ impl Line {
         // create a new annotated string
         let mut result = AnnotatedString::from(&self.string);
 
+        // Added first, so that everything else takes precedence
+        for highlight in highlights {
+            result.add_annotation(
+                highlight.annotation_type,
+                highlight.start_byte_idx,
+                highlight.end_byte_idx,
+            );
+        }
+
+        // Added before the matches and the selection, so that they take precedence
+        if show_whitespace {
+            self.annotate_whitespace(&mut result);
+        }
+
         if let Some(query) = query {
             if !query.is_empty() {
                 self.find_all(query, 0..self.string.len()).iter().for_each(
@@ -130,10 +185,18 @@ impl Line {
                             start_byte_idx.saturating_add(query.len()),
                         );
                     }
-                )
+                );
             }
         }
 
+        if let Some(selection) = selection {
+            result.add_annotation(
+                AnnotationType::Selection,
+                self.grapheme_idx_to_byte_idx_or_end(selection.start),
+                self.grapheme_idx_to_byte_idx_or_end(selection.end),
+            );
+        }
+
         // Insert replacement characters, and truncate if needed.
         // We do this backwards, otherwise the byte indices would be off in case a replacement character has a different width than the original character.
         let mut fragment_start = self.width();
@@ -179,13 +242,21 @@ impl Line {
 
             // Fragment is fully within range: Apply replacement characters if appropriate
             if fragment_start >= range.start && fragment_end <= range.end {
-                if let Some(replacement) = fragment.replacement {
+                let replacement = if show_whitespace {
+                    Self::get_whitespace_symbol(&fragment.grapheme).or(fragment.replacement)
+                } else {
+                    fragment.replacement
+                };
+                if let Some(replacement) = replacement {
                     let start_byte_idx = fragment.start_byte_idx;
                     let end_byte_idx = start_byte_idx.saturating_add(fragment.grapheme.len());
                     result.replace(start_byte_idx, end_byte_idx, &replacement.to_string());
                 }
             }
         }
+        if show_whitespace && (range.start..range.end).contains(&self.width()) {
+            result.push_annotated(END_OF_LINE_SYMBOL, AnnotationType::Whitespace);
+        }
         result
     }
 
@@ -202,6 +273,47 @@ impl Line {
             })
             .sum()
     }
+    // Returns the index of the grapheme covering the given column, or grapheme_count if the column is behind the end of the line.
+    pub fn grapheme_idx_at_col(&self, col: Col) -> GraphemeIdx {
+        let mut fragment_end: Col = 0;
+        for (grapheme_idx, fragment) in self.fragments.iter().enumerate() {
+            fragment_end = fragment_end.saturating_add(fragment.rendered_width.into());
+            if col < fragment_end {
+                return grapheme_idx;
+            }
+        }
+        self.grapheme_count()
+    }
+    // Splits the line into rows no wider than `width` columns for soft wrapping, preferably right after whitespace.
+    // Returns the index of the grapheme each row starts at. There is always at least one row, starting at 0.
+    pub fn wrap_points(&self, width: Col) -> Vec<GraphemeIdx> {
+        let mut points = vec![0];
+        if width == 0 {
+            return points;
+        }
+        let mut row_start: GraphemeIdx = 0;
+        let mut row_width: Col = 0;
+        let mut last_break: Option<GraphemeIdx> = None;
+        for (grapheme_idx, fragment) in self.fragments.iter().enumerate() {
+            let fragment_width: Col = fragment.rendered_width.into();
+            if row_width.saturating_add(fragment_width) > width && grapheme_idx > row_start {
+                let break_idx = last_break
+                    .filter(|break_idx| *break_idx > row_start)
+                    .unwrap_or(grapheme_idx);
+                points.push(break_idx);
+                row_start = break_idx;
+                row_width = self
+                    .width_until(grapheme_idx)
+                    .saturating_sub(self.width_until(break_idx));
+                last_break = None;
+            }
+            row_width = row_width.saturating_add(fragment_width);
+            if fragment.grapheme.trim().is_empty() {
+                last_break = Some(grapheme_idx.saturating_add(1));
+            }
+        }
+        points
+    }
     pub fn width(&self) -> Col {
         self.width_until(self.grapheme_count())
     }
@@ -234,6 +346,35 @@ impl Line {
         self.delete(self.grapheme_count().saturating_sub(1));
     }
 
+    // Inserts a string into the line at the given grapheme index, or appends it if at == grapheme_count
+    pub fn insert_str(&mut self, string: &str, at: GraphemeIdx) {
+        debug_assert!(at <= self.grapheme_count());
+        let byte_idx = self.grapheme_idx_to_byte_idx_or_end(at);
+        self.string.insert_str(byte_idx, string);
+        self.rebuild_fragments();
+    }
+
+    // Deletes all graphemes within the given range. Out of bound indices are clamped to the end of the line.
+    pub fn delete_range(&mut self, range: Range<GraphemeIdx>) {
+        if range.start >= range.end {
+            return;
+        }
+        let start = self.grapheme_idx_to_byte_idx_or_end(range.start);
+        let end = self.grapheme_idx_to_byte_idx_or_end(range.end);
+        self.string.drain(start..end);
+        self.rebuild_fragments();
+    }
+
+    // Returns the text of the graphemes within the given range. Out of bound indices are clamped to the end of the line.
+    pub fn get_substr(&self, range: Range<GraphemeIdx>) -> &str {
+        if range.start >= range.end {
+            return "";
+        }
+        let start = self.grapheme_idx_to_byte_idx_or_end(range.start);
+        let end = self.grapheme_idx_to_byte_idx_or_end(range.end);
+        self.string.get(start..end).unwrap_or_default()
+    }
+
     pub fn append(&mut self, other: &Self) {
         self.string.push_str(&other.string);
         self.rebuild_fragments();
@@ -258,6 +399,17 @@ impl Line {
             .position(|fragment| fragment.start_byte_idx >= byte_idx)
     }
 
+    // Converts a column counted in characters, as other tools report them. Columns past the end map to the end.
+    pub fn char_idx_to_grapheme_idx(&self, char_idx: usize) -> GraphemeIdx {
+        let byte_idx = self
+            .string
+            .char_indices()
+            .nth(char_idx)
+            .map_or(self.string.len(), |(byte_idx, _)| byte_idx);
+        self.byte_idx_to_grapheme_idx(byte_idx)
+            .unwrap_or_else(|| self.grapheme_count())
+    }
+
     fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
         debug_assert!(grapheme_idx <= self.grapheme_count());
         if grapheme_idx == 0 || self.grapheme_count() == 0 {
@@ -278,6 +430,13 @@ impl Line {
         )
     }
 
+    // Same as grapheme_idx_to_byte_idx, but maps every index past the last grapheme to the end of the string.
+    pub fn grapheme_idx_to_byte_idx_or_end(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+        self.fragments
+            .get(grapheme_idx)
+            .map_or(self.string.len(), |fragment| fragment.start_byte_idx)
+    }
+
     pub fn search_forward(
         &self,
         query: &str,
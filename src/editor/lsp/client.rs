@@ -0,0 +1,587 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::read_to_string,
+    io::{BufRead, BufReader, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::super::json::JsonValue;
+use super::{uri, Document, PositionEncoding};
+
+// How long to wait for an answer before giving up on the server
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Servers get less time to shut down, the editor is quitting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const CONTENT_LENGTH_HEADER: &str = "Content-Length:";
+// TextDocumentSyncKind
+const SYNC_FULL: usize = 1;
+const SYNC_INCREMENTAL: usize = 2;
+
+// A language server process, talked to over its standard input and output. A thread reads what it sends.
+pub struct LanguageClient {
+    process: Child,
+    stdin: ChildStdin,
+    messages: Receiver<JsonValue>,
+    next_id: usize,
+    pub encoding: PositionEncoding,
+    // Whether changes are sent as edits, or as the whole new text
+    incremental: bool,
+    // Keyed by canonical path
+    documents: HashMap<PathBuf, Document>,
+    // Notifications which came in while waiting for an answer
+    pending: VecDeque<JsonValue>,
+    // The results of the requests, or their errors, until they're taken
+    answers: HashMap<usize, Result<JsonValue, String>>,
+    // The id of the initialize request and when it was sent, until it's answered, see poll_initialized
+    initializing: Option<(usize, Instant)>,
+}
+
+impl LanguageClient {
+    // Starts the server and asks it to initialize, without waiting for it to be done, see poll_initialized.
+    pub fn start(command: &[String], root: &Path) -> Result<Self, String> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| String::from("no command"))?;
+        let mut process = Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("could not start {program}: {err}"))?;
+        let (Some(stdin), Some(stdout)) = (process.stdin.take(), process.stdout.take()) else {
+            let _ = process.kill();
+            return Err(format!("could not talk to {program}"));
+        };
+        let (sender, messages) = channel();
+        thread::spawn(move || read_messages(stdout, &sender));
+        let mut client = Self {
+            process,
+            stdin,
+            messages,
+            next_id: 0,
+            encoding: PositionEncoding::default(),
+            incremental: false,
+            documents: HashMap::new(),
+            pending: VecDeque::new(),
+            answers: HashMap::new(),
+            initializing: None,
+        };
+        let id = client.send_initialize(root)?;
+        client.initializing = Some((id, Instant::now()));
+        Ok(client)
+    }
+
+    // region: initialization
+    // Whether the server is ready for documents and requests: it answered initialize and was told so.
+    // Errors if it failed to initialize or took too long.
+    pub fn poll_initialized(&mut self) -> Result<bool, String> {
+        let Some((id, sent)) = self.initializing else {
+            return Ok(true);
+        };
+        match self.take_answer(id) {
+            Some(Ok(result)) => {
+                self.initializing = None;
+                self.read_capabilities(&result);
+                self.notify("initialized", JsonValue::object([]))?;
+                Ok(true)
+            }
+            Some(Err(err)) => Err(format!("initialize: {err}")),
+            None if sent.elapsed() > REQUEST_TIMEOUT => {
+                Err(String::from("initialize: no answer from the language server"))
+            }
+            None => Ok(false),
+        }
+    }
+    fn send_initialize(&mut self, root: &Path) -> Result<usize, String> {
+        let capabilities = JsonValue::object([
+            (
+                "general",
+                JsonValue::object([(
+                    "positionEncodings",
+                    JsonValue::Array(vec![JsonValue::from("utf-8"), JsonValue::from("utf-16")]),
+                )]),
+            ),
+            (
+                "textDocument",
+                JsonValue::object([
+                    ("synchronization", JsonValue::object([("didSave", JsonValue::from(true))])),
+                    (
+                        "hover",
+                        JsonValue::object([(
+                            "contentFormat",
+                            JsonValue::Array(vec![JsonValue::from("plaintext"), JsonValue::from("markdown")]),
+                        )]),
+                    ),
+                    (
+                        "completion",
+                        JsonValue::object([(
+                            "completionItem",
+                            JsonValue::object([("snippetSupport", JsonValue::from(true))]),
+                        )]),
+                    ),
+                    ("definition", JsonValue::object([])),
+                    ("rename", JsonValue::object([])),
+                    ("publishDiagnostics", JsonValue::object([])),
+                ]),
+            ),
+        ]);
+        let root_uri = uri::from_path(root);
+        self.send_request(
+            "initialize",
+            JsonValue::object([
+                (
+                    "processId",
+                    usize::try_from(std::process::id()).map_or(JsonValue::Null, JsonValue::from),
+                ),
+                ("rootUri", JsonValue::from(root_uri.as_str())),
+                (
+                    "workspaceFolders",
+                    JsonValue::Array(vec![JsonValue::object([
+                        ("uri", JsonValue::from(root_uri.as_str())),
+                        (
+                            "name",
+                            JsonValue::from(root.file_name().and_then(|name| name.to_str()).unwrap_or_default()),
+                        ),
+                    ])]),
+                ),
+                ("capabilities", capabilities),
+            ]),
+        )
+    }
+    fn read_capabilities(&mut self, result: &JsonValue) {
+        let server_capabilities = result.get("capabilities");
+        self.encoding = server_capabilities
+            .and_then(|capabilities| capabilities.get("positionEncoding"))
+            .and_then(JsonValue::as_str)
+            .and_then(PositionEncoding::parse)
+            .unwrap_or_default();
+        // Either a kind, or options with a kind
+        let sync = server_capabilities.and_then(|capabilities| capabilities.get("textDocumentSync"));
+        let sync_kind = sync
+            .and_then(JsonValue::as_usize)
+            .or_else(|| sync.and_then(|sync| sync.get("change")).and_then(JsonValue::as_usize))
+            .unwrap_or(SYNC_FULL);
+        self.incremental = sync_kind == SYNC_INCREMENTAL;
+    }
+    // endregion
+
+    // region: messages
+    fn send(&mut self, message: &JsonValue) -> Result<(), String> {
+        let body = message.to_string();
+        write!(self.stdin, "{CONTENT_LENGTH_HEADER} {}\r\n\r\n{body}", body.len())
+            .and_then(|()| self.stdin.flush())
+            .map_err(|err| format!("language server stopped: {err}"))
+    }
+    pub fn notify(&mut self, method: &str, params: JsonValue) -> Result<(), String> {
+        self.send(&JsonValue::object([
+            ("jsonrpc", JsonValue::from("2.0")),
+            ("method", JsonValue::from(method)),
+            ("params", params),
+        ]))
+    }
+    // Sends a request without waiting for its answer, see take_answer. Returns its id.
+    pub fn send_request(&mut self, method: &str, params: JsonValue) -> Result<usize, String> {
+        let id = self.next_id;
+        self.next_id = self.next_id.saturating_add(1);
+        // Only the latest request is waited for, earlier answers no longer matter
+        self.answers.clear();
+        self.send(&JsonValue::object([
+            ("jsonrpc", JsonValue::from("2.0")),
+            ("id", JsonValue::from(id)),
+            ("method", JsonValue::from(method)),
+            ("params", params),
+        ]))?;
+        Ok(id)
+    }
+    // Sends a request and waits for its result, keeping the notifications which come in meanwhile.
+    // Only used while stopping the server, when there is nothing else to do.
+    fn request_with_timeout(&mut self, method: &str, params: JsonValue, timeout: Duration) -> Result<JsonValue, String> {
+        let id = self.send_request(method, params)?;
+        let deadline = Instant::now().checked_add(timeout).unwrap_or_else(Instant::now);
+        loop {
+            if let Some(answer) = self.answers.remove(&id) {
+                return answer.map_err(|err| format!("{method}: {err}"));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.messages.recv_timeout(remaining) {
+                Ok(message) => self.handle_server_message(message),
+                Err(RecvTimeoutError::Timeout) => return Err(format!("{method}: no answer from the language server")),
+                Err(RecvTimeoutError::Disconnected) => return Err(String::from("language server stopped")),
+            }
+        }
+    }
+    // Keeps answers for take_answer, answers requests from the server, and keeps notifications for
+    // take_notifications.
+    fn handle_server_message(&mut self, message: JsonValue) {
+        if message.get("method").is_none() {
+            if let Some(id) = message.get("id").and_then(JsonValue::as_usize) {
+                let answer = match message.get("error") {
+                    Some(error) => Err(error
+                        .get("message")
+                        .and_then(JsonValue::as_str)
+                        .unwrap_or("unknown error")
+                        .to_string()),
+                    None => Ok(message.get("result").cloned().unwrap_or(JsonValue::Null)),
+                };
+                self.answers.insert(id, answer);
+            }
+            return;
+        }
+        let Some(id) = message.get("id").cloned() else {
+            self.pending.push_back(message);
+            return;
+        };
+        // Settings are asked for one by one, none of them are set
+        let result = if message.get("method").and_then(JsonValue::as_str) == Some("workspace/configuration") {
+            let item_count = message
+                .get("params")
+                .and_then(|params| params.get("items"))
+                .map_or(0, |items| items.as_array().len());
+            JsonValue::Array(vec![JsonValue::Null; item_count])
+        } else {
+            JsonValue::Null
+        };
+        let _ = self.send(&JsonValue::object([
+            ("jsonrpc", JsonValue::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+    // Handles what the server sent, without waiting for more.
+    fn receive(&mut self) -> Result<(), String> {
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => self.handle_server_message(message),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err(String::from("language server stopped")),
+            }
+        }
+    }
+    // The answer to the request with the given id if it came in: its result, or the error the server
+    // returned. Errors too if the server stopped.
+    pub fn take_answer(&mut self, id: usize) -> Option<Result<JsonValue, String>> {
+        let received = self.receive();
+        self.answers
+            .remove(&id)
+            .or_else(|| received.err().map(Err))
+    }
+    // Returns the notifications the server sent since the last call, without waiting for more.
+    pub fn take_notifications(&mut self) -> Vec<JsonValue> {
+        let _ = self.receive();
+        self.pending.drain(..).collect()
+    }
+    // endregion
+
+    // region: documents
+    // The buffer revision of the text the server has of the file, None if it doesn't know the file
+    pub fn synced_revision(&self, path: &Path) -> Option<usize> {
+        self.documents.get(path).map(|document| document.revision)
+    }
+    // Tells the server about a file it doesn't know yet.
+    pub fn open(&mut self, path: &Path, language_id: &str, revision: usize, lines: Vec<String>) -> Result<(), String> {
+        let document = Document::new(uri::from_path(path), revision, lines);
+        let params = JsonValue::object([(
+            "textDocument",
+            JsonValue::object([
+                ("uri", JsonValue::from(document.uri.as_str())),
+                ("languageId", JsonValue::from(language_id)),
+                ("version", JsonValue::from(document.version)),
+                ("text", JsonValue::String(document.text())),
+            ]),
+        )]);
+        self.documents.insert(path.to_path_buf(), document);
+        self.notify("textDocument/didOpen", params)
+    }
+    // Tells the server that the given lines of an open file were replaced with new ones.
+    pub fn change(&mut self, path: &Path, revision: usize, old_lines: Range<usize>, lines: Vec<String>) -> Result<(), String> {
+        let encoding = self.encoding;
+        let incremental = self.incremental;
+        let Some(document) = self.documents.get_mut(path) else {
+            return Ok(());
+        };
+        let Some(change) = document.update(revision, old_lines, lines, encoding) else {
+            return Ok(());
+        };
+        let change = if incremental {
+            change
+        } else {
+            JsonValue::object([("text", JsonValue::String(document.text()))])
+        };
+        let params = JsonValue::object([
+            ("textDocument", Self::versioned_identifier(document)),
+            ("contentChanges", JsonValue::Array(vec![change])),
+        ]);
+        self.notify("textDocument/didChange", params)
+    }
+    pub fn did_save(&mut self, path: &Path) -> Result<(), String> {
+        let Some(document) = self.documents.get(path) else {
+            return Ok(());
+        };
+        let params = JsonValue::object([(
+            "textDocument",
+            JsonValue::object([("uri", JsonValue::from(document.uri.as_str()))]),
+        )]);
+        self.notify("textDocument/didSave", params)
+    }
+    fn versioned_identifier(document: &Document) -> JsonValue {
+        JsonValue::object([
+            ("uri", JsonValue::from(document.uri.as_str())),
+            ("version", JsonValue::from(document.version)),
+        ])
+    }
+    // The text of a line as the server sees it: as last sent if the file is open, as saved otherwise
+    fn line_text(&self, path: &Path, line_idx: usize) -> String {
+        if let Some(document) = self.documents.get(path) {
+            return document.line(line_idx).unwrap_or_default().to_string();
+        }
+        read_to_string(path)
+            .ok()
+            .and_then(|text| text.lines().nth(line_idx).map(String::from))
+            .unwrap_or_default()
+    }
+    // The parameters identifying a position in a file
+    pub fn position_params(&self, path: &Path, (line_idx, char_idx): (usize, usize)) -> JsonValue {
+        let col = self.encoding.col(&self.line_text(path, line_idx), char_idx);
+        JsonValue::object([
+            (
+                "textDocument",
+                JsonValue::object([("uri", JsonValue::String(uri::from_path(path)))]),
+            ),
+            (
+                "position",
+                JsonValue::object([("line", JsonValue::from(line_idx)), ("character", JsonValue::from(col))]),
+            ),
+        ])
+    }
+    // Converts a position the server sent to a line and character column
+    pub fn char_location(&self, path: &Path, position: &JsonValue) -> Option<(usize, usize)> {
+        let line_idx = position.get("line")?.as_usize()?;
+        let col = position.get("character")?.as_usize()?;
+        Some((line_idx, self.encoding.char_idx(&self.line_text(path, line_idx), col)))
+    }
+    // endregion
+}
+
+impl Drop for LanguageClient {
+    fn drop(&mut self) {
+        let _ = self.request_with_timeout("shutdown", JsonValue::Null, SHUTDOWN_TIMEOUT);
+        let _ = self.notify("exit", JsonValue::Null);
+        // The server gets as long again to exit on its own
+        let deadline = Instant::now().checked_add(SHUTDOWN_TIMEOUT).unwrap_or_else(Instant::now);
+        while matches!(self.process.try_wait(), Ok(None)) && Instant::now() < deadline {
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+// Forwards the messages the server writes until it stops or the client is gone.
+// Each message is a JSON body preceded by headers, of which only its length matters.
+fn read_messages(stdout: ChildStdout, sender: &Sender<JsonValue>) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let mut content_length = None;
+        loop {
+            let mut header = String::new();
+            match reader.read_line(&mut header) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix(CONTENT_LENGTH_HEADER) {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+        let Some(content_length) = content_length else {
+            continue;
+        };
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        if let Ok(message) = JsonValue::parse(&String::from_utf8_lossy(&body)) {
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::super::mockserver::{self, MockRoot, ECHO_METHOD, HOVER_TEXT, NEVER_METHOD};
+    use super::{JsonValue, LanguageClient, PositionEncoding};
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn start_initializing(root: &MockRoot, prefers_utf8: bool) -> LanguageClient {
+        LanguageClient::start(&mockserver::command(prefers_utf8), root.path())
+            .unwrap_or_else(|err| panic!("the mock server didn't start: {err}"))
+    }
+    fn wait_until_initialized(client: &mut LanguageClient) {
+        let deadline = Instant::now().checked_add(WAIT).unwrap_or_else(Instant::now);
+        while client.poll_initialized() == Ok(false) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(client.poll_initialized(), Ok(true));
+    }
+    // A client whose server is done initializing
+    fn start(root: &MockRoot, prefers_utf8: bool) -> LanguageClient {
+        let mut client = start_initializing(root, prefers_utf8);
+        wait_until_initialized(&mut client);
+        client
+    }
+    // The notifications the server got, as it sent them back, once there are as many as expected
+    fn received(client: &mut LanguageClient, count: usize) -> Vec<JsonValue> {
+        let mut notifications = Vec::new();
+        let deadline = Instant::now().checked_add(WAIT).unwrap_or_else(Instant::now);
+        while notifications.len() < count && Instant::now() < deadline {
+            notifications.extend(
+                client
+                    .take_notifications()
+                    .into_iter()
+                    .filter(|notification| notification.get("method").and_then(JsonValue::as_str) == Some(ECHO_METHOD))
+                    .filter_map(|notification| notification.get("params").cloned()),
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        notifications
+    }
+    fn method(message: &JsonValue) -> Option<&str> {
+        message.get("method").and_then(JsonValue::as_str)
+    }
+    fn at_path<'a>(message: &'a JsonValue, path: &[&str]) -> Option<&'a JsonValue> {
+        path.iter().try_fold(message, |value, key| value.get(key))
+    }
+
+    #[test]
+    fn picks_the_encoding_the_server_chose() {
+        let root = MockRoot::new("encoding");
+        let client = start(&root, true);
+        assert_eq!(client.encoding, PositionEncoding::Utf8);
+        assert!(client.incremental);
+        drop(client);
+        // Servers which don't choose count in UTF-16
+        let client = start(&root, false);
+        assert_eq!(client.encoding, PositionEncoding::Utf16);
+        drop(client);
+        let methods = root.logged_methods();
+        assert_eq!(methods.get(..2), Some(&[String::from("initialize"), String::from("initialized")][..]));
+    }
+
+    #[test]
+    fn starts_without_waiting_for_the_server() {
+        let root = MockRoot::new("starting");
+        let mut client = start_initializing(&root, false);
+        // The answer to initialize is only looked at when polled for
+        assert!(client.initializing.is_some());
+        wait_until_initialized(&mut client);
+        assert!(client.initializing.is_none());
+        drop(client);
+        let methods = root.logged_methods();
+        assert_eq!(methods.get(..2), Some(&[String::from("initialize"), String::from("initialized")][..]));
+    }
+
+    #[test]
+    fn sends_versioned_changes() {
+        let root = MockRoot::new("changes");
+        let mut client = start(&root, false);
+        let path = root.path().join("main.rs");
+        let lines = vec![String::from("fn main() {"), String::from("}")];
+        assert_eq!(client.open(&path, "rust", 3, lines), Ok(()));
+        assert_eq!(client.synced_revision(&path), Some(3));
+        assert_eq!(client.change(&path, 4, 1..1, vec![String::from("    x😀")]), Ok(()));
+        assert_eq!(client.change(&path, 5, 1..2, vec![String::from("    y😀")]), Ok(()));
+        // Nothing changed, nothing is sent
+        assert_eq!(client.change(&path, 6, 0..1, vec![String::from("fn main() {")]), Ok(()));
+        assert_eq!(client.synced_revision(&path), Some(6));
+
+        let notifications = received(&mut client, 4);
+        let methods: Vec<_> = notifications.iter().filter_map(method).collect();
+        assert_eq!(methods, ["initialized", "textDocument/didOpen", "textDocument/didChange", "textDocument/didChange"]);
+        let open = notifications.get(1).and_then(|open| at_path(open, &["params", "textDocument"]));
+        assert_eq!(open.and_then(|document| document.get("version")).and_then(JsonValue::as_usize), Some(0));
+        assert_eq!(open.and_then(|document| document.get("text")).and_then(JsonValue::as_str), Some("fn main() {\n}\n"));
+        let versions: Vec<_> = notifications
+            .iter()
+            .skip(2)
+            .filter_map(|change| at_path(change, &["params", "textDocument", "version"]))
+            .filter_map(JsonValue::as_usize)
+            .collect();
+        assert_eq!(versions, [1, 2]);
+        let change = notifications
+            .get(3)
+            .and_then(|change| at_path(change, &["params", "contentChanges"]))
+            .and_then(|changes| changes.as_array().first())
+            .cloned()
+            .unwrap_or(JsonValue::Null);
+        let position = |key: &str| {
+            let position = at_path(&change, &["range", key]);
+            (
+                position.and_then(|position| position.get("line")).and_then(JsonValue::as_usize),
+                position.and_then(|position| position.get("character")).and_then(JsonValue::as_usize),
+            )
+        };
+        assert_eq!(position("start"), (Some(1), Some(4)));
+        assert_eq!(position("end"), (Some(1), Some(5)));
+        assert_eq!(change.get("text").and_then(JsonValue::as_str), Some("y"));
+    }
+
+    #[test]
+    fn shuts_the_server_down_when_dropped() {
+        let root = MockRoot::new("shutdown");
+        let client = start(&root, false);
+        drop(client);
+        let methods = root.logged_methods();
+        assert_eq!(methods.get(methods.len().saturating_sub(2)..), Some(&[String::from("shutdown"), String::from("exit")][..]));
+    }
+
+    #[test]
+    fn times_out_without_an_answer() {
+        let root = MockRoot::new("timeout");
+        let mut client = start(&root, false);
+        let start = Instant::now();
+        let result = client.request_with_timeout(NEVER_METHOD, JsonValue::Null, Duration::from_millis(200));
+        assert_eq!(result, Err(format!("{NEVER_METHOD}: no answer from the language server")));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        // Waiting in the background doesn't block
+        let id = client.send_request(NEVER_METHOD, JsonValue::Null);
+        assert_eq!(id.map(|id| client.take_answer(id)), Ok(None));
+    }
+
+    #[test]
+    fn takes_answers_without_blocking() {
+        let root = MockRoot::new("answers");
+        let mut client = start(&root, false);
+        let params = client.position_params(Path::new("main.rs"), (0, 0));
+        let id = client.send_request("textDocument/hover", params).unwrap_or_default();
+        let deadline = Instant::now().checked_add(WAIT).unwrap_or_else(Instant::now);
+        let mut answer = None;
+        while answer.is_none() && Instant::now() < deadline {
+            answer = client.take_answer(id);
+            thread::sleep(Duration::from_millis(10));
+        }
+        let contents = answer.and_then(Result::ok).and_then(|result| result.get("contents").cloned());
+        assert_eq!(contents.as_ref().and_then(JsonValue::as_str), Some(HOVER_TEXT));
+        // Answers are only taken once
+        assert!(client.take_answer(id).is_none());
+    }
+}
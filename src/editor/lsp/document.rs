@@ -0,0 +1,187 @@
+use std::ops::Range;
+
+use super::super::json::JsonValue;
+use super::PositionEncoding;
+
+// A file as the language server knows it: the text sent with the last notification about it.
+pub struct Document {
+    pub uri: String,
+    pub version: usize,
+    // The buffer revision the text is from
+    pub revision: usize,
+    lines: Vec<String>,
+}
+
+impl Document {
+    pub const fn new(uri: String, revision: usize, lines: Vec<String>) -> Self {
+        Self {
+            uri,
+            version: 0,
+            revision,
+            lines,
+        }
+    }
+
+    // Lines end with a newline, the way the buffer saves them
+    pub fn text(&self) -> String {
+        Self::join(&self.lines)
+    }
+    fn join(lines: &[String]) -> String {
+        lines.iter().fold(String::new(), |mut text, line| {
+            text.push_str(line);
+            text.push('\n');
+            text
+        })
+    }
+    pub fn line(&self, line_idx: usize) -> Option<&str> {
+        self.lines.get(line_idx).map(String::as_str)
+    }
+
+    // Replaces the given lines with new ones and returns the change to send: the range of the old text which
+    // differs, and what it was replaced with. Returns None if nothing changed.
+    pub fn update(
+        &mut self,
+        revision: usize,
+        old_lines: Range<usize>,
+        lines: Vec<String>,
+        encoding: PositionEncoding,
+    ) -> Option<JsonValue> {
+        self.revision = revision;
+        let end = old_lines.end.min(self.lines.len());
+        let start = old_lines.start.min(end);
+        let old_text = Self::join(self.lines.get(start..end).unwrap_or_default());
+        let new_text = Self::join(&lines);
+        self.lines.splice(start..end, lines);
+        if old_text == new_text {
+            return None;
+        }
+        self.version = self.version.saturating_add(1);
+        let prefix_len: usize = old_text
+            .chars()
+            .zip(new_text.chars())
+            .take_while(|(old, new)| old == new)
+            .map(|(old, _)| old.len_utf8())
+            .sum();
+        let max_suffix_len = old_text.len().min(new_text.len()).saturating_sub(prefix_len);
+        let suffix_len: usize = old_text
+            .chars()
+            .rev()
+            .zip(new_text.chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(old, _)| old.len_utf8())
+            .scan(0, |len: &mut usize, char_len| {
+                *len = len.saturating_add(char_len);
+                (*len <= max_suffix_len).then_some(char_len)
+            })
+            .sum();
+        let old_end = old_text.len().saturating_sub(suffix_len);
+        let new_end = new_text.len().saturating_sub(suffix_len);
+        Some(JsonValue::object([
+            (
+                "range",
+                JsonValue::object([
+                    ("start", Self::position_at(&old_text, start, prefix_len, encoding)),
+                    ("end", Self::position_at(&old_text, start, old_end, encoding)),
+                ]),
+            ),
+            ("text", JsonValue::from(new_text.get(prefix_len..new_end).unwrap_or_default())),
+        ]))
+    }
+
+    // The position of the given byte of the text, which starts at the given line
+    fn position_at(text: &str, first_line_idx: usize, byte_idx: usize, encoding: PositionEncoding) -> JsonValue {
+        let before = text.get(..byte_idx).unwrap_or_default();
+        let line_start = before.rfind('\n').map_or(0, |idx| idx.saturating_add(1));
+        JsonValue::object([
+            (
+                "line",
+                JsonValue::from(first_line_idx.saturating_add(before.matches('\n').count())),
+            ),
+            (
+                "character",
+                JsonValue::from(encoding.len(before.get(line_start..).unwrap_or_default())),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::json::JsonValue;
+    use super::{Document, PositionEncoding};
+
+    fn document(lines: &[&str]) -> Document {
+        Document::new(
+            String::from("file:///main.rs"),
+            0,
+            lines.iter().map(ToString::to_string).collect(),
+        )
+    }
+    fn strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+    // A line and a column
+    type Position = (usize, usize);
+
+    // The start and end of the change's range, and its text
+    fn range(change: Option<JsonValue>) -> Option<(Position, Position, String)> {
+        let change = change?;
+        let position = |key: &str| -> Option<Position> {
+            let position = change.get("range")?.get(key)?;
+            Some((position.get("line")?.as_usize()?, position.get("character")?.as_usize()?))
+        };
+        Some((position("start")?, position("end")?, change.get("text")?.as_str()?.to_string()))
+    }
+
+    #[test]
+    fn sends_the_part_of_a_line_which_changed() {
+        let mut document = document(&["fn main() {", "    let x = 1;", "}"]);
+        let change = document.update(1, 1..2, strings(&["    let xy = 1;"]), PositionEncoding::Utf16);
+        assert_eq!(range(change), Some(((1, 9), (1, 9), String::from("y"))));
+        assert_eq!(document.line(1), Some("    let xy = 1;"));
+        assert_eq!((document.version, document.revision), (1, 1));
+    }
+
+    #[test]
+    fn sends_inserted_and_deleted_lines() {
+        let mut document = document(&["a", "b", "c"]);
+        let change = document.update(1, 1..1, strings(&["x", "y"]), PositionEncoding::Utf16);
+        assert_eq!(range(change), Some(((1, 0), (1, 0), String::from("x\ny\n"))));
+        assert_eq!(document.text(), "a\nx\ny\nb\nc\n");
+        let change = document.update(2, 0..3, strings(&["a"]), PositionEncoding::Utf16);
+        assert_eq!(range(change), Some(((1, 0), (3, 0), String::new())));
+        assert_eq!(document.text(), "a\nb\nc\n");
+        // Up to the end of the text, after its last newline
+        let change = document.update(3, 2..3, Vec::new(), PositionEncoding::Utf16);
+        assert_eq!(range(change), Some(((2, 0), (3, 0), String::new())));
+        assert_eq!(document.text(), "a\nb\n");
+        assert_eq!(document.version, 3);
+    }
+
+    #[test]
+    fn counts_columns_in_the_encoding() {
+        let mut utf16 = document(&["x = \"😀a\""]);
+        let mut utf8 = document(&["x = \"😀a\""]);
+        let new_lines = strings(&["x = \"😀b\""]);
+        let change = utf16.update(1, 0..1, new_lines.clone(), PositionEncoding::Utf16);
+        assert_eq!(range(change), Some(((0, 7), (0, 8), String::from("b"))));
+        let change = utf8.update(1, 0..1, new_lines, PositionEncoding::Utf8);
+        assert_eq!(range(change), Some(((0, 9), (0, 10), String::from("b"))));
+    }
+
+    #[test]
+    fn sends_nothing_without_changes() {
+        let mut document = document(&["a", "b"]);
+        assert!(document.update(4, 0..1, strings(&["a"]), PositionEncoding::Utf16).is_none());
+        assert_eq!((document.version, document.revision), (0, 4));
+    }
+
+    #[test]
+    fn replaces_everything_past_the_end() {
+        let mut document = document(&["a", "b"]);
+        let change = document.update(1, 0..usize::MAX, strings(&["c"]), PositionEncoding::Utf16);
+        // The newline they end with is the same
+        assert_eq!(range(change), Some(((0, 0), (1, 1), String::from("c"))));
+        assert_eq!(document.text(), "c\n");
+    }
+}
@@ -0,0 +1,157 @@
+// A language server for the tests, played by the test binary itself: the command runs one of the ignored
+// tests below, which talk to the client over standard input and output like a real server would. Outside
+// of a directory set up by MockRoot, they do nothing.
+//
+// The server answers initialize and shutdown, hover requests with HOVER_TEXT, and never answers NEVER_METHOD.
+// It sends every notification it gets back as an ECHO_METHOD notification, and logs the method of every
+// message into LOG_FILE, so the tests can tell what the client sent even after it's gone.
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{stdin, stdout, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use super::super::json::JsonValue;
+
+pub const ECHO_METHOD: &str = "mock/received";
+pub const NEVER_METHOD: &str = "mock/never";
+pub const HOVER_TEXT: &str = "The documentation";
+const LOG_FILE: &str = "mock-lsp.log";
+const MARKER_FILE: &str = ".mock-lsp";
+
+// The command line starting the server, which picks UTF-8 positions if it's offered them or leaves
+// the default if not
+pub fn command(prefers_utf8: bool) -> Vec<String> {
+    let entry = if prefers_utf8 { "utf8_server" } else { "default_server" };
+    vec![
+        env::current_exe().map(|exe| exe.to_string_lossy().to_string()).unwrap_or_default(),
+        String::from("--exact"),
+        format!("{}::{entry}", module_path!().trim_start_matches("hecto::")),
+        String::from("--ignored"),
+        String::from("--nocapture"),
+        String::from("--quiet"),
+    ]
+}
+
+// The directory the server runs in, removed when done with
+pub struct MockRoot(PathBuf);
+
+impl MockRoot {
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("hecto-mock-lsp-{}-{name}", process::id()));
+        let _ = fs::create_dir_all(&path);
+        let _ = fs::write(path.join(MARKER_FILE), "");
+        Self(path)
+    }
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+    // The methods the server got, in order
+    pub fn logged_methods(&self) -> Vec<String> {
+        fs::read_to_string(self.0.join(LOG_FILE))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Drop for MockRoot {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+#[ignore = "run by the language server tests"]
+fn utf8_server() {
+    serve(true);
+}
+
+#[test]
+#[ignore = "run by the language server tests"]
+fn default_server() {
+    serve(false);
+}
+
+fn serve(prefers_utf8: bool) {
+    if !Path::new(MARKER_FILE).exists() {
+        return;
+    }
+    let mut input = BufReader::new(stdin());
+    while let Some(message) = read_message(&mut input) {
+        let method = message.get("method").and_then(JsonValue::as_str).unwrap_or_default();
+        log(method);
+        let Some(id) = message.get("id").cloned() else {
+            if method == "exit" {
+                return;
+            }
+            send(&JsonValue::object([
+                ("jsonrpc", JsonValue::from("2.0")),
+                ("method", JsonValue::from(ECHO_METHOD)),
+                ("params", message.clone()),
+            ]));
+            continue;
+        };
+        let result = match method {
+            "initialize" => initialize_result(&message, prefers_utf8),
+            "textDocument/hover" => JsonValue::object([("contents", JsonValue::from(HOVER_TEXT))]),
+            NEVER_METHOD => continue,
+            _ => JsonValue::Null,
+        };
+        send(&JsonValue::object([
+            ("jsonrpc", JsonValue::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+}
+
+fn initialize_result(message: &JsonValue, prefers_utf8: bool) -> JsonValue {
+    let offers_utf8 = message
+        .get("params")
+        .and_then(|params| params.get("capabilities"))
+        .and_then(|capabilities| capabilities.get("general"))
+        .and_then(|general| general.get("positionEncodings"))
+        .is_some_and(|encodings| encodings.as_array().iter().any(|encoding| encoding.as_str() == Some("utf-8")));
+    let mut capabilities = vec![(String::from("textDocumentSync"), JsonValue::from(2))];
+    if prefers_utf8 && offers_utf8 {
+        capabilities.push((String::from("positionEncoding"), JsonValue::from("utf-8")));
+    }
+    JsonValue::object([("capabilities", JsonValue::Object(capabilities))])
+}
+
+fn read_message(input: &mut impl BufRead) -> Option<JsonValue> {
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse().ok()?;
+        }
+    }
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body).ok()?;
+    JsonValue::parse(&String::from_utf8_lossy(&body)).ok()
+}
+
+fn send(message: &JsonValue) {
+    let body = message.to_string();
+    let mut output = stdout().lock();
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{body}", body.len());
+    let _ = output.flush();
+}
+
+fn log(method: &str) {
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(LOG_FILE) {
+        let _ = writeln!(file, "{method}");
+    }
+}
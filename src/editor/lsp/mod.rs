@@ -0,0 +1,449 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::canonicalize,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use super::json::JsonValue;
+use super::uicomponents::{BufferHandle, DiagnosticReport, FileType, Severity, TextEdit};
+
+mod client;
+use client::{LanguageClient, REQUEST_TIMEOUT};
+mod document;
+use document::Document;
+mod positionencoding;
+use positionencoding::PositionEncoding;
+mod uri;
+#[cfg(test)]
+mod mockserver;
+
+// CompletionItem.insertTextFormat of snippets
+const SNIPPET_FORMAT: usize = 2;
+
+// A file, and a line and character column in it
+type FileLocation = (PathBuf, (usize, usize));
+
+// A completion offered by a language server
+#[derive(Clone, Debug)]
+pub struct CompletionItem {
+    pub label: String,
+    // What the typed prefix is matched against
+    pub filter_text: String,
+    pub text: String,
+    pub is_snippet: bool,
+}
+
+// What a request is for, which tells how to read its answer
+#[derive(Clone, Copy, Debug)]
+enum RequestKind {
+    Hover,
+    Definition,
+    Completion,
+    Rename,
+}
+
+impl RequestKind {
+    const fn method(self) -> &'static str {
+        match self {
+            Self::Hover => "textDocument/hover",
+            Self::Definition => "textDocument/definition",
+            Self::Completion => "textDocument/completion",
+            Self::Rename => "textDocument/rename",
+        }
+    }
+}
+
+// The request the editor is waiting for an answer to
+struct PendingRequest {
+    // The file type of the server it was sent to
+    file_type: String,
+    id: usize,
+    kind: RequestKind,
+    sent: Instant,
+}
+
+// The answer to a request, see LanguageServers::take_answer
+pub enum Answer {
+    // The documentation of the symbol, if any
+    Hover(Option<String>),
+    Definition(Option<FileLocation>),
+    Completion(Vec<CompletionItem>),
+    // The edits for each file they apply to
+    Rename(Vec<(PathBuf, Vec<TextEdit>)>),
+}
+
+// The language servers configured with `language_server.<file type>` settings, see Config.
+// A server starts when the first file of its type is synced, and stops with the editor.
+#[derive(Default)]
+pub struct LanguageServers {
+    // The command line for each file type
+    commands: HashMap<String, Vec<String>>,
+    clients: HashMap<String, LanguageClient>,
+    // File types whose server didn't start, so that it isn't tried on every key press. It's tried again
+    // when asked for something, see client_for.
+    failed: Vec<String>,
+    // Requests are answered in the background, one at a time
+    pending: Option<PendingRequest>,
+}
+
+impl LanguageServers {
+    pub fn new(commands: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            commands,
+            clients: HashMap::new(),
+            failed: Vec::new(),
+            pending: None,
+        }
+    }
+    pub fn is_running(&self) -> bool {
+        !self.clients.is_empty()
+    }
+    // Whether a server is configured for the file type
+    pub fn handles(&self, file_type: FileType) -> bool {
+        self.commands.contains_key(&file_type.to_string())
+    }
+
+    // Returns the server for the buffer's file type, starting it if needed, and the buffer's canonical path.
+    // None while the server is initializing: nothing is sent to it until it's done, see sync.
+    fn client_for(&mut self, buffer: &BufferHandle) -> Result<Option<(&mut LanguageClient, PathBuf)>, String> {
+        let file_type = buffer.file_type().to_string();
+        let path = buffer
+            .path()
+            .and_then(|path| canonicalize(path).ok())
+            .ok_or_else(|| String::from("Save the file to use its language server."))?;
+        let command = self
+            .commands
+            .get(&file_type)
+            .ok_or_else(|| format!("No language server for {file_type} files."))?;
+        self.failed.retain(|failed| *failed != file_type);
+        if !self.clients.contains_key(&file_type) {
+            let root = env::current_dir().map_err(|err| err.to_string())?;
+            match LanguageClient::start(command, &root) {
+                Ok(client) => {
+                    self.clients.insert(file_type.clone(), client);
+                }
+                Err(err) => {
+                    self.failed.push(file_type);
+                    return Err(err);
+                }
+            }
+        }
+        let initialized = self
+            .clients
+            .get_mut(&file_type)
+            .ok_or_else(|| String::from("language server stopped"))?
+            .poll_initialized();
+        match initialized {
+            Ok(true) => Ok(self.clients.get_mut(&file_type).map(|client| (client, path))),
+            Ok(false) => Ok(None),
+            Err(err) => {
+                self.clients.remove(&file_type);
+                self.failed.push(file_type);
+                Err(err)
+            }
+        }
+    }
+    // Like client_for, for requests, which can't wait for the server to initialize
+    fn ready_client_for(&mut self, buffer: &BufferHandle) -> Result<(&mut LanguageClient, PathBuf), String> {
+        let file_type = buffer.file_type();
+        self.client_for(buffer)?
+            .ok_or_else(|| format!("The {file_type} language server is starting, try again in a moment."))
+    }
+
+    // region: documents
+    // Sends the servers what changed in the buffers they handle. Returns the first error, if any.
+    // Servers which fail are stopped, so that the error shows only once. Servers which are initializing
+    // get the buffers once they're done: until then, the buffers are left unsynced.
+    pub fn sync(&mut self, buffers: &[BufferHandle]) -> Option<String> {
+        let mut first_error = None;
+        for buffer in buffers {
+            let file_type = buffer.file_type();
+            let name = file_type.to_string();
+            if !self.commands.contains_key(&name) || self.failed.contains(&name) || buffer.path().is_none() {
+                continue;
+            }
+            let result = self.client_for(buffer).and_then(|client| {
+                let Some((client, path)) = client else {
+                    return Ok(());
+                };
+                let revision = buffer.revision();
+                match client.synced_revision(&path) {
+                    Some(synced) if synced == revision => Ok(()),
+                    // Only the edited lines are sent, unless the buffer no longer knows which they are
+                    Some(synced) => match buffer.edited_lines_since(synced) {
+                        Some((old_lines, lines)) => client.change(&path, revision, old_lines, lines),
+                        None => client.change(&path, revision, 0..usize::MAX, buffer.line_strings()),
+                    },
+                    None => client.open(&path, language_id(file_type), revision, buffer.line_strings()),
+                }
+            });
+            if let Err(err) = result {
+                if self.clients.remove(&name).is_some() {
+                    self.failed.push(name);
+                }
+                first_error.get_or_insert(err);
+            }
+        }
+        first_error
+    }
+    pub fn did_save(&mut self, buffer: &BufferHandle) {
+        if self.failed.contains(&buffer.file_type().to_string()) {
+            return;
+        }
+        if let Ok(Some((client, path))) = self.client_for(buffer) {
+            let _ = client.did_save(&path);
+        }
+    }
+    // Handles what the servers sent in the meantime. Returns the diagnostics of each file they reported on.
+    pub fn poll(&mut self) -> Vec<(PathBuf, Vec<DiagnosticReport>)> {
+        let mut reports = Vec::new();
+        for client in self.clients.values_mut() {
+            for notification in client.take_notifications() {
+                if notification.get("method").and_then(JsonValue::as_str) != Some("textDocument/publishDiagnostics") {
+                    continue;
+                }
+                if let Some(diagnostics) = notification
+                    .get("params")
+                    .and_then(|params| parse_diagnostics(client, params))
+                {
+                    reports.push(diagnostics);
+                }
+            }
+        }
+        reports
+    }
+    // endregion
+
+    // region: requests
+    // The requests only send the question. The answer is picked up by take_answer, which the editor polls
+    // for so that it keeps responding meanwhile. A new request replaces the one being waited for.
+
+    // The documentation of the symbol at the given line and character column
+    pub fn hover(&mut self, buffer: &BufferHandle, location: (usize, usize)) -> Result<(), String> {
+        let (client, path) = self.ready_client_for(buffer)?;
+        let params = client.position_params(&path, location);
+        self.send_request(buffer, RequestKind::Hover, params)
+    }
+    // Where the symbol at the given location is defined
+    pub fn definition(&mut self, buffer: &BufferHandle, location: (usize, usize)) -> Result<(), String> {
+        let (client, path) = self.ready_client_for(buffer)?;
+        let params = client.position_params(&path, location);
+        self.send_request(buffer, RequestKind::Definition, params)
+    }
+    pub fn completion(&mut self, buffer: &BufferHandle, location: (usize, usize)) -> Result<(), String> {
+        let (client, path) = self.ready_client_for(buffer)?;
+        let params = client.position_params(&path, location);
+        self.send_request(buffer, RequestKind::Completion, params)
+    }
+    // The edits renaming the symbol at the given location
+    pub fn rename(&mut self, buffer: &BufferHandle, location: (usize, usize), new_name: &str) -> Result<(), String> {
+        let (client, path) = self.ready_client_for(buffer)?;
+        let mut params = client.position_params(&path, location);
+        if let JsonValue::Object(members) = &mut params {
+            members.push((String::from("newName"), JsonValue::from(new_name)));
+        }
+        self.send_request(buffer, RequestKind::Rename, params)
+    }
+    fn send_request(&mut self, buffer: &BufferHandle, kind: RequestKind, params: JsonValue) -> Result<(), String> {
+        self.cancel();
+        let file_type = buffer.file_type().to_string();
+        let client = self
+            .clients
+            .get_mut(&file_type)
+            .ok_or_else(|| String::from("language server stopped"))?;
+        let id = client.send_request(kind.method(), params)?;
+        self.pending = Some(PendingRequest {
+            file_type,
+            id,
+            kind,
+            sent: Instant::now(),
+        });
+        Ok(())
+    }
+    pub const fn is_waiting(&self) -> bool {
+        self.pending.is_some()
+    }
+    // Stops waiting for the answer to the last request, and tells the server it's no longer needed.
+    pub fn cancel(&mut self) {
+        let Some(request) = self.pending.take() else {
+            return;
+        };
+        if let Some(client) = self.clients.get_mut(&request.file_type) {
+            let _ = client.notify("$/cancelRequest", JsonValue::object([("id", JsonValue::from(request.id))]));
+        }
+    }
+    // The answer to the last request once it's there, or an error if the server failed or took too long.
+    pub fn take_answer(&mut self) -> Option<Result<Answer, String>> {
+        let request = self.pending.as_ref()?;
+        let method = request.kind.method();
+        let answer = match self.clients.get_mut(&request.file_type) {
+            Some(client) => match client.take_answer(request.id) {
+                Some(Ok(result)) => Ok(parse_answer(client, request.kind, &result)),
+                Some(Err(err)) => Err(format!("{method}: {err}")),
+                None if request.sent.elapsed() > REQUEST_TIMEOUT => {
+                    Err(format!("{method}: no answer from the language server"))
+                }
+                None => return None,
+            },
+            None => Err(String::from("language server stopped")),
+        };
+        self.pending = None;
+        Some(answer)
+    }
+    // endregion
+}
+
+fn parse_answer(client: &LanguageClient, kind: RequestKind, result: &JsonValue) -> Answer {
+    match kind {
+        RequestKind::Hover => Answer::Hover(parse_hover(result)),
+        RequestKind::Definition => Answer::Definition(parse_definition(client, result)),
+        RequestKind::Completion => {
+            // A list of items, or a list with whether it's complete
+            let items = result.get("items").unwrap_or(result).as_array();
+            Answer::Completion(items.iter().filter_map(parse_completion_item).collect())
+        }
+        RequestKind::Rename => Answer::Rename(parse_workspace_edit(client, result)),
+    }
+}
+
+fn parse_hover(result: &JsonValue) -> Option<String> {
+    let text = result.get("contents").map(hover_text).unwrap_or_default();
+    // Markdown code fences are noise in plain text
+    let text: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect();
+    let text = text.join("\n");
+    (!text.trim().is_empty()).then_some(text)
+}
+
+fn parse_definition(client: &LanguageClient, result: &JsonValue) -> Option<FileLocation> {
+    // A location, a list of them, or a list of links
+    let target = match result {
+        JsonValue::Array(targets) => targets.first(),
+        JsonValue::Null => None,
+        _ => Some(result),
+    }?;
+    let uri = target.get("uri").or_else(|| target.get("targetUri"))?.as_str()?;
+    let range = target.get("range").or_else(|| target.get("targetSelectionRange"))?;
+    let path = uri::to_path(uri)?;
+    let path = canonicalize(&path).unwrap_or(path);
+    let location = client.char_location(&path, range.get("start")?)?;
+    Some((path, location))
+}
+
+fn parse_workspace_edit(client: &LanguageClient, result: &JsonValue) -> Vec<(PathBuf, Vec<TextEdit>)> {
+    let mut file_edits: Vec<(String, &JsonValue)> = Vec::new();
+    if let Some(JsonValue::Object(changes)) = result.get("changes") {
+        file_edits.extend(changes.iter().map(|(uri, edits)| (uri.clone(), edits)));
+    }
+    // Newer servers describe the edits per document. Creating, renaming and deleting files isn't supported.
+    for change in result.get("documentChanges").map_or(&[][..], JsonValue::as_array) {
+        if let (Some(uri), Some(edits)) = (
+            change
+                .get("textDocument")
+                .and_then(|document| document.get("uri"))
+                .and_then(JsonValue::as_str),
+            change.get("edits"),
+        ) {
+            file_edits.push((uri.to_string(), edits));
+        }
+    }
+    file_edits
+        .into_iter()
+        .filter_map(|(uri, edits)| {
+            let path = uri::to_path(&uri)?;
+            let path = canonicalize(&path).unwrap_or(path);
+            let edits = edits
+                .as_array()
+                .iter()
+                .filter_map(|edit| parse_text_edit(client, &path, edit))
+                .collect();
+            Some((path, edits))
+        })
+        .collect()
+}
+
+// The identifiers the protocol knows the file types by
+fn language_id(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Rust => "rust",
+        FileType::Toml => "toml",
+        FileType::Markdown => "markdown",
+        FileType::Json => "json",
+        FileType::Shell => "shellscript",
+        FileType::Text => "plaintext",
+    }
+}
+
+// Hover contents are markup, a plain or marked string, or a list of the latter
+fn hover_text(contents: &JsonValue) -> String {
+    match contents {
+        JsonValue::String(text) => text.clone(),
+        JsonValue::Array(parts) => parts.iter().map(hover_text).collect::<Vec<_>>().join("\n"),
+        _ => contents
+            .get("value")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+fn parse_completion_item(item: &JsonValue) -> Option<CompletionItem> {
+    let label = item.get("label")?.as_str()?.to_string();
+    let text = item
+        .get("textEdit")
+        .and_then(|edit| edit.get("newText"))
+        .or_else(|| item.get("insertText"))
+        .and_then(JsonValue::as_str)
+        .unwrap_or(&label)
+        .to_string();
+    Some(CompletionItem {
+        filter_text: item
+            .get("filterText")
+            .and_then(JsonValue::as_str)
+            .unwrap_or(&label)
+            .to_string(),
+        is_snippet: item.get("insertTextFormat").and_then(JsonValue::as_usize) == Some(SNIPPET_FORMAT),
+        label,
+        text,
+    })
+}
+
+fn parse_text_edit(client: &LanguageClient, path: &Path, edit: &JsonValue) -> Option<TextEdit> {
+    let range = edit.get("range")?;
+    Some(TextEdit {
+        start: client.char_location(path, range.get("start")?)?,
+        end: client.char_location(path, range.get("end")?)?,
+        new_text: edit.get("newText")?.as_str()?.to_string(),
+    })
+}
+
+fn parse_diagnostics(client: &LanguageClient, params: &JsonValue) -> Option<(PathBuf, Vec<DiagnosticReport>)> {
+    let path = uri::to_path(params.get("uri")?.as_str()?)?;
+    let path = canonicalize(&path).unwrap_or(path);
+    let reports = params
+        .get("diagnostics")?
+        .as_array()
+        .iter()
+        .filter_map(|diagnostic| {
+            let range = diagnostic.get("range")?;
+            // 1 is an error, 2 a warning, 3 and 4 information and hints. Servers leaving it out mean errors.
+            let severity = match diagnostic.get("severity").and_then(JsonValue::as_usize) {
+                Some(1) | None => Severity::Error,
+                Some(2) => Severity::Warning,
+                Some(_) => Severity::Info,
+            };
+            Some(DiagnosticReport {
+                path: path.clone(),
+                severity,
+                message: diagnostic.get("message")?.as_str()?.to_string(),
+                start: client.char_location(&path, range.get("start")?)?,
+                end: client.char_location(&path, range.get("end")?)?,
+            })
+        })
+        .collect();
+    Some((path, reports))
+}
@@ -0,0 +1,84 @@
+// How columns are counted on the wire. The client offers both, the server picks one while initializing
+// and defaults to UTF-16 if it doesn't. Columns are converted from and to characters, which Line
+// converts from and to graphemes.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "utf-8" => Some(Self::Utf8),
+            "utf-16" => Some(Self::Utf16),
+            _ => None,
+        }
+    }
+
+    const fn char_len(self, character: char) -> usize {
+        match self {
+            Self::Utf8 => character.len_utf8(),
+            Self::Utf16 => character.len_utf16(),
+        }
+    }
+    // The number of code units of the text
+    pub fn len(self, text: &str) -> usize {
+        text.chars()
+            .fold(0, |len, character| len.saturating_add(self.char_len(character)))
+    }
+    // The column of the given character of the line
+    pub fn col(self, line: &str, char_idx: usize) -> usize {
+        line.chars()
+            .take(char_idx)
+            .fold(0, |len, character| len.saturating_add(self.char_len(character)))
+    }
+    // The character at the given column of the line. A column inside of a character maps to that character,
+    // one past the end to the end of the line.
+    pub fn char_idx(self, line: &str, col: usize) -> usize {
+        let mut end: usize = 0;
+        line.chars()
+            .take_while(|character| {
+                end = end.saturating_add(self.char_len(*character));
+                end <= col
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PositionEncoding::{self, Utf16, Utf8};
+
+    // 😀 is outside the basic plane: 4 bytes, 2 UTF-16 code units
+    const LINE: &str = "aé😀b";
+
+    #[test]
+    fn counts_columns_in_code_units() {
+        let cols = |encoding: PositionEncoding| -> Vec<usize> { (0..=5).map(|char_idx| encoding.col(LINE, char_idx)).collect() };
+        assert_eq!(cols(Utf8), [0, 1, 3, 7, 8, 8]);
+        assert_eq!(cols(Utf16), [0, 1, 2, 4, 5, 5]);
+        assert_eq!(Utf8.len(LINE), 8);
+        assert_eq!(Utf16.len(LINE), 5);
+    }
+
+    #[test]
+    fn finds_the_character_at_a_column() {
+        let char_idxs = |encoding: PositionEncoding, cols: &[usize]| -> Vec<usize> {
+            cols.iter().map(|col| encoding.char_idx(LINE, *col)).collect()
+        };
+        assert_eq!(char_idxs(Utf8, &[0, 1, 3, 7, 8]), [0, 1, 2, 3, 4]);
+        assert_eq!(char_idxs(Utf16, &[0, 1, 2, 4, 5]), [0, 1, 2, 3, 4]);
+        // Columns inside of a character map to it, columns past the end to the end
+        assert_eq!(char_idxs(Utf8, &[2, 4, 6, 100]), [1, 2, 2, 4]);
+        assert_eq!(char_idxs(Utf16, &[3, 100]), [2, 4]);
+    }
+
+    #[test]
+    fn parses_the_names() {
+        assert_eq!(PositionEncoding::parse("utf-8"), Some(Utf8));
+        assert_eq!(PositionEncoding::parse("utf-16"), Some(Utf16));
+        assert_eq!(PositionEncoding::parse("utf-32"), None);
+    }
+}
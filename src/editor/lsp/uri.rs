@@ -0,0 +1,55 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+const FILE_SCHEME: &str = "file://";
+
+// Turns an absolute path into a file URI, percent-encoding everything but unreserved characters and separators.
+pub fn from_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from(FILE_SCHEME);
+    // Windows paths start with the drive letter
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'/' | b'-' | b'.' | b'_' | b'~' | b':') {
+            uri.push(char::from(byte));
+        } else {
+            let _ = write!(uri, "%{byte:02X}");
+        }
+    }
+    uri
+}
+
+// Returns the path of a file URI, or None for other schemes.
+pub fn to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix(FILE_SCHEME)?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = if byte == b'%' {
+            tail.get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        if let Some(decoded) = decoded {
+            bytes.push(decoded);
+            rest = tail.get(2..).unwrap_or_default();
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    let path = String::from_utf8_lossy(&bytes).into_owned();
+    // `/C:/dir` on Windows
+    if cfg!(windows) {
+        if let Some(path) = path.strip_prefix('/').filter(|path| path.get(1..2) == Some(":")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    Some(PathBuf::from(path))
+}
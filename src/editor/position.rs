@@ -1,7 +1,7 @@
 pub type Row = usize;
 pub type Col = usize;
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Eq, PartialEq)]
 pub struct Position {
     pub col: Col,
     pub row: Row,
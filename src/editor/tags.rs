@@ -0,0 +1,171 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::{canonicalize, read_to_string},
+    path::{Path, PathBuf},
+};
+
+const TAGS_FILE_NAME: &str = "tags";
+// Lines of the tags file describing the file itself rather than a symbol
+const PSEUDO_TAG_PREFIX: &str = "!_TAG_";
+// Separates the address from the extension fields
+const FIELDS_SEPARATOR: &str = ";\"";
+
+// Where a tag's definition is in its file
+#[derive(Clone, Debug)]
+enum TagAddress {
+    // Counting from 1
+    Line(usize),
+    // A search pattern matching the whole line or its start, unescaped and without delimiters nor anchors
+    Pattern {
+        text: String,
+        whole_line: bool,
+    },
+}
+
+// A symbol definition read from a tags file in the universal-ctags format:
+// `name<Tab>file<Tab>address;"<Tab>kind<Tab>...`, with the file relative to the tags file.
+#[derive(Clone, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub path: PathBuf,
+    address: TagAddress,
+    kind: Option<String>,
+}
+
+impl Tag {
+    // Returns the tags of the given name, from the tags file in the file's directory or the closest one above it.
+    // Files without a path look from the current directory.
+    pub fn find(file: Option<&Path>, name: &str) -> Result<Vec<Self>, String> {
+        let start = file
+            .and_then(|file| canonicalize(file).ok())
+            .and_then(|file| file.parent().map(Path::to_path_buf))
+            .or_else(|| env::current_dir().ok())
+            .ok_or_else(|| String::from("no directory to look for tags in"))?;
+        let tags_file = start
+            .ancestors()
+            .map(|dir| dir.join(TAGS_FILE_NAME))
+            .find(|path| path.is_file())
+            .ok_or_else(|| String::from("No tags file found."))?;
+        let contents = read_to_string(&tags_file).map_err(|err| format!("{}: {err}", tags_file.display()))?;
+        let base_dir = tags_file.parent().unwrap_or(&start);
+        Ok(contents
+            .lines()
+            .filter(|line| !line.starts_with(PSEUDO_TAG_PREFIX))
+            .filter(|line| {
+                line.strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with('\t'))
+            })
+            .filter_map(|line| Self::parse(line, base_dir))
+            .collect())
+    }
+
+    fn parse(line: &str, base_dir: &Path) -> Option<Self> {
+        let mut parts = line.splitn(3, '\t');
+        let name = parts.next()?;
+        let file = parts.next()?;
+        let rest = parts.next()?;
+        let (address, fields) = Self::parse_address(rest)?;
+        // The kind is the first field without a key, or the `kind:` field
+        let kind = fields
+            .split('\t')
+            .filter(|field| !field.is_empty())
+            .find_map(|field| match field.split_once(':') {
+                None => Some(field),
+                Some(("kind", kind)) => Some(kind),
+                Some(_) => None,
+            })
+            .map(String::from);
+        Some(Self {
+            name: name.to_string(),
+            path: base_dir.join(file),
+            address,
+            kind,
+        })
+    }
+
+    // Splits the address from the fields following it. Patterns may contain tabs, so this can't split on them.
+    fn parse_address(rest: &str) -> Option<(TagAddress, &str)> {
+        let Some(delimiter) = rest.chars().next().filter(|ch| *ch == '/' || *ch == '?') else {
+            let (number, fields) = rest.split_once(FIELDS_SEPARATOR).unwrap_or((rest, ""));
+            return Some((TagAddress::Line(number.trim().parse().ok()?), fields));
+        };
+        let mut text = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        let end = loop {
+            match chars.next()? {
+                (_, '\\') => match chars.next()? {
+                    (_, escaped) if escaped == delimiter || escaped == '\\' => text.push(escaped),
+                    (_, escaped) => {
+                        text.push('\\');
+                        text.push(escaped);
+                    }
+                },
+                (idx, ch) if ch == delimiter => break idx,
+                (_, ch) => text.push(ch),
+            }
+        };
+        let fields = rest
+            .get(end.saturating_add(1)..)
+            .and_then(|after| after.strip_prefix(FIELDS_SEPARATOR))
+            .unwrap_or_default();
+        let text = text.strip_prefix('^').map_or(text.as_str(), |text| text);
+        let (text, whole_line) = text
+            .strip_suffix('$')
+            .map_or((text, false), |text| (text, true));
+        Some((
+            TagAddress::Pattern {
+                text: text.to_string(),
+                whole_line,
+            },
+            fields,
+        ))
+    }
+
+    // The line and character column of the definition in the given text, at the tag name if it's on the line.
+    // Patterns which no longer match anything lead to the top of the file.
+    pub fn locate(&self, lines: &[String]) -> (usize, usize) {
+        let line_idx = match &self.address {
+            TagAddress::Line(line) => line.saturating_sub(1),
+            TagAddress::Pattern { text, whole_line } => lines
+                .iter()
+                .position(|line| {
+                    if *whole_line {
+                        line == text
+                    } else {
+                        line.starts_with(text.as_str())
+                    }
+                })
+                .unwrap_or(0),
+        };
+        let char_idx = lines
+            .get(line_idx)
+            .and_then(|line| {
+                line.find(&self.name)
+                    .and_then(|byte_idx| line.get(..byte_idx))
+            })
+            .map_or(0, |before| before.chars().count());
+        (line_idx, char_idx)
+    }
+}
+
+// Describes the tag for the picker
+impl Display for Tag {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let path = env::current_dir()
+            .ok()
+            .and_then(|dir| self.path.strip_prefix(dir).ok())
+            .unwrap_or(&self.path);
+        write!(formatter, "{}", path.display())?;
+        if let TagAddress::Line(line) = self.address {
+            write!(formatter, ":{line}")?;
+        }
+        if let Some(kind) = &self.kind {
+            write!(formatter, " ({kind})")?;
+        }
+        if let TagAddress::Pattern { text, .. } = &self.address {
+            write!(formatter, " {}", text.trim())?;
+        }
+        Ok(())
+    }
+}
@@ -1,42 +1,66 @@
 use crossterm::style::Color;
 
-use crate::editor::annotatedstring::AnnotationType;
-
+// How text gets printed. Colors left out keep those of the text beneath.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
 pub struct Attribute {
     pub foreground: Option<Color>,
     pub background: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
 }
 
-impl From<AnnotationType> for Attribute {
-
-    fn from(annotation_type: AnnotationType) -> Self {
-        match annotation_type {
-            AnnotationType::Match => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 255,
-                }),
-                background: Some(Color::Rgb {
-                    r: 100,
-                    g: 100,
-                    b: 100,
-                }),
-            },
-            AnnotationType::SelectedMatch => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 255,
-                }),
-                background: Some(Color::Rgb {
-                    r: 255,
-                    g: 251,
-                    b: 0,
-                }),
-            },
+impl Attribute {
+    // Combines this attribute with the one of the text beneath it, whose colors show where this one has none.
+    pub fn over(self, beneath: Self) -> Self {
+        Self {
+            foreground: self.foreground.or(beneath.foreground),
+            background: self.background.or(beneath.background),
+            bold: self.bold || beneath.bold,
+            italic: self.italic || beneath.italic,
+            underline: self.underline || beneath.underline,
         }
     }
-}
 
+    // Parses a style like `#d4d4d4 on #1e1e1e bold`: an optional foreground color, an optional
+    // background color following `on`, and any of bold, italic and underline.
+    // Colors are either `#rrggbb` or a name like `dark_grey`, `default` keeps the terminal's own.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut attribute = Self::default();
+        let mut words = value.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "bold" => attribute.bold = true,
+                "italic" => attribute.italic = true,
+                "underline" => attribute.underline = true,
+                "on" => {
+                    let color = words
+                        .next()
+                        .ok_or_else(|| String::from("expected a color after `on`"))?;
+                    attribute.background = Self::parse_color(color)?;
+                }
+                _ => attribute.foreground = Self::parse_color(word)?,
+            }
+        }
+        Ok(attribute)
+    }
 
+    fn parse_color(value: &str) -> Result<Option<Color>, String> {
+        if value == "default" {
+            return Ok(None);
+        }
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |range| {
+                hex.get(range)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            };
+            return match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Some(Color::Rgb { r, g, b })),
+                _ => Err(format!("expected a color like #1e1e1e, got `{value}`")),
+            };
+        }
+        Color::try_from(value)
+            .map(Some)
+            .map_err(|()| format!("unknown color `{value}`"))
+    }
+}
@@ -0,0 +1,105 @@
+use crossterm::style::Color;
+
+use super::super::config::ColorSupport;
+
+// The 16 basic colors, with the values xterm uses for them
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+// The levels of each channel in the 6x6x6 color cube of the 256 color palette, starting at index 16
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+const CUBE_START: u8 = 16;
+// The 24 shades of grey following the cube, from 8 to 238 in steps of 10
+const GREY_START: u8 = 232;
+const GREY_COUNT: u8 = 24;
+// How far apart the channels of a color must be for it not to count as a grey
+const MIN_COLORFULNESS: u8 = 40;
+
+// Returns the closest color the terminal can show, or None if it can't show any.
+pub fn convert(color_support: ColorSupport, color: Color) -> Option<Color> {
+    match (color_support, color) {
+        (ColorSupport::Monochrome, _) => None,
+        (ColorSupport::Ansi256, Color::Rgb { r, g, b }) => Some(Color::AnsiValue(nearest_ansi_value((r, g, b)))),
+        (ColorSupport::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_basic_color((r, g, b))),
+        (ColorSupport::Ansi16, Color::AnsiValue(value)) => Some(nearest_basic_color(ansi_value_to_rgb(value))),
+        // The basic colors are there in every palette
+        _ => Some(color),
+    }
+}
+
+fn distance(first: (u8, u8, u8), second: (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| u32::from(a.abs_diff(b)).saturating_pow(2);
+    channel(first.0, second.0)
+        .saturating_add(channel(first.1, second.1))
+        .saturating_add(channel(first.2, second.2))
+}
+
+// Colorful colors don't turn into greys, even if one is closer: syntax highlighting would be lost.
+fn nearest_basic_color(rgb: (u8, u8, u8)) -> Color {
+    let is_colorful = |(r, g, b): (u8, u8, u8)| r.max(g).max(b).saturating_sub(r.min(g).min(b)) >= MIN_COLORFULNESS;
+    BASIC_COLORS
+        .iter()
+        .filter(|(_, basic)| !is_colorful(rgb) || is_colorful(*basic))
+        .min_by_key(|(_, basic)| distance(rgb, *basic))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+// The closest entry of the color cube or the grey ramp
+fn nearest_ansi_value(rgb: (u8, u8, u8)) -> u8 {
+    let nearest_level = |channel: u8| -> u8 {
+        (0u8..6)
+            .min_by_key(|idx| {
+                CUBE_LEVELS
+                    .get(usize::from(*idx))
+                    .map_or(u8::MAX, |level| level.abs_diff(channel))
+            })
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (nearest_level(rgb.0), nearest_level(rgb.1), nearest_level(rgb.2));
+    let cube_value = CUBE_START
+        .saturating_add(r.saturating_mul(36))
+        .saturating_add(g.saturating_mul(6))
+        .saturating_add(b);
+    let grey_value = (0..GREY_COUNT)
+        .map(|idx| GREY_START.saturating_add(idx))
+        .min_by_key(|value| distance(rgb, ansi_value_to_rgb(*value)))
+        .unwrap_or(GREY_START);
+    if distance(rgb, ansi_value_to_rgb(grey_value)) < distance(rgb, ansi_value_to_rgb(cube_value)) {
+        grey_value
+    } else {
+        cube_value
+    }
+}
+
+fn ansi_value_to_rgb(value: u8) -> (u8, u8, u8) {
+    if let Some(grey_idx) = value.checked_sub(GREY_START) {
+        let level = grey_idx.saturating_mul(10).saturating_add(8);
+        return (level, level, level);
+    }
+    let Some(cube_idx) = value.checked_sub(CUBE_START) else {
+        return BASIC_COLORS
+            .get(usize::from(value))
+            .map_or((0, 0, 0), |(_, rgb)| *rgb);
+    };
+    let level = |idx: u8| CUBE_LEVELS.get(usize::from(idx)).copied().unwrap_or(0);
+    (
+        level(cube_idx.checked_div(36).unwrap_or(0)),
+        level(cube_idx.checked_div(6).unwrap_or(0).checked_rem(6).unwrap_or(0)),
+        level(cube_idx.checked_rem(6).unwrap_or(0)),
+    )
+}
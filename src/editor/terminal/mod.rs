@@ -1,20 +1,32 @@
 mod attribute;
 use attribute::Attribute;
+mod colorsupport;
+mod theme;
+pub use theme::Theme;
 use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::style::{
-    Attribute::{Reset, Reverse},
-    Print, ResetColor, SetBackgroundColor, SetForegroundColor,
+    Attribute::{Bold, Italic, Reset, Reverse, Underlined},
+    Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
 };
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, size, Clear, ClearType, DisableLineWrap, EnableLineWrap,
     EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
 };
 use crossterm::{queue, Command};
+use std::cell::{Cell, RefCell};
 use std::io::{stdout, Error, Write};
 
-use super::AnnotatedString;
+use super::config::ColorSupport;
+use super::{AnnotatedString, AnnotationType};
 use super::{Position, Size};
 
+thread_local! {
+    // Everything is printed in the colors of this theme. Like the terminal itself, there is only one.
+    static THEME: RefCell<Theme> = RefCell::new(Theme::default());
+    static COLOR_SUPPORT: Cell<ColorSupport> = Cell::new(ColorSupport::default());
+}
+
 /// Represents the Terminal.
 /// Edge Case for platforms where `usize` < `u16`:
 /// Regardless of the actual size of the Terminal, this representation
@@ -26,6 +38,7 @@ pub struct Terminal;
 impl Terminal {
 
     pub fn terminate() -> Result<(), Error> {
+        Self::disable_mouse_capture()?;
         Self::leave_alternate_screen()?;
         Self::enable_line_wrap()?;
         Self::show_caret()?;
@@ -38,20 +51,33 @@ impl Terminal {
         enable_raw_mode()?;
         Self::enter_alternate_screen()?;
         Self::disable_line_wrap()?;
+        Self::enable_mouse_capture()?;
         Self::clear_screen()?;
         Self::execute()?;
         Ok(())
     }
 
+    pub fn set_theme(theme: Theme) {
+        THEME.with(|current| *current.borrow_mut() = theme);
+    }
+    pub fn set_color_support(color_support: ColorSupport) {
+        COLOR_SUPPORT.with(|current| current.set(color_support));
+    }
+    pub fn theme_name() -> String {
+        THEME.with(|theme| theme.borrow().name().to_string())
+    }
+    fn style(annotation_types: &[AnnotationType]) -> Attribute {
+        THEME.with(|theme| theme.borrow().style(annotation_types))
+    }
+
+    // Clearing fills with the current background color, so it's set to the one of the text first.
     pub fn clear_screen() -> Result<(), Error> {
+        Self::set_attribute(&[])?;
         Self::queue_command(Clear(ClearType::All))?;
+        Self::reset_attribute()?;
         Ok(())
     }
 
-    pub fn clear_line() -> Result<(), Error> {
-        Self::queue_command(Clear(ClearType::CurrentLine))?;
-        Ok(())
-    }
 
     /// Moves the caret to the given Position.
     /// # Arguments
@@ -88,6 +114,16 @@ impl Terminal {
         Ok(())
     }
 
+    pub fn enable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(EnableMouseCapture)?;
+        Ok(())
+    }
+
+    pub fn disable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(DisableMouseCapture)?;
+        Ok(())
+    }
+
     pub fn set_title(title: &str) -> Result<(), Error> {
         Self::queue_command(SetTitle(title))?;
         Ok(())
@@ -99,49 +135,96 @@ impl Terminal {
     }
 
     pub fn print(string: &str) -> Result<(), Error> {
+        Self::print_styled(string, &[])
+    }
+
+    // The annotations go from the one beneath the others to the topmost, see Theme::style
+    fn print_styled(string: &str, annotation_types: &[AnnotationType]) -> Result<(), Error> {
+        Self::set_attribute(annotation_types)?;
         Self::queue_command(Print(string))?;
+        Self::reset_attribute()?;
         Ok(())
     }
 
-    pub fn print_inverted_row(row: usize, line_text: &str) -> Result<(), Error> {
+    // Prints the text across the whole row, in the style of the given user interface part.
+    pub fn print_styled_row(row: usize, line_text: &str, annotation_type: AnnotationType) -> Result<(), Error> {
         let width = Self::size()?.width;
-        Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}"))
+        Self::move_caret_to(Position { row, col: 0 })?;
+        Self::print_styled(&format!("{line_text:width$.width$}"), &[annotation_type])
     }
 
-    pub fn print_row(row: usize, line_text: &str) -> Result<(), Error> {
-        Self::move_caret_to(Position { row, col: 0 })?;
-        Self::clear_line()?;
-        Self::print(line_text)?;
+    // Prints the text at the given position, without clearing the rest of the row.
+    pub fn print_at(position: Position, text: &str) -> Result<(), Error> {
+        Self::move_caret_to(position)?;
+        Self::print(text)?;
         Ok(())
     }
 
-    pub fn print_annotated_row(row: usize, annotated_string: &AnnotatedString) -> Result<(), Error> {
-        Self::move_caret_to(Position{row, col: 0})?;
-        Self::clear_line()?;
+    pub fn print_styled_at(position: Position, text: &str, annotation_type: AnnotationType) -> Result<(), Error> {
+        Self::move_caret_to(position)?;
+        Self::print_styled(text, &[annotation_type])
+    }
+
+    // Prints the annotated string at the given position, without clearing the rest of the row.
+    pub fn print_annotated_at(position: Position, annotated_string: &AnnotatedString) -> Result<(), Error> {
+        Self::move_caret_to(position)?;
+        Self::print_annotated(annotated_string)
+    }
+
+    fn print_annotated(annotated_string: &AnnotatedString) -> Result<(), Error> {
         annotated_string.into_iter()
-                        .try_for_each(|part| -> Result<(), Error> {
-                            if let Some(annotation_type) = part.annotation_type {
-                                let attribute: Attribute = annotation_type.into();
-                                Self::set_attribute(&attribute)?;
-                            }
-                            Self::print(part.string)?;
-                            Self::reset_color()?;
-                            Ok(())
-                        })?;
+                        .try_for_each(|part| Self::print_styled(part.string, &part.annotation_types))?;
         Ok(())
     }
 
-    fn set_attribute(attribute: &Attribute) -> Result<(), Error> {
-        if let Some(foreground_color) = attribute.foreground {
+    // Colors are brought down to what the terminal supports. Without any, see monochrome_attributes.
+    fn set_attribute(annotation_types: &[AnnotationType]) -> Result<(), Error> {
+        let attribute = Self::style(annotation_types);
+        let color_support = COLOR_SUPPORT.with(Cell::get);
+        if let Some(foreground_color) = attribute.foreground.and_then(|color| colorsupport::convert(color_support, color)) {
             Self::queue_command(SetForegroundColor(foreground_color))?;
         }
-        if let Some(background_color) = attribute.background {
+        if let Some(background_color) = attribute.background.and_then(|color| colorsupport::convert(color_support, color)) {
             Self::queue_command(SetBackgroundColor(background_color))?;
         }
+        if color_support == ColorSupport::Monochrome {
+            for monochrome_attribute in annotation_types.iter().flat_map(|annotation_type| Self::monochrome_attributes(*annotation_type)) {
+                Self::queue_command(SetAttribute(*monochrome_attribute))?;
+            }
+        }
+        if attribute.bold {
+            Self::queue_command(SetAttribute(Bold))?;
+        }
+        if attribute.italic {
+            Self::queue_command(SetAttribute(Italic))?;
+        }
+        if attribute.underline {
+            Self::queue_command(SetAttribute(Underlined))?;
+        }
         Ok(())
     }
-    fn reset_color() -> Result<(), Error> {
-        Self::queue_command(ResetColor)?;
+    // What stands in for the colors without any: the selection, the matches and the bars are reversed, the
+    // selected match among them is bold and underlined instead. The tints beneath the text are left out.
+    const fn monochrome_attributes(annotation_type: AnnotationType) -> &'static [crossterm::style::Attribute] {
+        match annotation_type {
+            AnnotationType::Selection
+            | AnnotationType::Match
+            | AnnotationType::StatusBar
+            | AnnotationType::InactiveStatusBar
+            | AnnotationType::MessageBar
+            | AnnotationType::TabBar
+            | AnnotationType::Popup
+            | AnnotationType::PopupTitle => &[Reverse],
+            // Stand out from the reversed bar or popup around them
+            AnnotationType::SelectedMatch | AnnotationType::ActiveTab | AnnotationType::PopupSelection => {
+                &[Bold, Underlined]
+            }
+            _ => &[],
+        }
+    }
+    // Back to the terminal's own colors, without bold and the like
+    fn reset_attribute() -> Result<(), Error> {
+        Self::queue_command(SetAttribute(Reset))?;
         Ok(())
     }
 
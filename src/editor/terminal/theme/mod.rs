@@ -0,0 +1,181 @@
+use std::{collections::HashMap, fs::read_to_string};
+
+use crate::editor::config::config_dir;
+
+use super::super::AnnotationType;
+use super::Attribute;
+
+const THEME_DIR_NAME: &str = "themes";
+const THEME_EXTENSION: &str = "theme";
+// Shipped with the editor, the first one is the default
+const BUILT_IN_THEMES: [(&str, &str); 2] = [
+    ("dark", include_str!("dark.theme")),
+    ("light", include_str!("light.theme")),
+];
+// The key for the plain text, which everything else is drawn on top of
+const TEXT_KEY: &str = "text";
+const KEYS: [(&str, AnnotationType); 31] = [
+    ("match", AnnotationType::Match),
+    ("selected_match", AnnotationType::SelectedMatch),
+    ("selection", AnnotationType::Selection),
+    ("line_number", AnnotationType::LineNumber),
+    ("fold_summary", AnnotationType::FoldSummary),
+    ("whitespace", AnnotationType::Whitespace),
+    ("trailing_whitespace", AnnotationType::TrailingWhitespace),
+    ("keyword", AnnotationType::Keyword),
+    ("type", AnnotationType::Type),
+    ("string", AnnotationType::String),
+    ("number", AnnotationType::Number),
+    ("comment", AnnotationType::Comment),
+    ("punctuation", AnnotationType::Punctuation),
+    ("error", AnnotationType::Error),
+    ("warning", AnnotationType::Warning),
+    ("info", AnnotationType::Info),
+    ("git_added", AnnotationType::GitAdded),
+    ("git_modified", AnnotationType::GitModified),
+    ("git_deleted", AnnotationType::GitDeleted),
+    ("current_line", AnnotationType::CurrentLine),
+    ("ruler", AnnotationType::Ruler),
+    ("gutter", AnnotationType::Gutter),
+    ("status_bar", AnnotationType::StatusBar),
+    ("inactive_status_bar", AnnotationType::InactiveStatusBar),
+    ("message_bar", AnnotationType::MessageBar),
+    ("tab_bar", AnnotationType::TabBar),
+    ("active_tab", AnnotationType::ActiveTab),
+    ("popup", AnnotationType::Popup),
+    ("popup_selection", AnnotationType::PopupSelection),
+    ("popup_title", AnnotationType::PopupTitle),
+    ("pane_separator", AnnotationType::PaneSeparator),
+];
+
+// The colors and font styles of the text, the annotations and the user interface.
+// Themes are read from <config dir>/themes/<name>.theme, which may also replace the built-in ones.
+// Like the configuration file, they consist of `key = value` lines, see Attribute::parse for the values:
+//
+// # Start out with everything from the built-in light theme
+// inherit = light
+// text = #1e1e1e on #fdf6e3
+// keyword = #859900 bold
+// status_bar = #fdf6e3 on #586e75
+//
+// Anything a theme leaves out is taken from the built-in dark theme, unless it inherits from another one.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    name: String,
+    text: Attribute,
+    styles: HashMap<AnnotationType, Attribute>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let (name, contents) = BUILT_IN_THEMES[0];
+        Self::parse(name, contents, None).unwrap_or_else(|_| Self {
+            name: name.to_string(),
+            text: Attribute::default(),
+            styles: HashMap::new(),
+        })
+    }
+}
+
+impl Theme {
+    // Loads the theme from the config directory, or the built-in one by that name.
+    pub fn load(name: &str) -> Result<Self, String> {
+        let user_theme = config_dir().and_then(|dir| {
+            read_to_string(
+                dir.join(THEME_DIR_NAME)
+                    .join(name)
+                    .with_extension(THEME_EXTENSION),
+            )
+            .ok()
+        });
+        if let Some(contents) = user_theme {
+            return Self::parse(name, &contents, Some(Self::default()));
+        }
+        Self::built_in(name).ok_or_else(|| format!("unknown theme `{name}`"))
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN_THEMES
+            .iter()
+            .find(|(built_in_name, _)| *built_in_name == name)
+            .and_then(|(name, contents)| Self::parse(name, contents, None).ok())
+    }
+
+    // The names of all themes to choose from, in alphabetical order.
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> = BUILT_IN_THEMES
+            .iter()
+            .map(|(name, _)| (*name).to_string())
+            .collect();
+        if let Some(entries) = config_dir().and_then(|dir| dir.join(THEME_DIR_NAME).read_dir().ok()) {
+            names.extend(entries.filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != THEME_EXTENSION {
+                    return None;
+                }
+                path.file_stem()?.to_str().map(String::from)
+            }));
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // Parses the contents of a theme file on top of the given base theme. Fails on the first invalid line.
+    fn parse(name: &str, contents: &str, base: Option<Self>) -> Result<Self, String> {
+        let mut theme = base.unwrap_or_else(|| Self {
+            name: String::new(),
+            text: Attribute::default(),
+            styles: HashMap::new(),
+        });
+        theme.name = name.to_string();
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            line.split_once('=')
+                .ok_or_else(|| String::from("expected `key = value`"))
+                .and_then(|(key, value)| theme.set(key.trim(), value.trim()))
+                .map_err(|err| {
+                    format!("theme {name} line {}: {err}", line_idx.saturating_add(1))
+                })?;
+        }
+        Ok(theme)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if key == "inherit" {
+            let base = Self::built_in(value)
+                .ok_or_else(|| format!("can only inherit from a built-in theme, got `{value}`"))?;
+            self.text = base.text;
+            self.styles = base.styles;
+            return Ok(());
+        }
+        let attribute = Attribute::parse(value)?;
+        if key == TEXT_KEY {
+            self.text = attribute;
+            return Ok(());
+        }
+        let (_, annotation_type) = KEYS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .ok_or_else(|| format!("unknown key `{key}`"))?;
+        self.styles.insert(*annotation_type, attribute);
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    // The style of text under the given annotations, from the one beneath the others to the topmost: each one
+    // shows through wherever those above leave something out, and the plain text style beneath them all.
+    pub fn style(&self, annotation_types: &[AnnotationType]) -> Attribute {
+        annotation_types
+            .iter()
+            .rev()
+            .filter_map(|annotation_type| self.styles.get(annotation_type))
+            .fold(Attribute::default(), |above, beneath| above.over(*beneath))
+            .over(self.text)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{cmp::min, io::Error};
 
-use super::super::{command::Edit, Line, Size, Terminal};
+use super::super::{command::Edit, AnnotationType, Line, Position, Size, Terminal};
 use super::UIComponent;
 
 #[derive(Default)]
@@ -15,7 +15,13 @@ impl CommandBar {
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
             Edit::Insert(character) => self.value.append_char(character),
-            Edit::Delete | Edit::InsertNewline => {}
+            Edit::Delete
+            | Edit::InsertNewline
+            | Edit::BackTab
+            | Edit::Increment(_)
+            | Edit::IncrementSequence(_)
+            | Edit::Indent
+            | Edit::Outdent => {}
             Edit::DeleteBackward => self.value.delete_last(),
         }
         self.set_needs_redraw(true);
@@ -51,7 +57,7 @@ impl UIComponent for CommandBar {
     fn set_size(&mut self, size: Size) {
         self.size = size;
     }
-    fn draw(&mut self, origin: usize) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
         let area_for_value = self.size.width.saturating_sub(self.prompt.len()); //this is how much space there is between the right side of the prompt and the edge of the bar
         let value_end = self.value.width(); // we always want to show the left part of the value, therefore the end of the visible range we try to access will be equal to the full width
         let value_start = value_end.saturating_sub(area_for_value); //This should give us the start for the grapheme subrange we want to print out.
@@ -65,6 +71,6 @@ impl UIComponent for CommandBar {
         } else {
             String::new()
         };
-        Terminal::print_row(origin, &to_print)
+        Terminal::print_styled_row(origin.row, &to_print, AnnotationType::MessageBar)
     }
 }
@@ -0,0 +1,139 @@
+use std::{cmp::min, io::Error};
+
+use unicode_width::UnicodeWidthStr;
+
+use super::super::{AnnotationType, Position, Size, Terminal};
+use super::UIComponent;
+
+mod wordranking;
+pub use wordranking::WordRanking;
+
+const MAX_VISIBLE_ITEMS: usize = 8;
+
+// A list of completion candidates, drawn on top of the view right below (or above) the caret.
+#[derive(Default)]
+pub struct CompletionPopup {
+    words: Vec<String>,
+    selected_idx: usize,
+    scroll_offset: usize,
+    // The position of the start of the completed word on the terminal
+    anchor: Position,
+    visible: bool,
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl CompletionPopup {
+    pub fn show(&mut self, words: Vec<String>, anchor: Position) {
+        self.words = words;
+        self.anchor = anchor;
+        self.selected_idx = 0;
+        self.scroll_offset = 0;
+        self.visible = true;
+        self.set_needs_redraw(true);
+    }
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.words.clear();
+    }
+    pub const fn is_visible(&self) -> bool {
+        self.visible
+    }
+    pub fn selected_word(&self) -> Option<&str> {
+        self.words.get(self.selected_idx).map(String::as_str)
+    }
+    pub fn select_next(&mut self) {
+        if self.words.is_empty() {
+            return;
+        }
+        self.selected_idx = self
+            .selected_idx
+            .saturating_add(1)
+            .checked_rem(self.words.len())
+            .unwrap_or(0);
+        self.scroll_selection_into_view();
+    }
+    pub fn select_prev(&mut self) {
+        if self.words.is_empty() {
+            return;
+        }
+        self.selected_idx = self
+            .selected_idx
+            .checked_sub(1)
+            .unwrap_or_else(|| self.words.len().saturating_sub(1));
+        self.scroll_selection_into_view();
+    }
+    fn scroll_selection_into_view(&mut self) {
+        let height = self.visible_item_count();
+        if self.selected_idx < self.scroll_offset {
+            self.scroll_offset = self.selected_idx;
+        } else if self.selected_idx >= self.scroll_offset.saturating_add(height) {
+            self.scroll_offset = self.selected_idx.saturating_sub(height).saturating_add(1);
+        }
+        self.set_needs_redraw(true);
+    }
+    fn visible_item_count(&self) -> usize {
+        // Leave at least the caret row free
+        min(
+            min(self.words.len(), MAX_VISIBLE_ITEMS),
+            self.size.height.saturating_sub(1),
+        )
+    }
+}
+
+impl UIComponent for CompletionPopup {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        if !self.visible {
+            return Ok(());
+        }
+        let height = self.visible_item_count();
+        let width = min(
+            self.words
+                .iter()
+                .map(|word| word.width())
+                .max()
+                .unwrap_or(0)
+                .saturating_add(2), // one column of padding on each side
+            self.size.width,
+        );
+
+        // Below the caret if there is room for it, above otherwise
+        let top = if self.anchor.row.saturating_add(1).saturating_add(height) <= self.size.height {
+            self.anchor.row.saturating_add(1)
+        } else {
+            self.anchor.row.saturating_sub(height)
+        };
+        let left = min(self.anchor.col, self.size.width.saturating_sub(width));
+
+        for (idx, word) in self
+            .words
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(height)
+        {
+            let row = origin
+                .row
+                .saturating_add(top)
+                .saturating_add(idx.saturating_sub(self.scroll_offset));
+            let item = format!(" {word:<width$.width$}", width = width.saturating_sub(1));
+            let position = Position { row, col: left };
+            let annotation_type = if idx == self.selected_idx {
+                AnnotationType::PopupSelection
+            } else {
+                AnnotationType::Popup
+            };
+            Terminal::print_styled_at(position, &item, annotation_type)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+struct WordStats {
+    // Distance in lines to the caret. usize::MAX for words from other buffers.
+    distance: usize,
+    frequency: usize,
+}
+
+// Collects the words starting with a given prefix, and ranks them by proximity to the caret and frequency.
+pub struct WordRanking {
+    prefix: String,
+    words: HashMap<String, WordStats>,
+}
+
+impl WordRanking {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            words: HashMap::new(),
+        }
+    }
+
+    pub fn is_word_char(ch: char) -> bool {
+        ch.is_alphanumeric() || ch == '_'
+    }
+
+    // Adds all the words of a line which complete the prefix.
+    // `distance` is the number of lines between this line and the caret.
+    pub fn add_line(&mut self, line: &str, distance: usize) {
+        for word in line.split(|ch: char| !Self::is_word_char(ch)) {
+            if word.len() <= self.prefix.len() || !word.starts_with(&self.prefix) {
+                continue;
+            }
+            if let Some(stats) = self.words.get_mut(word) {
+                stats.distance = stats.distance.min(distance);
+                stats.frequency = stats.frequency.saturating_add(1);
+            } else {
+                self.words.insert(
+                    word.to_string(),
+                    WordStats {
+                        distance,
+                        frequency: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    // Returns the words, closest first. Words at the same distance are ordered by descending frequency, then alphabetically.
+    pub fn into_ranked(self) -> Vec<String> {
+        let mut words: Vec<(String, WordStats)> = self.words.into_iter().collect();
+        words.sort_by(|(left_word, left), (right_word, right)| {
+            left.distance
+                .cmp(&right.distance)
+                .then(right.frequency.cmp(&left.frequency))
+                .then(left_word.cmp(right_word))
+        });
+        words.into_iter().map(|(word, _)| word).collect()
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::Error;
+
+use super::super::{config::LineNumbers, AnnotatedString, AnnotationType, Position, Size, Terminal};
+use super::UIComponent;
+
+// Room for a sign (bookmarks and the like) and a change marker, which also separates it from the line number
+const SIGN_COLUMN_WIDTH: usize = 2;
+// Room for an abbreviated commit, author and date, and a space
+const BLAME_COLUMN_WIDTH: usize = 32;
+
+// What to show next to a row of text
+pub struct GutterRow {
+    pub line_idx: usize,
+    // Rows continuing a soft-wrapped line show neither number nor sign
+    pub is_continuation: bool,
+    // The sign and how to color it
+    pub sign: Option<(char, AnnotationType)>,
+    // The marker of the line's changes since the last commit, and how to color it
+    pub change: Option<(char, AnnotationType)>,
+    // Who last changed the line and when, see Blame::column_text
+    pub blame: Option<String>,
+}
+
+// The columns left of the text: the blame column if it's turned on, a sign column, shown only if there are
+// signs, and the line numbers.
+#[derive(Default)]
+pub struct Gutter {
+    line_numbers: LineNumbers,
+    show_signs: bool,
+    show_blame: bool,
+    caret_line_idx: usize,
+    rows: Vec<GutterRow>,
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl Gutter {
+    pub const fn line_numbers(&self) -> LineNumbers {
+        self.line_numbers
+    }
+    pub fn set_line_numbers(&mut self, line_numbers: LineNumbers) {
+        self.line_numbers = line_numbers;
+        self.set_needs_redraw(true);
+    }
+
+    // The width of the gutter for a buffer of the given height. Line numbers are as wide as the biggest one.
+    pub fn width(&self, line_count: usize, show_signs: bool, show_blame: bool) -> usize {
+        let sign_width = if show_signs { SIGN_COLUMN_WIDTH } else { 0 };
+        let blame_width = if show_blame { BLAME_COLUMN_WIDTH } else { 0 };
+        let number_width = match self.line_numbers {
+            LineNumbers::Off => 0,
+            LineNumbers::Absolute | LineNumbers::Relative => {
+                line_count.max(1).to_string().len().saturating_add(1)
+            }
+        };
+        blame_width.saturating_add(sign_width).saturating_add(number_width)
+    }
+
+    // Sets the rows to draw, from the top of the view.
+    pub fn set_rows(&mut self, rows: Vec<GutterRow>, caret_line_idx: usize, show_signs: bool, show_blame: bool) {
+        self.rows = rows;
+        self.caret_line_idx = caret_line_idx;
+        self.show_signs = show_signs;
+        self.show_blame = show_blame;
+        self.set_needs_redraw(true);
+    }
+
+    fn number(&self, line_idx: usize) -> usize {
+        match self.line_numbers {
+            LineNumbers::Relative if line_idx != self.caret_line_idx => {
+                line_idx.abs_diff(self.caret_line_idx)
+            }
+            _ => line_idx.saturating_add(1),
+        }
+    }
+}
+
+impl UIComponent for Gutter {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        let sign_width = if self.show_signs { SIGN_COLUMN_WIDTH } else { 0 };
+        let blame_width = if self.show_blame { BLAME_COLUMN_WIDTH } else { 0 };
+        let blame_text_width = blame_width.saturating_sub(1);
+        let number_width = self
+            .size
+            .width
+            .saturating_sub(blame_width)
+            .saturating_sub(sign_width)
+            .saturating_sub(1);
+        for (row, gutter_row) in self.rows.iter().enumerate().take(self.size.height) {
+            let at = Position {
+                row: origin.row.saturating_add(row),
+                col: origin.col,
+            };
+            let sign = gutter_row.sign.filter(|_| self.show_signs && !gutter_row.is_continuation);
+            let change = gutter_row.change.filter(|_| self.show_signs && !gutter_row.is_continuation);
+            let sign_text = if self.show_signs {
+                format!(
+                    "{}{}",
+                    sign.map_or(' ', |(sign, _)| sign),
+                    change.map_or(' ', |(marker, _)| marker)
+                )
+            } else {
+                String::new()
+            };
+            let blame_text = if self.show_blame {
+                let blame = gutter_row
+                    .blame
+                    .as_deref()
+                    .filter(|_| !gutter_row.is_continuation)
+                    .unwrap_or_default();
+                format!("{blame:<blame_text_width$.blame_text_width$} ")
+            } else {
+                String::new()
+            };
+            let number = match self.line_numbers {
+                LineNumbers::Off => String::new(),
+                _ if gutter_row.is_continuation => format!("{:number_width$} ", ""),
+                _ => format!("{:>number_width$} ", self.number(gutter_row.line_idx)),
+            };
+            let text = format!("{blame_text}{sign_text}{number}");
+            let signs_start = blame_text.len();
+            let signs_end = signs_start.saturating_add(sign_text.len());
+            let mut annotated = AnnotatedString::from(&text);
+            annotated.add_annotation(AnnotationType::Gutter, 0, signs_end);
+            if let Some((sign, annotation_type)) = sign {
+                annotated.add_annotation(annotation_type, signs_start, signs_start.saturating_add(sign.len_utf8()));
+            }
+            if let Some((marker, annotation_type)) = change {
+                annotated.add_annotation(annotation_type, signs_end.saturating_sub(marker.len_utf8()), signs_end);
+            }
+            annotated.add_annotation(AnnotationType::LineNumber, signs_end, text.len());
+            Terminal::print_annotated_at(at, &annotated)?;
+        }
+        Ok(())
+    }
+}
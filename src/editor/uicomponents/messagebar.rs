@@ -3,7 +3,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use super::super::{Size, Terminal};
+use super::super::{AnnotationType, Position, Size, Terminal};
 use super::UIComponent;
 
 const DEFAULT_DURATION: Duration = Duration::new(5, 0);
@@ -54,7 +54,7 @@ impl UIComponent for MessageBar {
         self.needs_redraw || (!self.cleared_after_expiry && self.current_message.is_expired())
     }
     fn set_size(&mut self, _: Size) {}
-    fn draw(&mut self, origin: usize) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
         if self.current_message.is_expired() {
             self.cleared_after_expiry = true;
         }
@@ -64,6 +64,6 @@ impl UIComponent for MessageBar {
             &self.current_message.text
         };
 
-        Terminal::print_row(origin, message)
+        Terminal::print_styled_row(origin.row, message, AnnotationType::MessageBar)
     }
 }
@@ -1,12 +1,22 @@
 mod commandbar;
+mod completionpopup;
+mod gutter;
 mod messagebar;
+mod picker;
 mod statusbar;
+mod tabbar;
 mod view;
 mod uicomponent;
 
 // Imports -> Re-export in public to make these files easier to use
 pub use commandbar::CommandBar;
+pub use completionpopup::{CompletionPopup, WordRanking};
+pub use gutter::{Gutter, GutterRow};
 pub use messagebar::MessageBar;
+pub use picker::Picker;
 pub use statusbar::StatusBar;
-pub use view::View;
+pub use tabbar::{TabBar, TabInfo};
+pub use view::{
+    BlameCommit, BlameLine, BufferHandle, DiagnosticReport, FileType, GotoTarget, Highlight, Severity, TextEdit, View,
+};
 pub use uicomponent::UIComponent;
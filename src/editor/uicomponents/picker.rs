@@ -0,0 +1,108 @@
+use std::{cmp::min, io::Error};
+
+use super::super::{AnnotationType, Position, Size, Terminal};
+use super::UIComponent;
+
+const MAX_VISIBLE_ITEMS: usize = 10;
+
+// A list to choose an entry from, drawn on top of the bottom rows of the view.
+#[derive(Default)]
+pub struct Picker {
+    title: String,
+    items: Vec<String>,
+    selected_idx: usize,
+    scroll_offset: usize,
+    visible: bool,
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl Picker {
+    pub fn show(&mut self, title: &str, items: Vec<String>) {
+        self.title = title.to_string();
+        self.items = items;
+        self.selected_idx = 0;
+        self.scroll_offset = 0;
+        self.visible = true;
+        self.set_needs_redraw(true);
+    }
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.items.clear();
+    }
+    pub const fn is_visible(&self) -> bool {
+        self.visible
+    }
+    pub const fn selected_idx(&self) -> usize {
+        self.selected_idx
+    }
+    pub fn select_next(&mut self, step: usize) {
+        self.selected_idx = min(
+            self.selected_idx.saturating_add(step),
+            self.items.len().saturating_sub(1),
+        );
+        self.scroll_selection_into_view();
+    }
+    pub fn select_prev(&mut self, step: usize) {
+        self.selected_idx = self.selected_idx.saturating_sub(step);
+        self.scroll_selection_into_view();
+    }
+    pub fn visible_item_count(&self) -> usize {
+        // One row is taken by the title
+        min(
+            min(self.items.len(), MAX_VISIBLE_ITEMS),
+            self.size.height.saturating_sub(1),
+        )
+    }
+    fn scroll_selection_into_view(&mut self) {
+        let height = self.visible_item_count();
+        if self.selected_idx < self.scroll_offset {
+            self.scroll_offset = self.selected_idx;
+        } else if self.selected_idx >= self.scroll_offset.saturating_add(height) {
+            self.scroll_offset = self.selected_idx.saturating_sub(height).saturating_add(1);
+        }
+        self.set_needs_redraw(true);
+    }
+}
+
+impl UIComponent for Picker {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        if !self.visible || self.size.height == 0 {
+            return Ok(());
+        }
+        let height = self.visible_item_count();
+        let title_row = origin
+            .row
+            .saturating_add(self.size.height)
+            .saturating_sub(height)
+            .saturating_sub(1);
+        Terminal::print_styled_row(title_row, &self.title, AnnotationType::PopupTitle)?;
+        for (idx, item) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(height)
+        {
+            let row = title_row
+                .saturating_add(1)
+                .saturating_add(idx.saturating_sub(self.scroll_offset));
+            let (marker, annotation_type) = if idx == self.selected_idx {
+                ('>', AnnotationType::PopupSelection)
+            } else {
+                (' ', AnnotationType::Popup)
+            };
+            Terminal::print_styled_row(row, &format!("{marker} {item}"), annotation_type)?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::io::Error;
 
-use super::super::{DocumentStatus, Size, Terminal};
+use super::super::{AnnotationType, DocumentStatus, Position, Size, Terminal};
 use super::UIComponent;
 
 #[derive(Default)]
@@ -8,6 +8,8 @@ pub struct StatusBar {
     current_status: DocumentStatus,
     needs_redraw: bool,
     size: Size,
+    // Only the status bar of the focused pane is inverted
+    is_focused: bool,
 }
 
 impl StatusBar {
@@ -17,7 +19,12 @@ impl StatusBar {
             self.set_needs_redraw(true);
         }
     }
-
+    pub fn set_focused(&mut self, is_focused: bool) {
+        if is_focused != self.is_focused {
+            self.is_focused = is_focused;
+            self.set_needs_redraw(true);
+        }
+    }
 }
 
 impl UIComponent for StatusBar {
@@ -30,7 +37,7 @@ impl UIComponent for StatusBar {
     fn set_size(&mut self, size: Size) {
         self.size = size;
     }
-    fn draw(&mut self, origin_row: usize) -> Result<(), Error> {
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
         //Assemble the first part of the status bar
         let line_count = self.current_status.line_count_to_string();
         let modified_indicator = self.current_status.modified_indicator_to_string();
@@ -51,8 +58,13 @@ impl UIComponent for StatusBar {
         } else {
             String::new()
         };
-        Terminal::print_inverted_row(origin_row, &to_print)?;
-
-        Ok(())
+        let width = self.size.width;
+        let to_print = format!("{to_print:width$}");
+        let annotation_type = if self.is_focused {
+            AnnotationType::StatusBar
+        } else {
+            AnnotationType::InactiveStatusBar
+        };
+        Terminal::print_styled_at(origin, &to_print, annotation_type)
     }
 }
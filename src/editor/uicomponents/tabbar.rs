@@ -0,0 +1,131 @@
+use std::{io::Error, ops::Range};
+
+use unicode_width::UnicodeWidthStr;
+
+use super::super::{AnnotationType, Position, Size, Terminal};
+use super::UIComponent;
+
+const MODIFIED_MARKER: &str = "*";
+// Shown at the edges when there are more tabs than fit
+const MORE_LEFT: &str = "<";
+const MORE_RIGHT: &str = ">";
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct TabInfo {
+    pub name: String,
+    pub is_modified: bool,
+}
+
+impl TabInfo {
+    fn label(&self) -> String {
+        let marker = if self.is_modified { MODIFIED_MARKER } else { "" };
+        format!(" {}{marker} ", self.name)
+    }
+}
+
+// One tab per open buffer on a single row, with the active one highlighted.
+// Scrolls sideways to keep the active tab visible.
+#[derive(Default)]
+pub struct TabBar {
+    tabs: Vec<TabInfo>,
+    active_idx: usize,
+    // The columns each drawn tab covers, to find the one which was clicked
+    drawn_tabs: Vec<(usize, Range<usize>)>,
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl TabBar {
+    pub fn set_tabs(&mut self, tabs: Vec<TabInfo>, active_idx: usize) {
+        if tabs != self.tabs || active_idx != self.active_idx {
+            self.tabs = tabs;
+            self.active_idx = active_idx;
+            self.set_needs_redraw(true);
+        }
+    }
+    // Returns the index of the tab drawn at the given column, if any.
+    pub fn tab_at(&self, col: usize) -> Option<usize> {
+        self.drawn_tabs
+            .iter()
+            .find(|(_, cols)| cols.contains(&col))
+            .map(|(idx, _)| *idx)
+    }
+    // The leftmost tab to draw: the first one if everything fits, otherwise as few as possible
+    // get hidden to the left while the active one stays visible.
+    fn first_visible_idx(&self) -> usize {
+        let widths: Vec<usize> = self.tabs.iter().map(|tab| tab.label().width()).collect();
+        if widths.iter().sum::<usize>() <= self.size.width {
+            return 0;
+        }
+        // Leave room for the markers on both sides
+        let available = self
+            .size
+            .width
+            .saturating_sub(MORE_LEFT.len())
+            .saturating_sub(MORE_RIGHT.len());
+        let mut first = self.active_idx;
+        let mut used = widths.get(self.active_idx).copied().unwrap_or(0);
+        while let Some(width) = first.checked_sub(1).and_then(|idx| widths.get(idx)) {
+            if used.saturating_add(*width) > available {
+                break;
+            }
+            used = used.saturating_add(*width);
+            first = first.saturating_sub(1);
+        }
+        first
+    }
+}
+
+impl UIComponent for TabBar {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        Terminal::print_styled_row(origin.row, "", AnnotationType::TabBar)?;
+        self.drawn_tabs.clear();
+        let first_idx = self.first_visible_idx();
+        let end_col = origin.col.saturating_add(self.size.width);
+        let mut col = origin.col;
+        if first_idx > 0 {
+            Terminal::print_styled_at(Position { row: origin.row, col }, MORE_LEFT, AnnotationType::TabBar)?;
+            col = col.saturating_add(MORE_LEFT.len());
+        }
+        for (idx, tab) in self.tabs.iter().enumerate().skip(first_idx) {
+            let is_last = idx.saturating_add(1) == self.tabs.len();
+            let room = if is_last {
+                end_col.saturating_sub(col)
+            } else {
+                end_col.saturating_sub(col).saturating_sub(MORE_RIGHT.len())
+            };
+            let label = tab.label();
+            let width = label.width();
+            if width > room {
+                // The active tab is cut rather than left out
+                if idx == self.active_idx && room > 0 {
+                    let cut: String = label.chars().take(room).collect();
+                    Terminal::print_styled_at(Position { row: origin.row, col }, &cut, AnnotationType::ActiveTab)?;
+                    self.drawn_tabs.push((idx, col..end_col));
+                } else {
+                    Terminal::print_styled_at(Position { row: origin.row, col }, MORE_RIGHT, AnnotationType::TabBar)?;
+                }
+                break;
+            }
+            let position = Position { row: origin.row, col };
+            let annotation_type = if idx == self.active_idx {
+                AnnotationType::ActiveTab
+            } else {
+                AnnotationType::TabBar
+            };
+            Terminal::print_styled_at(position, &label, annotation_type)?;
+            self.drawn_tabs.push((idx, col..col.saturating_add(width)));
+            col = col.saturating_add(width);
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::io::Error;
 
-use super::super::Size;
+use super::super::{Position, Size};
 
 pub trait UIComponent {
     // Marks this UI component as in need of redrawing (or not)
@@ -15,9 +15,9 @@ pub trait UIComponent {
     // Set the new size
     fn set_size(&mut self, size: Size);
     // Draw this component if it's visible and in need of redrawing
-    fn render(&mut self, origin_row: usize) {
+    fn render(&mut self, origin: Position) {
         if self.needs_redraw() {
-            if let Err(err) = self.draw(origin_row) {
+            if let Err(err) = self.draw(origin) {
                 #[cfg(debug_assertions)]
                 {
                     panic!("Could not render component: {err:?}");
@@ -31,6 +31,6 @@ pub trait UIComponent {
             self.set_needs_redraw(false);
         }
     }
-    // Method to actually draw the component, must be implemented by each component
-    fn draw(&mut self, origin_row: usize) -> Result<(), Error>;
+    // Method to actually draw the component at the given top left corner, must be implemented by each component
+    fn draw(&mut self, origin: Position) -> Result<(), Error>;
 }
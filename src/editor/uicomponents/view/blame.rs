@@ -0,0 +1,79 @@
+use super::gitdiff::unchanged_base_lines;
+use super::Line;
+
+const HASH_WIDTH: usize = 7;
+const AUTHOR_WIDTH: usize = 12;
+const NOT_COMMITTED: &str = "Not committed yet.";
+
+// The commit which last changed a line, see git::blame
+#[derive(Clone, Debug)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub author: String,
+    // YYYY-MM-DD, in the author's time zone
+    pub date: String,
+    pub summary: String,
+}
+
+// A line of the file as git blame saw it, with its commit if it has been committed
+#[derive(Clone, Debug)]
+pub struct BlameLine {
+    pub text: String,
+    pub commit: Option<BlameCommit>,
+}
+
+// The blamed lines shown next to a view's text. Lines edited since then aren't attributed to any commit,
+// which is found out by diffing again whenever the buffer changed.
+pub struct Blame {
+    lines: Vec<BlameLine>,
+    // For each buffer line, the blamed line it still is
+    mapping: Vec<Option<usize>>,
+    // The buffer revision the mapping is from
+    revision: Option<usize>,
+}
+
+impl Blame {
+    pub const fn new(lines: Vec<BlameLine>) -> Self {
+        Self {
+            lines,
+            mapping: Vec::new(),
+            revision: None,
+        }
+    }
+    pub fn update(&mut self, lines: &[Line], revision: usize) {
+        if self.revision == Some(revision) {
+            return;
+        }
+        self.revision = Some(revision);
+        let texts: Vec<String> = self.lines.iter().map(|line| line.text.clone()).collect();
+        self.mapping = unchanged_base_lines(&texts, lines);
+    }
+
+    fn commit_on_line(&self, line_idx: usize) -> Option<&BlameCommit> {
+        self.mapping
+            .get(line_idx)
+            .copied()
+            .flatten()
+            .and_then(|blamed_idx| self.lines.get(blamed_idx))
+            .and_then(|line| line.commit.as_ref())
+    }
+    // The abbreviated commit, author and date of the line for the gutter, blank if it isn't committed
+    pub fn column_text(&self, line_idx: usize) -> String {
+        self.commit_on_line(line_idx)
+            .map(|commit| {
+                let hash = commit.hash.get(..HASH_WIDTH).unwrap_or(&commit.hash);
+                format!("{hash} {:<AUTHOR_WIDTH$.AUTHOR_WIDTH$} {}", commit.author, commit.date)
+            })
+            .unwrap_or_default()
+    }
+    // Everything about the line's commit, for the message bar
+    pub fn summary(&self, line_idx: usize) -> String {
+        self.commit_on_line(line_idx).map_or_else(
+            || String::from(NOT_COMMITTED),
+            |commit| {
+                let hash = commit.hash.get(..HASH_WIDTH).unwrap_or(&commit.hash);
+                format!("{hash} {}, {}: {}", commit.author, commit.date, commit.summary)
+            },
+        )
+    }
+}
@@ -0,0 +1,96 @@
+use super::{BufferEdit, Location};
+
+#[derive(Copy, Clone)]
+pub struct Bookmark {
+    // A letter for named bookmarks, None for anonymous ones
+    pub name: Option<char>,
+    pub location: Location,
+}
+
+// The bookmarks of a buffer, which move along with their text as the buffer is edited.
+#[derive(Default)]
+pub struct Bookmarks {
+    marks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    // Sets the named bookmark, moving it if it already exists
+    pub fn set_named(&mut self, name: char, location: Location) {
+        self.marks.retain(|mark| mark.name != Some(name));
+        self.marks.push(Bookmark {
+            name: Some(name),
+            location,
+        });
+    }
+
+    // Adds an anonymous bookmark to the given location, or removes the one already on its line.
+    // Returns true if a bookmark was added.
+    pub fn toggle_anonymous(&mut self, location: Location) -> bool {
+        let count = self.marks.len();
+        self.marks
+            .retain(|mark| mark.name.is_some() || mark.location.line_idx != location.line_idx);
+        if self.marks.len() == count {
+            self.marks.push(Bookmark {
+                name: None,
+                location,
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_named(&self, name: char) -> Option<Location> {
+        self.marks
+            .iter()
+            .find(|mark| mark.name == Some(name))
+            .map(|mark| mark.location)
+    }
+
+    // All bookmarks, in text order
+    pub fn sorted(&self) -> Vec<Bookmark> {
+        let mut marks = self.marks.clone();
+        marks.sort_by_key(|mark| mark.location);
+        marks
+    }
+
+    // The bookmark to show for the given line. Named bookmarks take precedence over anonymous ones.
+    pub fn on_line(&self, line_idx: usize) -> Option<Bookmark> {
+        self.marks
+            .iter()
+            .filter(|mark| mark.location.line_idx == line_idx)
+            .max_by_key(|mark| mark.name.is_some())
+            .copied()
+    }
+
+    // Returns the first bookmark on a line after the given one, wrapping around to the first bookmark.
+    pub fn next_after(&self, location: Location) -> Option<Location> {
+        let sorted = self.sorted();
+        sorted
+            .iter()
+            .find(|mark| mark.location.line_idx > location.line_idx)
+            .or_else(|| sorted.first())
+            .map(|mark| mark.location)
+    }
+
+    // Returns the last bookmark on a line before the given one, wrapping around to the last bookmark.
+    pub fn prev_before(&self, location: Location) -> Option<Location> {
+        let sorted = self.sorted();
+        sorted
+            .iter()
+            .rev()
+            .find(|mark| mark.location.line_idx < location.line_idx)
+            .or_else(|| sorted.last())
+            .map(|mark| mark.location)
+    }
+
+    pub fn adjust(&mut self, edit: BufferEdit) {
+        for mark in &mut self.marks {
+            mark.location = edit.adjust(mark.location);
+        }
+    }
+}
@@ -1,15 +1,37 @@
+use super::Bookmarks;
+use super::BufferEdit;
+use super::{Diagnostic, DiagnosticReport, Diagnostics, TextEdit};
 use super::FileInfo;
+use super::GitDiff;
+use super::{Highlight, Highlighter};
+use super::JumpList;
 use super::Line;
 use super::Location;
-use std::fs::{read_to_string, File};
+use std::collections::VecDeque;
+use std::fs::{canonicalize, read_to_string, File};
 use std::io::Error;
 use std::io::Write;
+use std::ops::Range;
+
+// Views lagging further behind than this many edits can't follow them anymore, see edits_since
+const MAX_EDIT_LOG: usize = 1000;
 
 #[derive(Default)]
 pub struct Buffer {
     pub lines: Vec<Line>,
     pub file_info: FileInfo,
     pub dirty: bool, // indicates wether there are changes (dirty) or not (not dirty) ? / falsened when file saved
+    pub bookmarks: Bookmarks,
+    pub jump_list: JumpList,
+    pub diagnostics: Diagnostics,
+    pub git_diff: GitDiff,
+    // Where the caret was when the last view stopped showing this buffer
+    pub last_location: Location,
+    highlighter: Highlighter,
+    // The most recent edits, so views sharing this buffer can keep their caret on the same text
+    edit_log: VecDeque<BufferEdit>,
+    // Number of edits since loading, including the ones dropped from the log
+    revision: usize,
 }
 
 impl Buffer {
@@ -20,10 +42,15 @@ impl Buffer {
             lines.push(Line::from(value));
         }
 
+        let file_info = FileInfo::from(file_name);
         Ok(Self {
             lines,
-            file_info: FileInfo::from(file_name),
+            highlighter: Highlighter::new(file_info.get_file_type()),
+            file_info,
             dirty: false,
+            bookmarks: Bookmarks::default(),
+            jump_list: JumpList::default(),
+            ..Self::default()
         })
     }
 
@@ -103,6 +130,9 @@ impl Buffer {
     pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
         let file_info = FileInfo::from(file_name);
         self.save_to_file(&file_info)?;
+        if file_info.get_file_type() != self.file_info.get_file_type() {
+            self.highlighter = Highlighter::new(file_info.get_file_type());
+        }
         self.file_info = file_info;
         self.dirty = false;
         Ok(())
@@ -127,11 +157,19 @@ impl Buffer {
         debug_assert!(at.line_idx <= self.height());
         if at.line_idx == self.height() {
             self.lines.push(Line::from(&character.to_string()));
-            self.dirty = true;
+            self.record_insertion(at, Location {
+                grapheme_idx: 1,
+                line_idx: at.line_idx,
+            });
         }
         else if let Some(line) = self.lines.get_mut(at.line_idx) {
+            let old_len = line.grapheme_count();
             line.insert_char(character, at.grapheme_idx);
-            self.dirty = true;
+            let grapheme_delta = line.grapheme_count().saturating_sub(old_len);
+            self.record_insertion(at, Location {
+                grapheme_idx: at.grapheme_idx.saturating_add(grapheme_delta),
+                line_idx: at.line_idx,
+            });
         }
     }
 
@@ -146,30 +184,290 @@ impl Buffer {
 
             // Suppresion en fin de ligne => fusion des deux lignes
             if at.grapheme_idx >= line.grapheme_count() && self.lines.len() > at.line_idx.saturating_add(1) {
+                let line_end = Location {
+                    grapheme_idx: line.grapheme_count(),
+                    line_idx: at.line_idx,
+                };
                 let next_line = self.lines.remove(at.line_idx.saturating_add(1));
                 // clippy::indexing_slicing: We checked for existence of this line in the surrounding if statment
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_idx].append(&next_line);
-                self.dirty = true;
+                self.record_deletion(line_end, Location {
+                    grapheme_idx: 0,
+                    line_idx: at.line_idx.saturating_add(1),
+                });
             }
             // Suppresion normale
             else if at.grapheme_idx < line.grapheme_count() {
                 // clippy::indexing_slicing: We checked for existence of this line in the surrounding if statment
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_idx].delete(at.grapheme_idx);
-                self.dirty = true;
+                self.record_deletion(at, Location {
+                    grapheme_idx: at.grapheme_idx.saturating_add(1),
+                    line_idx: at.line_idx,
+                });
             }
         }
     }
 
     pub fn insert_newline(&mut self, at: Location) {
+        if at.line_idx <= self.height() {
+            self.split_line(at);
+            self.record_insertion(at, Location {
+                grapheme_idx: 0,
+                line_idx: at.line_idx.saturating_add(1),
+            });
+        }
+    }
+
+    // Moves everything behind the given location to a new line, or adds an empty line at the end of the buffer.
+    fn split_line(&mut self, at: Location) {
         if at.line_idx == self.height() {
             self.lines.push(Line::default());
-            self.dirty = true;
         } else if let Some(line) = self.lines.get_mut(at.line_idx) {
             let new = line.split(at.grapheme_idx);
             self.lines.insert(at.line_idx.saturating_add(1), new);
-            self.dirty = true;
         }
     }
+
+    // Inserts a (possibly multi-line) text at the given location and returns the location right behind the inserted text.
+    pub fn insert_text(&mut self, text: &str, at: Location) -> Location {
+        debug_assert!(at.line_idx <= self.height());
+        if text.is_empty() {
+            return at;
+        }
+        if at.line_idx == self.height() {
+            self.lines.push(Line::default());
+        }
+        let mut end = at;
+        for (idx, line_text) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.split_line(end);
+                end = Location {
+                    line_idx: end.line_idx.saturating_add(1),
+                    grapheme_idx: 0,
+                };
+            }
+            if let Some(line) = self.lines.get_mut(end.line_idx) {
+                let old_len = line.grapheme_count();
+                line.insert_str(line_text, end.grapheme_idx);
+                let grapheme_delta = line.grapheme_count().saturating_sub(old_len);
+                end.grapheme_idx = end.grapheme_idx.saturating_add(grapheme_delta);
+            }
+        }
+        self.record_insertion(at, end);
+        end
+    }
+
+    // Deletes everything between from (inclusive) and to (exclusive), joining lines if the range spans several lines.
+    pub fn delete_range(&mut self, from: Location, to: Location) {
+        if to <= from || from.line_idx >= self.height() {
+            return;
+        }
+        if from.line_idx == to.line_idx {
+            if let Some(line) = self.lines.get_mut(from.line_idx) {
+                line.delete_range(from.grapheme_idx..to.grapheme_idx);
+            }
+        } else {
+            let remainder = self
+                .lines
+                .get_mut(to.line_idx)
+                .map(|line| line.split(to.grapheme_idx));
+            let last_removed = to.line_idx.min(self.height().saturating_sub(1));
+            self.lines
+                .drain(from.line_idx.saturating_add(1)..=last_removed);
+            if let Some(line) = self.lines.get_mut(from.line_idx) {
+                line.split(from.grapheme_idx);
+                if let Some(remainder) = remainder {
+                    line.append(&remainder);
+                }
+            }
+        }
+        self.record_deletion(from, to);
+    }
+
+    // region: syntax highlighting
+    // Highlights the lines which aren't yet, up to the given one.
+    pub fn highlight_until(&mut self, line_idx: usize) {
+        self.highlighter.highlight_until(&self.lines, line_idx);
+    }
+    pub fn highlights(&self, line_idx: usize) -> &[Highlight] {
+        self.highlighter.highlights(line_idx)
+    }
+    // endregion
+
+    // region: text edits
+    // Applies edits which refer to the text as it is now, so they must not overlap.
+    pub fn apply_text_edits(&mut self, edits: &[TextEdit]) {
+        let mut edits: Vec<(Location, Location, &str)> = edits
+            .iter()
+            .map(|edit| (self.char_location(edit.start), self.char_location(edit.end), edit.new_text.as_str()))
+            .collect();
+        // Back to front, so that the locations of the remaining edits stay valid
+        edits.sort_by_key(|(start, _, _)| *start);
+        for (start, end, new_text) in edits.into_iter().rev() {
+            self.delete_range(start, end);
+            self.insert_text(new_text, start);
+        }
+    }
+    // The text of every line
+    pub fn line_strings(&self) -> Vec<String> {
+        self.lines.iter().map(ToString::to_string).collect()
+    }
+    // endregion
+
+    // region: diagnostics
+    // Replaces the diagnostics with the reported ones which are about this buffer's file.
+    pub fn set_diagnostics(&mut self, reports: &[DiagnosticReport]) {
+        let Some(path) = self.file_info.get_path().and_then(|path| canonicalize(path).ok()) else {
+            return;
+        };
+        let diagnostics = reports
+            .iter()
+            .filter(|report| report.path == path)
+            .map(|report| Diagnostic {
+                severity: report.severity,
+                message: report.message.clone(),
+                start: self.char_location(report.start),
+                end: self.char_location(report.end),
+            })
+            .collect();
+        self.diagnostics.set(diagnostics);
+    }
+    // Converts a line and character column, which may be out of date, to a location in the buffer
+    fn char_location(&self, (line_idx, char_idx): (usize, usize)) -> Location {
+        self.lines.get(line_idx).map_or_else(
+            || Location {
+                line_idx: self.lines.len(),
+                grapheme_idx: 0,
+            },
+            |line| Location {
+                line_idx,
+                grapheme_idx: line.char_idx_to_grapheme_idx(char_idx),
+            },
+        )
+    }
+    // endregion
+
+    // region: git changes
+    // Sets the version of the file in the last commit, None if it isn't tracked.
+    pub fn set_git_base(&mut self, base: Option<Vec<String>>) {
+        self.git_diff.set_base(base);
+    }
+    // Diffs the lines against the last commit again if they changed since
+    pub fn update_git_diff(&mut self) {
+        let edits = self
+            .git_diff
+            .revision()
+            .and_then(|revision| self.edits_since(revision));
+        self.git_diff.update(&self.lines, self.revision, edits.as_deref());
+    }
+    // Restores the lines of the hunk marked on the given line as they are in the last commit.
+    // Returns the first restored line, or None if there is no hunk there.
+    pub fn revert_hunk(&mut self, line_idx: usize) -> Option<usize> {
+        self.update_git_diff();
+        let hunk = self.git_diff.hunk_on_line(line_idx)?.clone();
+        let base_lines = self.git_diff.base_lines(&hunk).to_vec();
+        self.replace_lines(hunk.lines.clone(), &base_lines);
+        Some(hunk.lines.start)
+    }
+    fn replace_lines(&mut self, lines: Range<usize>, new_lines: &[String]) {
+        let height = self.height();
+        let line_end = |line_idx: usize| Location {
+            line_idx,
+            grapheme_idx: self.lines.get(line_idx).map_or(0, Line::grapheme_count),
+        };
+        // The last line has no line break of its own: replacing lines at the end takes the one before them
+        let (from, to, text) = if lines.end < height {
+            let text: String = new_lines.iter().flat_map(|line| [line.as_str(), "\n"]).collect();
+            (
+                Location {
+                    line_idx: lines.start,
+                    grapheme_idx: 0,
+                },
+                Location {
+                    line_idx: lines.end,
+                    grapheme_idx: 0,
+                },
+                text,
+            )
+        } else if lines.start > 0 {
+            let from = line_end(lines.start.saturating_sub(1));
+            let to = if lines.is_empty() { from } else { line_end(height.saturating_sub(1)) };
+            let text: String = new_lines.iter().flat_map(|line| ["\n", line.as_str()]).collect();
+            (from, to, text)
+        } else {
+            (Location::default(), line_end(height.saturating_sub(1)), new_lines.join("\n"))
+        };
+        self.delete_range(from, to);
+        self.insert_text(&text, from);
+    }
+    // endregion
+
+    // region: edit tracking
+    fn record_insertion(&mut self, from: Location, new_end: Location) {
+        self.record_edit(BufferEdit {
+            from,
+            old_end: from,
+            new_end,
+        });
+    }
+    fn record_deletion(&mut self, from: Location, old_end: Location) {
+        self.record_edit(BufferEdit {
+            from,
+            old_end,
+            new_end: from,
+        });
+    }
+    fn record_edit(&mut self, edit: BufferEdit) {
+        self.bookmarks.adjust(edit);
+        self.jump_list.adjust(edit);
+        self.diagnostics.adjust(edit);
+        self.highlighter.invalidate_from(edit.from.line_idx);
+        self.dirty = true;
+        if self.edit_log.len() == MAX_EDIT_LOG {
+            self.edit_log.pop_front();
+        }
+        self.edit_log.push_back(edit);
+        self.revision = self.revision.saturating_add(1);
+    }
+    pub const fn revision(&self) -> usize {
+        self.revision
+    }
+    // Returns the edits made after the given revision, oldest first, or None if they are no longer known.
+    pub fn edits_since(&self, revision: usize) -> Option<Vec<BufferEdit>> {
+        let oldest_known = self.revision.saturating_sub(self.edit_log.len());
+        let skip = revision.checked_sub(oldest_known)?;
+        Some(self.edit_log.iter().skip(skip).copied().collect())
+    }
+    // Returns the lines edited since the given revision, as they were then and as they are now, or None if
+    // the edits are no longer known. The lines around them are unchanged, the ones after them moved along.
+    pub fn edited_lines_since(&self, revision: usize) -> Option<(Range<usize>, Range<usize>)> {
+        let mut edited: Option<Range<usize>> = None;
+        let (mut added, mut removed): (usize, usize) = (0, 0);
+        for edit in self.edits_since(revision)? {
+            let start = edit.from.line_idx;
+            let old_end = edit.old_end.line_idx.saturating_add(1);
+            let new_end = edit.new_end.line_idx.saturating_add(1);
+            // Where a line boundary of the earlier edits ends up after this one
+            let moved = |line_idx: usize| {
+                if line_idx < start {
+                    line_idx
+                } else if line_idx >= old_end {
+                    line_idx.saturating_sub(old_end).saturating_add(new_end)
+                } else {
+                    new_end
+                }
+            };
+            edited = Some(edited.map_or(start..new_end, |lines| {
+                moved(lines.start).min(start)..moved(lines.end).max(new_end)
+            }));
+            added = added.saturating_add(new_end.saturating_sub(start));
+            removed = removed.saturating_add(old_end.saturating_sub(start));
+        }
+        let lines = edited.unwrap_or_default();
+        let old_end = lines.end.saturating_add(removed).saturating_sub(added);
+        Some((lines.start..old_end, lines))
+    }
+    // endregion
 }
@@ -0,0 +1,39 @@
+use super::Location;
+
+// Describes a change to the buffer: the text between `from` and `old_end` was replaced by text now ending at `new_end`.
+// An insertion has `old_end == from`, a deletion has `new_end == from`.
+// Used to keep locations (bookmarks and the like) attached to their text while the buffer changes.
+#[derive(Copy, Clone, Debug)]
+pub struct BufferEdit {
+    pub from: Location,
+    pub old_end: Location,
+    pub new_end: Location,
+}
+
+impl BufferEdit {
+    // Returns where the text at the given location ended up after this edit.
+    // Locations within replaced text collapse to the start of the edit.
+    pub fn adjust(&self, location: Location) -> Location {
+        if location < self.from {
+            location
+        } else if location < self.old_end {
+            self.from
+        } else if location.line_idx == self.old_end.line_idx {
+            Location {
+                grapheme_idx: location
+                    .grapheme_idx
+                    .saturating_sub(self.old_end.grapheme_idx)
+                    .saturating_add(self.new_end.grapheme_idx),
+                line_idx: self.new_end.line_idx,
+            }
+        } else {
+            Location {
+                grapheme_idx: location.grapheme_idx,
+                line_idx: location
+                    .line_idx
+                    .saturating_sub(self.old_end.line_idx)
+                    .saturating_add(self.new_end.line_idx),
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    io::Error,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use super::{Buffer, DiagnosticReport, FileType, TextEdit, WordRanking};
+
+// An open buffer, which any number of views may show. Clones refer to the same buffer.
+#[derive(Clone, Default)]
+pub struct BufferHandle(Rc<RefCell<Buffer>>);
+
+impl BufferHandle {
+    pub fn load(file_name: &str) -> Result<Self, Error> {
+        Ok(Self(Rc::new(RefCell::new(Buffer::load(file_name)?))))
+    }
+    pub fn is_same(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+    // The file name, as shown to the user
+    pub fn name(&self) -> String {
+        format!("{}", self.0.borrow().file_info)
+    }
+    pub fn is_modified(&self) -> bool {
+        self.0.borrow().dirty
+    }
+    pub fn has_path(&self, path: &Path) -> bool {
+        self.0.borrow().file_info.get_path() == Some(path)
+    }
+    pub fn path(&self) -> Option<PathBuf> {
+        self.0.borrow().file_info.get_path().map(Path::to_path_buf)
+    }
+    // Never loaded, saved nor edited: nothing is lost when it's replaced
+    pub fn is_pristine(&self) -> bool {
+        let buffer = self.0.borrow();
+        !buffer.is_file_loaded() && !buffer.dirty && buffer.is_empty()
+    }
+    pub fn file_type(&self) -> FileType {
+        self.0.borrow().file_info.get_file_type()
+    }
+    // Changes with every edit, see Buffer::revision
+    pub fn revision(&self) -> usize {
+        self.0.borrow().revision()
+    }
+    pub fn line_strings(&self) -> Vec<String> {
+        self.0.borrow().line_strings()
+    }
+    // The lines edited since the given revision as they were then, and their text now, see Buffer::edited_lines_since
+    pub fn edited_lines_since(&self, revision: usize) -> Option<(Range<usize>, Vec<String>)> {
+        let buffer = self.0.borrow();
+        let (old_lines, lines) = buffer.edited_lines_since(revision)?;
+        let texts = buffer
+            .lines
+            .get(lines)
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        Some((old_lines, texts))
+    }
+    pub fn apply_text_edits(&self, edits: &[TextEdit]) {
+        self.0.borrow_mut().apply_text_edits(edits);
+    }
+    pub fn set_diagnostics(&self, reports: &[DiagnosticReport]) {
+        self.0.borrow_mut().set_diagnostics(reports);
+    }
+    // The version of the file in the last commit, which the gutter marks the changes against
+    pub fn set_git_base(&self, base: Option<Vec<String>>) {
+        self.0.borrow_mut().set_git_base(base);
+    }
+    // Adds the words of a buffer other than the one being edited, which rank below all of its own words
+    pub fn add_completion_words(&self, ranking: &mut WordRanking) {
+        for line in &self.0.borrow().lines {
+            ranking.add_line(line, usize::MAX);
+        }
+    }
+    pub(super) fn borrow(&self) -> Ref<'_, Buffer> {
+        self.0.borrow()
+    }
+    pub(super) fn borrow_mut(&self) -> RefMut<'_, Buffer> {
+        self.0.borrow_mut()
+    }
+}
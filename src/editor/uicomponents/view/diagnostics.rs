@@ -0,0 +1,137 @@
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use super::super::super::AnnotationType;
+use super::{BufferEdit, Location};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Severity {
+    // Notes and help
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const fn sign(self) -> char {
+        match self {
+            Self::Error => '✖',
+            Self::Warning => '▲',
+            Self::Info => '•',
+        }
+    }
+    pub const fn annotation_type(self) -> AnnotationType {
+        match self {
+            Self::Error => AnnotationType::Error,
+            Self::Warning => AnnotationType::Warning,
+            Self::Info => AnnotationType::Info,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "note",
+        };
+        write!(formatter, "{name}")
+    }
+}
+
+// A diagnostic as a tool reports it, before it's attached to a buffer.
+// Lines and columns count from 0, columns in characters.
+#[derive(Clone, Debug)]
+pub struct DiagnosticReport {
+    pub path: PathBuf,
+    pub severity: Severity,
+    pub message: String,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub start: Location,
+    // Exclusive
+    pub end: Location,
+}
+
+impl Diagnostic {
+    // Whether the caret at the given location is on the diagnostic, including right behind it
+    fn touches(&self, location: Location) -> bool {
+        self.start <= location && location <= self.end
+    }
+}
+
+// The diagnostics of a buffer, which move along with their text as the buffer is edited
+// until the next check replaces them.
+#[derive(Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn set(&mut self, mut diagnostics: Vec<Diagnostic>) {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.start);
+        self.diagnostics = diagnostics;
+    }
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    // The diagnostics covering some part of the given line
+    pub fn on_line(&self, line_idx: usize) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(move |diagnostic| {
+            diagnostic.start.line_idx <= line_idx && line_idx <= diagnostic.end.line_idx
+        })
+    }
+    // The most severe diagnostic starting on the given line, for the sign column
+    pub fn worst_on_line(&self, line_idx: usize) -> Option<Severity> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.start.line_idx == line_idx)
+            .map(|diagnostic| diagnostic.severity)
+            .max()
+    }
+    // The diagnostic under the caret, or else the most severe one on its line
+    pub fn at(&self, location: Location) -> Option<&Diagnostic> {
+        self.on_line(location.line_idx)
+            .filter(|diagnostic| diagnostic.touches(location))
+            .max_by_key(|diagnostic| diagnostic.severity)
+            .or_else(|| {
+                self.on_line(location.line_idx)
+                    .max_by_key(|diagnostic| diagnostic.severity)
+            })
+    }
+
+    // Returns the start of the first diagnostic behind the given location, wrapping around to the first one.
+    pub fn next_after(&self, location: Location) -> Option<Location> {
+        self.diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.start > location)
+            .or_else(|| self.diagnostics.first())
+            .map(|diagnostic| diagnostic.start)
+    }
+    // Returns the start of the last diagnostic before the given location, wrapping around to the last one.
+    pub fn prev_before(&self, location: Location) -> Option<Location> {
+        self.diagnostics
+            .iter()
+            .rev()
+            .find(|diagnostic| diagnostic.start < location)
+            .or_else(|| self.diagnostics.last())
+            .map(|diagnostic| diagnostic.start)
+    }
+
+    pub fn adjust(&mut self, edit: BufferEdit) {
+        for diagnostic in &mut self.diagnostics {
+            diagnostic.start = edit.adjust(diagnostic.start);
+            diagnostic.end = edit.adjust(diagnostic.end);
+        }
+    }
+}
@@ -3,15 +3,20 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use super::FileType;
+
 #[derive(Default, Debug)]
 pub struct FileInfo {
-    path: Option<PathBuf>
+    path: Option<PathBuf>,
+    file_type: FileType,
 }
 
 impl FileInfo {
     pub fn from(file_name: &str) -> Self {
+        let path = PathBuf::from(file_name);
         Self {
-            path: Some(PathBuf::from(file_name)),
+            file_type: FileType::from(&path),
+            path: Some(path),
         }
     }
     pub fn get_path(&self) -> Option<&Path> {
@@ -20,6 +25,9 @@ impl FileInfo {
     pub const fn has_path(&self) -> bool {
         self.path.is_some()
     }
+    pub const fn get_file_type(&self) -> FileType {
+        self.file_type
+    }
 }
 
 impl Display for FileInfo {
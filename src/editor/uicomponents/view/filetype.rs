@@ -0,0 +1,43 @@
+use std::{
+    fmt::{self, Display},
+    path::Path,
+};
+
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FileType {
+    Rust,
+    Toml,
+    Markdown,
+    Json,
+    Shell,
+    #[default]
+    Text,
+}
+
+impl FileType {
+    pub fn from(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("rs") => Self::Rust,
+            Some("toml") => Self::Toml,
+            Some("md" | "markdown") => Self::Markdown,
+            Some("json") => Self::Json,
+            Some("sh" | "bash" | "zsh") => Self::Shell,
+            _ => Self::Text,
+        }
+    }
+}
+
+// The name used to refer to the file type in configuration files, e.g. `rust.snippets`
+impl Display for FileType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Rust => "rust",
+            Self::Toml => "toml",
+            Self::Markdown => "markdown",
+            Self::Json => "json",
+            Self::Shell => "sh",
+            Self::Text => "text",
+        };
+        write!(formatter, "{name}")
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::RangeInclusive;
+
+use super::super::super::config::FoldMethod;
+use super::super::super::AnnotationType;
+use super::{Buffer, BufferEdit, FileType, Line, Location};
+
+// Tabs count as this many columns when comparing indentation
+const TAB_INDENT_WIDTH: usize = 4;
+
+// Picks the actual method for the file type
+fn resolve(method: FoldMethod, file_type: FileType) -> FoldMethod {
+    match (method, file_type) {
+        (FoldMethod::Auto, FileType::Rust | FileType::Json) => FoldMethod::Braces,
+        (FoldMethod::Auto, _) => FoldMethod::Indent,
+        (method, _) => method,
+    }
+}
+
+// A folded region: its first line stays visible as a summary, the following lines up to the end are hidden.
+// Stored as locations, so that the fold moves along with its text as the buffer is edited.
+#[derive(Copy, Clone)]
+struct Fold {
+    start: Location,
+    end: Location,
+}
+
+impl Fold {
+    const fn header_line_idx(&self) -> usize {
+        self.start.line_idx
+    }
+    const fn hides(&self, line_idx: usize) -> bool {
+        line_idx > self.start.line_idx && line_idx <= self.end.line_idx
+    }
+}
+
+// The folds of a view: other views on the same buffer fold their own regions. They follow the edits made
+// to the buffer through its edit log, see follow.
+#[derive(Clone, Default)]
+pub struct Folds {
+    folds: Vec<Fold>,
+    // The buffer revision the folds have caught up with
+    revision: usize,
+}
+
+impl Folds {
+    // Moves the folds along with the edits made since they last caught up. They are dropped if those edits
+    // are no longer known.
+    pub fn follow(&mut self, buffer: &Buffer) {
+        let revision = buffer.revision();
+        if revision == self.revision {
+            return;
+        }
+        match buffer.edits_since(self.revision) {
+            Some(edits) => edits.into_iter().for_each(|edit| self.adjust(edit)),
+            None => self.folds.clear(),
+        }
+        self.revision = revision;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    // Folds the given lines. Folds inside the new one are merged into it.
+    pub fn add(&mut self, lines: RangeInclusive<usize>, buffer_lines: &[Line]) {
+        let (start, end) = (*lines.start(), *lines.end());
+        if end <= start {
+            return;
+        }
+        self.folds
+            .retain(|fold| fold.header_line_idx() < start || fold.end.line_idx > end);
+        self.folds.push(Fold {
+            start: Location {
+                grapheme_idx: 0,
+                line_idx: start,
+            },
+            end: Location {
+                grapheme_idx: buffer_lines.get(end).map_or(0, Line::grapheme_count),
+                line_idx: end,
+            },
+        });
+    }
+
+    // Removes the folds containing the given line, header included. Returns false if there were none.
+    pub fn remove_at(&mut self, line_idx: usize) -> bool {
+        let count = self.folds.len();
+        self.folds.retain(|fold| {
+            !(fold.header_line_idx() == line_idx || fold.hides(line_idx))
+        });
+        self.folds.len() != count
+    }
+
+    // Removes the folds hiding the given line. Returns false if it wasn't hidden.
+    pub fn reveal(&mut self, line_idx: usize) -> bool {
+        let count = self.folds.len();
+        self.folds.retain(|fold| !fold.hides(line_idx));
+        self.folds.len() != count
+    }
+
+    pub fn clear(&mut self) {
+        self.folds.clear();
+    }
+
+    pub fn is_hidden(&self, line_idx: usize) -> bool {
+        self.folds.iter().any(|fold| fold.hides(line_idx))
+    }
+
+    // Returns the number of lines hidden behind the given line, if it is the header of a visible fold.
+    pub fn hidden_after(&self, line_idx: usize) -> Option<usize> {
+        if self.is_hidden(line_idx) {
+            return None;
+        }
+        self.folds
+            .iter()
+            .filter(|fold| fold.header_line_idx() == line_idx)
+            .map(|fold| fold.end.line_idx.saturating_sub(line_idx))
+            .max()
+    }
+
+    // The first and last line of each fold
+    pub fn line_ranges(&self) -> Vec<(usize, usize)> {
+        self.folds
+            .iter()
+            .map(|fold| (fold.start.line_idx, fold.end.line_idx))
+            .collect()
+    }
+
+    fn adjust(&mut self, edit: BufferEdit) {
+        for fold in &mut self.folds {
+            fold.start = edit.adjust(fold.start);
+            fold.end = edit.adjust(fold.end);
+        }
+        self.folds
+            .retain(|fold| fold.end.line_idx > fold.start.line_idx);
+    }
+}
+
+// Returns the lines of the innermost foldable region containing the given line: the region starting at it
+// if there is one, or else the region enclosing it. Braces are matched as highlighted, see brace_region_starting_at.
+pub fn region_at(buffer: &Buffer, line_idx: usize, method: FoldMethod) -> Option<RangeInclusive<usize>> {
+    let lines = &buffer.lines;
+    match resolve(method, buffer.file_info.get_file_type()) {
+        FoldMethod::Braces => brace_region_starting_at(buffer, line_idx).or_else(|| {
+            (0..line_idx).rev().find_map(|header| {
+                brace_region_starting_at(buffer, header).filter(|region| region.contains(&line_idx))
+            })
+        }),
+        _ => indent_region_starting_at(lines, line_idx).or_else(|| {
+            let indent = indent_of(lines.get(line_idx)?)?;
+            (0..line_idx)
+                .rev()
+                .find(|header| {
+                    lines
+                        .get(*header)
+                        .and_then(indent_of)
+                        .is_some_and(|header_indent| header_indent < indent)
+                })
+                .and_then(|header| indent_region_starting_at(lines, header))
+        }),
+    }
+}
+
+// Returns all outermost foldable regions, in text order.
+pub fn top_level_regions(buffer: &Buffer, method: FoldMethod) -> Vec<RangeInclusive<usize>> {
+    let lines = &buffer.lines;
+    let method = resolve(method, buffer.file_info.get_file_type());
+    let mut regions = Vec::new();
+    let mut line_idx = 0;
+    while line_idx < lines.len() {
+        let region = match method {
+            FoldMethod::Braces => brace_region_starting_at(buffer, line_idx),
+            _ => indent_region_starting_at(lines, line_idx),
+        };
+        if let Some(region) = region {
+            line_idx = region.end().saturating_add(1);
+            regions.push(region);
+        } else {
+            line_idx = line_idx.saturating_add(1);
+        }
+    }
+    regions
+}
+
+// The indentation of a line, None for blank lines which don't take part in indentation blocks.
+fn indent_of(line: &Line) -> Option<usize> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(
+        line.chars()
+            .take_while(|ch| ch.is_whitespace())
+            .map(|ch| if ch == '\t' { TAB_INDENT_WIDTH } else { 1 })
+            .sum(),
+    )
+}
+
+// A line followed by more indented lines starts a region, which ends at the last of them.
+fn indent_region_starting_at(lines: &[Line], line_idx: usize) -> Option<RangeInclusive<usize>> {
+    let indent = indent_of(lines.get(line_idx)?)?;
+    let mut end = line_idx;
+    for (idx, line) in lines.iter().enumerate().skip(line_idx.saturating_add(1)) {
+        match indent_of(line) {
+            Some(line_indent) if line_indent > indent => end = idx,
+            Some(_) => break,
+            None => {} // Blank lines don't end the region, but trailing ones are not part of it
+        }
+    }
+    (end > line_idx).then_some(line_idx..=end)
+}
+
+// A line with an unclosed brace starts a region, which ends at the line closing it. Braces in strings,
+// character literals and comments don't count: the lines must have been highlighted, see Buffer::highlight_until.
+fn brace_region_starting_at(buffer: &Buffer, line_idx: usize) -> Option<RangeInclusive<usize>> {
+    let mut depth: usize = 0;
+    let mut opened = false;
+    for (idx, line) in buffer.lines.iter().enumerate().skip(line_idx) {
+        let highlights = buffer.highlights(idx);
+        let is_code = |byte_idx: usize| {
+            !highlights.iter().any(|highlight| {
+                matches!(highlight.annotation_type, AnnotationType::String | AnnotationType::Comment)
+                    && (highlight.start_byte_idx..highlight.end_byte_idx).contains(&byte_idx)
+            })
+        };
+        for (_, ch) in line.char_indices().filter(|(byte_idx, _)| is_code(*byte_idx)) {
+            match ch {
+                '{' | '[' => {
+                    depth = depth.saturating_add(1);
+                    opened = true;
+                }
+                '}' | ']' if depth > 0 => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if idx == line_idx && depth == 0 {
+            // Everything opened on the header line is closed there too
+            return None;
+        }
+        if opened && depth == 0 {
+            return (idx > line_idx).then_some(line_idx..=idx);
+        }
+    }
+    None
+}
@@ -0,0 +1,568 @@
+use std::ops::Range;
+
+use super::super::super::AnnotationType;
+use super::{BufferEdit, Line};
+
+// Texts differing in more lines than this aren't diffed further: everything between their common start and end
+// is marked as changed
+const MAX_EDIT_DISTANCE: usize = 1000;
+const CHANGED_MARKER: char = '▎';
+// Deleted lines are marked beneath the line above them, or above the first line
+const DELETED_BELOW_MARKER: char = '▁';
+const DELETED_ABOVE_MARKER: char = '▔';
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub const fn annotation_type(self) -> AnnotationType {
+        match self {
+            Self::Added => AnnotationType::GitAdded,
+            Self::Modified => AnnotationType::GitModified,
+            Self::Deleted => AnnotationType::GitDeleted,
+        }
+    }
+}
+
+// A run of changed lines: the buffer lines which replaced the base lines. Either may be empty, not both.
+#[derive(Clone, Debug)]
+pub struct Hunk {
+    pub lines: Range<usize>,
+    pub base_lines: Range<usize>,
+}
+
+impl Hunk {
+    pub fn kind(&self) -> ChangeKind {
+        if self.base_lines.is_empty() {
+            ChangeKind::Added
+        } else if self.lines.is_empty() {
+            ChangeKind::Deleted
+        } else {
+            ChangeKind::Modified
+        }
+    }
+    // The lines showing the marker: a deletion has no lines of its own
+    fn marked_lines(&self) -> Range<usize> {
+        if self.lines.is_empty() {
+            let line_idx = self.lines.start.saturating_sub(1);
+            line_idx..line_idx.saturating_add(1)
+        } else {
+            self.lines.clone()
+        }
+    }
+    pub fn marker(&self) -> char {
+        match self.kind() {
+            ChangeKind::Added | ChangeKind::Modified => CHANGED_MARKER,
+            ChangeKind::Deleted if self.lines.start == 0 => DELETED_ABOVE_MARKER,
+            ChangeKind::Deleted => DELETED_BELOW_MARKER,
+        }
+    }
+}
+
+// The differences between a buffer and the version of its file in git's HEAD commit.
+// They are diffed again whenever they are needed after the buffer changed, around the edits if they're known.
+#[derive(Default)]
+pub struct GitDiff {
+    // None if the file isn't tracked
+    base: Option<Vec<String>>,
+    hunks: Vec<Hunk>,
+    // The buffer revision the hunks are from
+    revision: Option<usize>,
+}
+
+impl GitDiff {
+    pub fn set_base(&mut self, base: Option<Vec<String>>) {
+        self.base = base;
+        self.hunks.clear();
+        self.revision = None;
+    }
+    pub fn update(&mut self, lines: &[Line], revision: usize, edits: Option<&[BufferEdit]>) {
+        if self.revision == Some(revision) {
+            return;
+        }
+        // The edits are relative to the lines the hunks are from
+        let edits = edits.filter(|_| self.revision.is_some());
+        self.revision = Some(revision);
+        let Some(base) = self.base.as_deref() else {
+            self.hunks.clear();
+            return;
+        };
+        self.hunks = edits
+            .and_then(|edits| rediff(&self.hunks, base, lines, edits))
+            .unwrap_or_else(|| diff(base, lines));
+    }
+    // The buffer revision the hunks are from, None if they need a full diff
+    pub const fn revision(&self) -> Option<usize> {
+        self.revision
+    }
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    pub fn hunk_on_line(&self, line_idx: usize) -> Option<&Hunk> {
+        self.hunks
+            .iter()
+            .find(|hunk| hunk.marked_lines().contains(&line_idx))
+    }
+    pub fn base_lines(&self, hunk: &Hunk) -> &[String] {
+        self.base
+            .as_deref()
+            .and_then(|base| base.get(hunk.base_lines.clone()))
+            .unwrap_or_default()
+    }
+    // Returns the first line of the next hunk below the given line, wrapping around to the first one.
+    pub fn next_after(&self, line_idx: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .map(|hunk| hunk.marked_lines().start)
+            .find(|start| *start > line_idx)
+            .or_else(|| self.hunks.first().map(|hunk| hunk.marked_lines().start))
+    }
+    // Returns the first line of the hunk above the given line, wrapping around to the last one.
+    pub fn prev_before(&self, line_idx: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .rev()
+            .find(|hunk| hunk.marked_lines().end <= line_idx)
+            .or_else(|| self.hunks.last())
+            .map(|hunk| hunk.marked_lines().start)
+    }
+}
+
+// For each of the lines, the base line it is unchanged from, if any
+pub fn unchanged_base_lines(base: &[String], lines: &[Line]) -> Vec<Option<usize>> {
+    let mut mapping = vec![None; lines.len()];
+    let (mut base_idx, mut line_idx) = (0, 0);
+    // Lines between the hunks are unchanged, as are the ones after the last hunk
+    let ends = diff(base, lines)
+        .into_iter()
+        .map(|hunk| (hunk.base_lines, hunk.lines))
+        .chain([(base.len()..base.len(), lines.len()..lines.len())]);
+    for (base_lines, changed_lines) in ends {
+        for (slot, unchanged_base_idx) in mapping
+            .iter_mut()
+            .take(changed_lines.start)
+            .skip(line_idx)
+            .zip(base_idx..base_lines.start)
+        {
+            *slot = Some(unchanged_base_idx);
+        }
+        base_idx = base_lines.end;
+        line_idx = changed_lines.end;
+    }
+    mapping
+}
+
+// The hunks turning the base into the lines
+fn diff(base: &[String], lines: &[Line]) -> Vec<Hunk> {
+    let base: Vec<&str> = base.iter().map(String::as_str).collect();
+    let mut lines: Vec<&str> = lines.iter().map(|line| &**line).collect();
+    // An empty file has no lines, but a single empty one once it has been edited
+    if lines == [""] {
+        lines.clear();
+    }
+    diff_lines(&base, &lines)
+}
+
+// Diffs the lines the edits touched again, along with the hunks next to them. The other hunks are kept,
+// moved along with the lines around them, so a line moved far away may show as changed where a full diff
+// would have matched it. Returns None if the hunks no longer fit the texts, then everything is diffed again.
+fn rediff(hunks: &[Hunk], base: &[String], lines: &[Line], edits: &[BufferEdit]) -> Option<Vec<Hunk>> {
+    // See diff about the single empty line
+    if lines.len() <= 1 {
+        return None;
+    }
+    let mut hunks = hunks.to_vec();
+    // The lines to diff again, after the edits so far
+    let mut dirty: Option<Range<usize>> = None;
+    for edit in edits.iter().map(LineEdit::new) {
+        let mut touched = edit.start..edit.new_end;
+        if let Some(previous) = dirty {
+            touched = edit.follow(previous, touched.clone()).unwrap_or(touched);
+        }
+        let mut kept = Vec::with_capacity(hunks.len());
+        for hunk in hunks {
+            match edit.follow(hunk.lines, touched.clone()) {
+                Ok(lines) => kept.push(Hunk { lines, ..hunk }),
+                Err(grown) => touched = grown,
+            }
+        }
+        hunks = kept;
+        dirty = Some(touched);
+    }
+    let Some(mut dirty) = dirty else {
+        return Some(hunks);
+    };
+
+    // Hunks next to the edited lines might join them
+    let before_count = hunks.iter().take_while(|hunk| hunk.lines.end < dirty.start).count();
+    let after_start = hunks
+        .iter()
+        .position(|hunk| hunk.lines.start > dirty.end)
+        .unwrap_or(hunks.len())
+        .max(before_count);
+    let after = hunks.split_off(after_start);
+    for hunk in hunks.drain(before_count..) {
+        dirty = dirty.start.min(hunk.lines.start)..dirty.end.max(hunk.lines.end);
+    }
+    // The lines between the hunks are unchanged, which lines up the edited lines with their base
+    let base_start = match hunks.last() {
+        Some(hunk) => hunk
+            .base_lines
+            .end
+            .checked_add(dirty.start.checked_sub(hunk.lines.end)?)?,
+        None => dirty.start,
+    };
+    let base_end = match after.first() {
+        Some(hunk) => hunk
+            .base_lines
+            .start
+            .checked_sub(hunk.lines.start.checked_sub(dirty.end)?)?,
+        None => base.len().checked_sub(lines.len().checked_sub(dirty.end)?)?,
+    };
+    let base_middle: Vec<&str> = base.get(base_start..base_end)?.iter().map(String::as_str).collect();
+    let middle: Vec<&str> = lines.get(dirty.clone())?.iter().map(|line| &**line).collect();
+    hunks.extend(diff_lines(&base_middle, &middle).into_iter().map(|hunk| Hunk {
+        lines: hunk.lines.start.saturating_add(dirty.start)..hunk.lines.end.saturating_add(dirty.start),
+        base_lines: hunk.base_lines.start.saturating_add(base_start)..hunk.base_lines.end.saturating_add(base_start),
+    }));
+    hunks.extend(after);
+    Some(hunks)
+}
+
+// The lines an edit replaced, start..old_end, and the ones it replaced them with, start..new_end
+#[derive(Clone, Copy)]
+struct LineEdit {
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+}
+
+impl LineEdit {
+    fn new(edit: &BufferEdit) -> Self {
+        Self {
+            start: edit.from.line_idx,
+            old_end: edit.old_end.line_idx.saturating_add(1),
+            new_end: edit.new_end.line_idx.saturating_add(1),
+        }
+    }
+    // Where a line boundary ends up: after the edit it moves along, within it it ends up at its end
+    fn boundary(self, line_idx: usize) -> usize {
+        if line_idx < self.start {
+            line_idx
+        } else if line_idx >= self.old_end {
+            line_idx.saturating_sub(self.old_end).saturating_add(self.new_end)
+        } else {
+            self.new_end
+        }
+    }
+    // Moves lines clear of the edit along with it. Lines touching it, or right next to it, are
+    // added to the touched ones instead, which are returned as the error.
+    fn follow(self, lines: Range<usize>, touched: Range<usize>) -> Result<Range<usize>, Range<usize>> {
+        if lines.start <= self.old_end && lines.end >= self.start {
+            Err(touched.start.min(lines.start)..touched.end.max(self.boundary(lines.end)))
+        } else {
+            Ok(self.boundary(lines.start)..self.boundary(lines.end))
+        }
+    }
+}
+
+// The hunks turning the base lines into the others
+fn diff_lines(base: &[&str], lines: &[&str]) -> Vec<Hunk> {
+    // Edits tend to be local: most of the text is the same at both ends
+    let prefix_len = base
+        .iter()
+        .zip(lines)
+        .take_while(|(base_line, line)| base_line == line)
+        .count();
+    let suffix_len = base
+        .iter()
+        .skip(prefix_len)
+        .rev()
+        .zip(lines.iter().skip(prefix_len).rev())
+        .take_while(|(base_line, line)| base_line == line)
+        .count();
+    let base_middle = base
+        .get(prefix_len..base.len().saturating_sub(suffix_len))
+        .unwrap_or_default();
+    let middle = lines
+        .get(prefix_len..lines.len().saturating_sub(suffix_len))
+        .unwrap_or_default();
+
+    let mut hunks = Vec::new();
+    let (mut base_idx, mut line_idx) = (0, 0);
+    let common_lines = common_lines(base_middle, middle).unwrap_or_default();
+    // The end of both texts closes the last hunk
+    for (common_base_idx, common_line_idx) in common_lines
+        .into_iter()
+        .chain([(base_middle.len(), middle.len())])
+    {
+        if common_base_idx > base_idx || common_line_idx > line_idx {
+            hunks.push(Hunk {
+                lines: prefix_len.saturating_add(line_idx)..prefix_len.saturating_add(common_line_idx),
+                base_lines: prefix_len.saturating_add(base_idx)..prefix_len.saturating_add(common_base_idx),
+            });
+        }
+        base_idx = common_base_idx.saturating_add(1);
+        line_idx = common_line_idx.saturating_add(1);
+    }
+    hunks
+}
+
+// Returns the pairs of equal lines, in order, left by a shortest edit script from one text to the other.
+// This is Myers' algorithm: for each number of edits d, it follows every diagonal k = x - y as far as the
+// texts match, remembering how far each diagonal got in order to walk back along the shortest path.
+// Returns None if more than MAX_EDIT_DISTANCE edits are needed.
+fn common_lines(base: &[&str], lines: &[&str]) -> Option<Vec<(usize, usize)>> {
+    let (base_len, len) = (base.len(), lines.len());
+    let max_distance = isize::try_from(base_len.saturating_add(len).min(MAX_EDIT_DISTANCE)).ok()?;
+    // The furthest x reached after each number of edits d, on the diagonals -d, -d + 2, .., d it can be on.
+    // Walking back needs no others.
+    let mut trace: Vec<Vec<usize>> = Vec::new();
+    for distance in 0..=max_distance {
+        let previous = trace.last().map(Vec::as_slice).unwrap_or_default();
+        let previous_distance = distance.saturating_sub(1);
+        let mut furthest = Vec::with_capacity(usize::try_from(distance).ok()?.saturating_add(1));
+        for k in (distance.saturating_neg()..=distance).step_by(2) {
+            let mut x = if takes_insertion(previous, k, distance) {
+                furthest_on(previous, previous_distance, k.saturating_add(1))
+            } else {
+                furthest_on(previous, previous_distance, k.saturating_sub(1)).saturating_add(1)
+            };
+            let mut y = usize::try_from(isize::try_from(x).ok()?.saturating_sub(k)).ok()?;
+            while x < base_len && y < len && base.get(x) == lines.get(y) {
+                x = x.saturating_add(1);
+                y = y.saturating_add(1);
+            }
+            furthest.push(x);
+            if x >= base_len && y >= len {
+                trace.push(furthest);
+                return Some(walk_back(&trace, base_len, len));
+            }
+        }
+        trace.push(furthest);
+    }
+    None
+}
+
+// The furthest x reached on diagonal k after the given number of edits, out of the diagonals of that step
+fn furthest_on(furthest: &[usize], distance: isize, k: isize) -> usize {
+    usize::try_from(k.saturating_add(distance))
+        .ok()
+        .and_then(|sum| sum.checked_div(2))
+        .and_then(|idx| furthest.get(idx).copied())
+        .unwrap_or(0)
+}
+
+// Whether the path to diagonal k comes from the diagonal above it (an inserted line),
+// rather than from the one below it (a deleted line), given the diagonals of the step before
+fn takes_insertion(previous: &[usize], k: isize, distance: isize) -> bool {
+    let previous_distance = distance.saturating_sub(1);
+    k == distance.saturating_neg()
+        || (k != distance
+            && furthest_on(previous, previous_distance, k.saturating_sub(1))
+                < furthest_on(previous, previous_distance, k.saturating_add(1)))
+}
+
+fn walk_back(trace: &[Vec<usize>], base_len: usize, len: usize) -> Vec<(usize, usize)> {
+    let mut common_lines = Vec::new();
+    let (mut x, mut y) = (base_len, len);
+    for distance in (0..trace.len()).rev() {
+        let (Ok(distance), Ok(x_signed), Ok(y_signed)) =
+            (isize::try_from(distance), isize::try_from(x), isize::try_from(y))
+        else {
+            break;
+        };
+        let (prev_x, prev_y) = if distance == 0 {
+            (0, 0)
+        } else {
+            let previous_distance = distance.saturating_sub(1);
+            let previous = usize::try_from(previous_distance)
+                .ok()
+                .and_then(|idx| trace.get(idx))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let k = x_signed.saturating_sub(y_signed);
+            let prev_k = if takes_insertion(previous, k, distance) {
+                k.saturating_add(1)
+            } else {
+                k.saturating_sub(1)
+            };
+            let prev_x = furthest_on(previous, previous_distance, prev_k);
+            let prev_y = isize::try_from(prev_x)
+                .ok()
+                .and_then(|prev_x| usize::try_from(prev_x.saturating_sub(prev_k)).ok())
+                .unwrap_or(0);
+            (prev_x, prev_y)
+        };
+        // The matching lines the step ended with
+        while x > prev_x && y > prev_y {
+            x = x.saturating_sub(1);
+            y = y.saturating_sub(1);
+            common_lines.push((x, y));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    common_lines.reverse();
+    common_lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Buffer, Location};
+    use super::{common_lines, diff, unchanged_base_lines, ChangeKind, Hunk, Line, MAX_EDIT_DISTANCE};
+
+    fn lines(texts: &[&str]) -> Vec<Line> {
+        texts.iter().map(|text| Line::from(text)).collect()
+    }
+    fn strings(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(ToString::to_string).collect()
+    }
+    fn ranges(hunks: &[Hunk]) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+        hunks
+            .iter()
+            .map(|hunk| (hunk.lines.clone(), hunk.base_lines.clone()))
+            .collect()
+    }
+    // Rebuilds the lines from the base by replacing the lines of each hunk
+    fn apply(base: &[String], hunks: &[Hunk], lines: &[Line]) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut base_idx = 0;
+        for hunk in hunks {
+            result.extend(base.get(base_idx..hunk.base_lines.start).unwrap_or_default().iter().cloned());
+            result.extend(lines.get(hunk.lines.clone()).unwrap_or_default().iter().map(ToString::to_string));
+            base_idx = hunk.base_lines.end;
+        }
+        result.extend(base.get(base_idx..).unwrap_or_default().iter().cloned());
+        result
+    }
+
+    #[test]
+    fn finds_insertions() {
+        let hunks = diff(&strings(&["a", "b", "c"]), &lines(&["a", "x", "y", "b", "c", "z"]));
+        assert_eq!(ranges(&hunks), [(1..3, 1..1), (5..6, 3..3)]);
+        assert!(hunks.iter().all(|hunk| hunk.kind() == ChangeKind::Added));
+    }
+
+    #[test]
+    fn finds_deletions() {
+        let hunks = diff(&strings(&["a", "b", "c", "d"]), &lines(&["a", "d"]));
+        assert_eq!(ranges(&hunks), [(1..1, 1..3)]);
+        assert_eq!(hunks.first().map(Hunk::kind), Some(ChangeKind::Deleted));
+        // Marked beneath the line above
+        assert_eq!(hunks.first().map(Hunk::marked_lines), Some(0..1));
+        assert_eq!(hunks.first().map(Hunk::marker), Some(super::DELETED_BELOW_MARKER));
+    }
+
+    #[test]
+    fn marks_deletions_at_the_start_above_the_first_line() {
+        let hunks = diff(&strings(&["a", "b", "c"]), &lines(&["c"]));
+        assert_eq!(ranges(&hunks), [(0..0, 0..2)]);
+        assert_eq!(hunks.first().map(Hunk::marked_lines), Some(0..1));
+        assert_eq!(hunks.first().map(Hunk::marker), Some(super::DELETED_ABOVE_MARKER));
+    }
+
+    #[test]
+    fn finds_modifications() {
+        let hunks = diff(&strings(&["a", "b", "c"]), &lines(&["a", "B", "c"]));
+        assert_eq!(ranges(&hunks), [(1..2, 1..2)]);
+        assert_eq!(hunks.first().map(Hunk::kind), Some(ChangeKind::Modified));
+    }
+
+    #[test]
+    fn treats_a_single_empty_line_as_an_empty_file() {
+        assert!(diff(&[], &lines(&[""])).is_empty());
+        assert!(diff(&[], &[]).is_empty());
+        assert_eq!(ranges(&diff(&strings(&["a"]), &lines(&[""]))), [(0..0, 0..1)]);
+        assert_eq!(ranges(&diff(&[], &lines(&["a"]))), [(0..1, 0..0)]);
+        // An empty line in a file is still a line
+        assert_eq!(ranges(&diff(&strings(&["a"]), &lines(&["a", ""]))), [(1..2, 1..1)]);
+    }
+
+    #[test]
+    fn walks_back_along_a_shortest_edit_script() {
+        let base = ["a", "b", "c", "a", "b", "b", "a"];
+        let other = ["c", "b", "a", "b", "a", "c"];
+        let common = common_lines(&base, &other).unwrap_or_default();
+        // The longest common subsequence has 4 lines
+        assert_eq!(common.len(), 4);
+        for pair in common.windows(2) {
+            if let [(base_a, a), (base_b, b)] = pair {
+                assert!(base_a < base_b && a < b);
+            }
+        }
+        assert!(common
+            .iter()
+            .all(|(base_idx, idx)| base.get(*base_idx) == other.get(*idx)));
+        assert_eq!(common_lines(&[], &[]), Some(Vec::new()));
+        assert_eq!(common_lines(&["a"], &["b"]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn gives_up_past_the_maximum_edit_distance() {
+        let base: Vec<String> = (0..MAX_EDIT_DISTANCE).flat_map(|idx| [format!("a{idx}"), String::from("=")]).collect();
+        let texts: Vec<String> = (0..MAX_EDIT_DISTANCE).flat_map(|idx| [format!("b{idx}"), String::from("=")]).collect();
+        let other: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let base_strs: Vec<&str> = base.iter().map(String::as_str).collect();
+        assert_eq!(common_lines(&base_strs, &other), None);
+        // Everything between the common ends is one changed hunk
+        let count = base.len().saturating_sub(1);
+        let hunks = diff(&base, &lines(&other));
+        assert_eq!(ranges(&hunks), [(0..count, 0..count)]);
+        assert_eq!(apply(&base, &hunks, &lines(&other)), texts);
+    }
+
+    #[test]
+    fn maps_unchanged_lines_to_their_base_lines() {
+        let mapping = unchanged_base_lines(&strings(&["a", "b", "c", "d"]), &lines(&["x", "a", "c", "y", "d"]));
+        assert_eq!(mapping, [None, Some(0), Some(2), None, Some(3)]);
+    }
+
+    #[test]
+    fn rediffs_the_edited_lines() {
+        let base = strings(&["fn main() {", "    let a = 1;", "    let b = 2;", "    a + b", "}", "", "// end"]);
+        let mut buffer = Buffer::default();
+        buffer.insert_text(&base.join("\n"), Location::default());
+        buffer.set_git_base(Some(base.clone()));
+        buffer.update_git_diff();
+        assert!(buffer.git_diff.is_empty());
+        // A pseudo-random sequence of edits, each one checked against the base
+        let mut seed: usize = 7;
+        let mut next = |limit: usize| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 8).checked_rem(limit).unwrap_or(0)
+        };
+        for _ in 0..500 {
+            let line_idx = next(buffer.height().saturating_add(1));
+            let at = Location { line_idx, grapheme_idx: 0 };
+            match next(4) {
+                0 => {
+                    buffer.insert_text("x\n", at);
+                }
+                1 => {
+                    let text = base.get(next(base.len())).cloned().unwrap_or_default();
+                    buffer.insert_text(&format!("{text}\n"), at);
+                }
+                2 => buffer.delete_range(at, Location { line_idx: line_idx.saturating_add(next(3)), grapheme_idx: 0 }),
+                _ => buffer.insert_char('y', at),
+            }
+            buffer.update_git_diff();
+            let hunks = buffer.git_diff.hunks.clone();
+            let texts: Vec<String> = buffer.lines.iter().map(ToString::to_string).collect();
+            if texts != [""] {
+                assert_eq!(apply(&base, &hunks, &buffer.lines), texts);
+            }
+            // Hunks are apart from each other
+            for pair in hunks.windows(2) {
+                if let [first, second] = pair {
+                    assert!(first.lines.end < second.lines.start);
+                }
+            }
+        }
+    }
+}
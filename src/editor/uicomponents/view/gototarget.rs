@@ -0,0 +1,52 @@
+// Where to jump to, as typed into the go-to-line prompt:
+// - `N` goes to line N, `N:C` to column C of line N (both counting from 1)
+// - `+N` and `-N` move N lines down or up
+// - `P%` goes to the line at P percent of the document
+#[derive(Copy, Clone, Debug)]
+pub enum GotoTarget {
+    Line { line: usize, col: Option<usize> },
+    Relative(isize),
+    Percent(usize),
+}
+
+impl GotoTarget {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let invalid = || format!("Invalid line '{input}'. Expected N, N:C, +N, -N or N%.");
+        if let Some(percent) = input.strip_suffix('%') {
+            return Self::parse_number(percent)
+                .filter(|percent| *percent <= 100)
+                .map(Self::Percent)
+                .ok_or_else(invalid);
+        }
+        if let Some(offset) = input.strip_prefix('+') {
+            return Self::parse_number(offset)
+                .and_then(|offset| isize::try_from(offset).ok())
+                .map(Self::Relative)
+                .ok_or_else(invalid);
+        }
+        if let Some(offset) = input.strip_prefix('-') {
+            return Self::parse_number(offset)
+                .and_then(|offset| isize::try_from(offset).ok())
+                .map(|offset| Self::Relative(offset.saturating_neg()))
+                .ok_or_else(invalid);
+        }
+        let (line, col) = input
+            .split_once(':')
+            .map_or((input, None), |(line, col)| (line, Some(col)));
+        let line = Self::parse_number(line).ok_or_else(invalid)?;
+        let col = match col {
+            Some(col) => Some(Self::parse_number(col).ok_or_else(invalid)?),
+            None => None,
+        };
+        Ok(Self::Line { line, col })
+    }
+
+    // Only plain digits are accepted, so that `+` and `-` can't sneak in twice
+    fn parse_number(input: &str) -> Option<usize> {
+        if input.is_empty() || !input.chars().all(|ch| ch.is_ascii_digit()) {
+            return None;
+        }
+        input.parse().ok()
+    }
+}
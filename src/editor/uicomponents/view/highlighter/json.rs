@@ -0,0 +1,36 @@
+use super::{AnnotationType, HighlightState, Scanner};
+
+// JSON strings can't span lines, so nothing is ever left open.
+pub fn highlight(scanner: &mut Scanner) -> HighlightState {
+    while let Some(character) = scanner.peek() {
+        let start = scanner.pos();
+        if character == '"' {
+            scanner.bump();
+            scanner.skip_quoted('"', true);
+            // Object keys are told apart from values by the colon following them
+            let is_key = scanner.rest().trim_start().starts_with(':');
+            let annotation_type = if is_key {
+                AnnotationType::Type
+            } else {
+                AnnotationType::String
+            };
+            scanner.highlight_from(start, annotation_type);
+        } else if character.is_ascii_digit() || character == '-' {
+            scanner.bump();
+            scanner.eat_while(|character| {
+                character.is_ascii_digit() || matches!(character, '.' | 'e' | 'E' | '+' | '-')
+            });
+            scanner.highlight_from(start, AnnotationType::Number);
+        } else if character.is_ascii_alphabetic() {
+            let word = scanner.eat_while(|character| character.is_ascii_alphabetic());
+            if matches!(word, "true" | "false" | "null") {
+                scanner.highlight_from(start, AnnotationType::Keyword);
+            }
+        } else if character.is_ascii_punctuation() {
+            scanner.highlight_char(AnnotationType::Punctuation);
+        } else {
+            scanner.bump();
+        }
+    }
+    HighlightState::Normal
+}
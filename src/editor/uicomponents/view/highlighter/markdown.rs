@@ -0,0 +1,133 @@
+use super::{AnnotationType, HighlightState, Scanner};
+
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+
+pub fn highlight(scanner: &mut Scanner, state: HighlightState) -> HighlightState {
+    match state {
+        HighlightState::CodeBlock(fence) => code_block_line(scanner, fence),
+        HighlightState::BlockComment(_) => {
+            let start = scanner.pos();
+            let closed = scanner.skip_past(COMMENT_END);
+            scanner.highlight_from(start, AnnotationType::Comment);
+            if closed {
+                inline(scanner)
+            } else {
+                state
+            }
+        }
+        _ => block(scanner),
+    }
+}
+
+// Inside a fenced code block, lines are shown as code until the closing fence.
+fn code_block_line(scanner: &mut Scanner, fence: char) -> HighlightState {
+    let is_fence = scanner.rest().trim_start().starts_with(&fence.to_string().repeat(3));
+    scanner.skip_to_end();
+    if is_fence {
+        scanner.highlight_from(0, AnnotationType::Punctuation);
+        HighlightState::Normal
+    } else {
+        scanner.highlight_from(0, AnnotationType::String);
+        HighlightState::CodeBlock(fence)
+    }
+}
+
+// Highlights what the line starts with: headings, quotes, fences, rules and list markers.
+fn block(scanner: &mut Scanner) -> HighlightState {
+    let trimmed = scanner.rest().trim();
+    for fence in ['`', '~'] {
+        if trimmed.starts_with(&fence.to_string().repeat(3)) {
+            scanner.skip_to_end();
+            scanner.highlight_from(0, AnnotationType::Punctuation);
+            return HighlightState::CodeBlock(fence);
+        }
+    }
+    let is_heading = trimmed.starts_with('#')
+        && trimmed
+            .trim_start_matches('#')
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace);
+    let is_rule = trimmed.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| trimmed.chars().all(|character| character == *marker || character == ' '));
+    if is_heading || is_rule || trimmed.starts_with('>') {
+        scanner.skip_to_end();
+        let annotation_type = if is_heading {
+            AnnotationType::Keyword
+        } else if is_rule {
+            AnnotationType::Punctuation
+        } else {
+            AnnotationType::Comment
+        };
+        scanner.highlight_from(0, annotation_type);
+        return HighlightState::Normal;
+    }
+    scanner.eat_while(char::is_whitespace);
+    let marker_start = scanner.pos();
+    let is_bullet = matches!(scanner.peek(), Some('-' | '*' | '+'))
+        && scanner.peek_nth(1).is_some_and(char::is_whitespace);
+    if is_bullet {
+        scanner.bump();
+    } else {
+        let digits = scanner.rest().len().saturating_sub(
+            scanner.rest().trim_start_matches(|character: char| character.is_ascii_digit()).len(),
+        );
+        let after_digits = scanner.rest().get(digits..).unwrap_or_default();
+        if digits > 0 && (after_digits.starts_with(". ") || after_digits.starts_with(") ")) {
+            scanner.advance(digits.saturating_add(1));
+        }
+    }
+    scanner.highlight_from(marker_start, AnnotationType::Punctuation);
+    inline(scanner)
+}
+
+// Highlights code spans, links and comments within the text.
+fn inline(scanner: &mut Scanner) -> HighlightState {
+    while let Some(character) = scanner.peek() {
+        let start = scanner.pos();
+        if scanner.eat(COMMENT_START) {
+            if !scanner.skip_past(COMMENT_END) {
+                scanner.highlight_from(start, AnnotationType::Comment);
+                return HighlightState::BlockComment(1);
+            }
+            scanner.highlight_from(start, AnnotationType::Comment);
+        } else if character == '`' {
+            let ticks = scanner.eat_while(|character| character == '`');
+            scanner.skip_past(ticks);
+            scanner.highlight_from(start, AnnotationType::String);
+        } else if character == '[' {
+            link(scanner);
+        } else {
+            scanner.bump();
+        }
+    }
+    HighlightState::Normal
+}
+
+// A link like `[text](target)`. A bracket without a target right behind it is left alone.
+fn link(scanner: &mut Scanner) {
+    let start = scanner.pos();
+    let rest = scanner.rest();
+    let Some(text_len) = rest.find(']').map(|idx| idx.saturating_add(1)) else {
+        scanner.bump();
+        return;
+    };
+    let after_text = rest.get(text_len..).unwrap_or_default();
+    let target_len = after_text
+        .starts_with('(')
+        .then(|| after_text.find(')'))
+        .flatten()
+        .map(|idx| idx.saturating_add(1));
+    let Some(target_len) = target_len else {
+        scanner.bump();
+        return;
+    };
+    scanner.advance(text_len);
+    scanner.highlight_from(start, AnnotationType::Type);
+    let target_start = scanner.pos();
+    scanner.advance(target_len);
+    scanner.highlight_from(target_start, AnnotationType::String);
+}
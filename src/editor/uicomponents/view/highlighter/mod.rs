@@ -0,0 +1,240 @@
+use super::super::super::AnnotationType;
+use super::{FileType, Line};
+
+mod json;
+mod markdown;
+mod rust;
+mod scanner;
+use scanner::Scanner;
+mod shell;
+mod toml;
+
+// A syntax element found in a line, by byte index
+#[derive(Clone, Debug)]
+pub struct Highlight {
+    pub annotation_type: AnnotationType,
+    pub start_byte_idx: usize,
+    pub end_byte_idx: usize,
+}
+
+// What is still open at the end of a line, and so continues on the next one
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+enum HighlightState {
+    #[default]
+    Normal,
+    // Counting how many comments are open, for languages nesting them
+    BlockComment(usize),
+    // Opened by the given quote
+    String(char),
+    // Rust raw string, closed by a quote followed by this many hashes
+    RawString(usize),
+    // TOML multi-line string, closed by three of the given quote
+    TripleQuoted(char),
+    // Markdown fenced code block, closed by three of the given fence character
+    CodeBlock(char),
+}
+
+#[derive(Default)]
+struct HighlightedLine {
+    highlights: Vec<Highlight>,
+    end_state: HighlightState,
+}
+
+// The syntax highlights of a buffer. Lines are highlighted from the top as far as they are shown,
+// and edits only discard the highlights from the first edited line downward.
+#[derive(Default)]
+pub struct Highlighter {
+    file_type: FileType,
+    // Highlights of the first lines of the buffer, as far as they are known
+    lines: Vec<HighlightedLine>,
+}
+
+impl Highlighter {
+    pub fn new(file_type: FileType) -> Self {
+        Self {
+            file_type,
+            lines: Vec::new(),
+        }
+    }
+    // Forgets the highlights of the given line and all lines after it, whose state may depend on it.
+    pub fn invalidate_from(&mut self, line_idx: usize) {
+        self.lines.truncate(line_idx);
+    }
+    // Makes sure all lines up to and including the given one are highlighted.
+    pub fn highlight_until(&mut self, lines: &[Line], line_idx: usize) {
+        if self.file_type == FileType::Text {
+            return;
+        }
+        let mut state = self
+            .lines
+            .last()
+            .map_or(HighlightState::Normal, |line| line.end_state);
+        for line in lines
+            .iter()
+            .take(line_idx.saturating_add(1))
+            .skip(self.lines.len())
+        {
+            let mut highlights = Vec::new();
+            state = self.highlight_line(line, state, &mut highlights);
+            self.lines.push(HighlightedLine {
+                highlights,
+                end_state: state,
+            });
+        }
+    }
+    // The highlights of the given line, or none if it wasn't highlighted yet.
+    pub fn highlights(&self, line_idx: usize) -> &[Highlight] {
+        self.lines
+            .get(line_idx)
+            .map_or(&[], |line| line.highlights.as_slice())
+    }
+
+    fn highlight_line(
+        &self,
+        text: &str,
+        state: HighlightState,
+        highlights: &mut Vec<Highlight>,
+    ) -> HighlightState {
+        let mut scanner = Scanner::new(text, highlights);
+        match self.file_type {
+            FileType::Rust => rust::highlight(&mut scanner, state),
+            FileType::Toml => toml::highlight(&mut scanner, state),
+            FileType::Markdown => markdown::highlight(&mut scanner, state),
+            FileType::Json => json::highlight(&mut scanner),
+            FileType::Shell => shell::highlight(&mut scanner, state),
+            FileType::Text => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnotationType, FileType, HighlightState, Highlighter, Line};
+
+    // A line of text, the highlights expected on it as the text they cover, and the state it should end in
+    type LineCase<'a> = (&'a str, &'a [(AnnotationType, &'a str)], HighlightState);
+
+    fn lines(texts: &[&str]) -> Vec<Line> {
+        texts.iter().map(|text| Line::from(text)).collect()
+    }
+    fn highlighted<'a>(highlighter: &Highlighter, line_idx: usize, text: &'a str) -> Vec<(AnnotationType, &'a str)> {
+        highlighter
+            .highlights(line_idx)
+            .iter()
+            .map(|highlight| {
+                let covered = text.get(highlight.start_byte_idx..highlight.end_byte_idx);
+                (highlight.annotation_type, covered.unwrap_or_default())
+            })
+            .collect()
+    }
+    fn end_states(highlighter: &Highlighter) -> Vec<HighlightState> {
+        highlighter.lines.iter().map(|line| line.end_state).collect()
+    }
+
+    #[test]
+    fn carries_state_across_lines() {
+        let nested_comments: &[LineCase] = &[
+            (
+                "/* a /* b",
+                &[(AnnotationType::Comment, "/*"), (AnnotationType::Comment, " a /* b")],
+                HighlightState::BlockComment(2),
+            ),
+            ("c */ d", &[(AnnotationType::Comment, "c */ d")], HighlightState::BlockComment(1)),
+            ("e */ f", &[(AnnotationType::Comment, "e */")], HighlightState::Normal),
+        ];
+        let raw_string: &[LineCase] = &[
+            (
+                r#"r#"a "b""#,
+                &[(AnnotationType::String, "r#\""), (AnnotationType::String, "a \"b\"")],
+                HighlightState::RawString(1),
+            ),
+            (r##"c"# x"##, &[(AnnotationType::String, "c\"#")], HighlightState::Normal),
+        ];
+        let triple_quotes: &[LineCase] = &[
+            (
+                r#"a = """"#,
+                &[
+                    (AnnotationType::Type, "a"),
+                    (AnnotationType::Punctuation, "="),
+                    (AnnotationType::String, r#"""""#),
+                ],
+                HighlightState::TripleQuoted('"'),
+            ),
+            (r#"b "" c"#, &[(AnnotationType::String, r#"b "" c"#)], HighlightState::TripleQuoted('"')),
+            // Up to two more quotes belong to the string
+            (r#"d"""""#, &[(AnnotationType::String, r#"d"""""#)], HighlightState::Normal),
+            (
+                "e = '''",
+                &[
+                    (AnnotationType::Type, "e"),
+                    (AnnotationType::Punctuation, "="),
+                    (AnnotationType::String, "'''"),
+                ],
+                HighlightState::TripleQuoted('\''),
+            ),
+            ("f'''", &[(AnnotationType::String, "f'''")], HighlightState::Normal),
+        ];
+        let fences: &[LineCase] = &[
+            ("```rust", &[(AnnotationType::Punctuation, "```rust")], HighlightState::CodeBlock('`')),
+            ("# not a heading", &[(AnnotationType::String, "# not a heading")], HighlightState::CodeBlock('`')),
+            ("```", &[(AnnotationType::Punctuation, "```")], HighlightState::Normal),
+            ("# Heading", &[(AnnotationType::Keyword, "# Heading")], HighlightState::Normal),
+            ("~~~", &[(AnnotationType::Punctuation, "~~~")], HighlightState::CodeBlock('~')),
+            // Only the same fence closes the block
+            ("```", &[(AnnotationType::String, "```")], HighlightState::CodeBlock('~')),
+        ];
+        let cases = [
+            (FileType::Rust, nested_comments),
+            (FileType::Rust, raw_string),
+            (FileType::Toml, triple_quotes),
+            (FileType::Markdown, fences),
+        ];
+        for (file_type, case) in cases {
+            let texts: Vec<&str> = case.iter().map(|(text, _, _)| *text).collect();
+            let mut highlighter = Highlighter::new(file_type);
+            highlighter.highlight_until(&lines(&texts), texts.len());
+            for (line_idx, (text, highlights, _)) in case.iter().enumerate() {
+                assert_eq!(highlighted(&highlighter, line_idx, text), *highlights, "{file_type:?} line {line_idx}");
+            }
+            let states: Vec<HighlightState> = case.iter().map(|(_, _, state)| *state).collect();
+            assert_eq!(end_states(&highlighter), states, "{file_type:?}");
+        }
+    }
+
+    #[test]
+    fn highlights_only_as_far_as_asked() {
+        let lines = lines(&["/* a", "b */", "c"]);
+        let mut highlighter = Highlighter::new(FileType::Rust);
+        highlighter.highlight_until(&lines, 1);
+        assert_eq!(end_states(&highlighter), [HighlightState::BlockComment(1), HighlightState::Normal]);
+        assert!(highlighter.highlights(2).is_empty());
+        // Plain text has nothing to highlight
+        let mut highlighter = Highlighter::new(FileType::Text);
+        highlighter.highlight_until(&lines, 2);
+        assert!(end_states(&highlighter).is_empty());
+    }
+
+    #[test]
+    fn highlights_again_from_the_invalidated_line() {
+        let mut texts = vec!["/* a", "b */ c", "d"];
+        let mut highlighter = Highlighter::new(FileType::Rust);
+        highlighter.highlight_until(&lines(&texts), 2);
+        assert_eq!(highlighted(&highlighter, 1, texts[1]), [(AnnotationType::Comment, "b */")]);
+
+        // The comment no longer continues on the next line
+        texts[0] = "// a";
+        highlighter.invalidate_from(0);
+        assert!(highlighter.highlights(0).is_empty());
+        highlighter.highlight_until(&lines(&texts), 2);
+        assert_eq!(end_states(&highlighter), [HighlightState::Normal; 3]);
+        assert_eq!(
+            highlighted(&highlighter, 1, texts[1]),
+            [(AnnotationType::Punctuation, "*"), (AnnotationType::Punctuation, "/")]
+        );
+
+        // The lines before the invalidated one are kept
+        highlighter.invalidate_from(2);
+        assert_eq!(end_states(&highlighter), [HighlightState::Normal; 2]);
+        assert_eq!(highlighted(&highlighter, 0, texts[0]), [(AnnotationType::Comment, "// a")]);
+    }
+}
@@ -0,0 +1,132 @@
+use super::scanner::{is_identifier_char, is_identifier_start};
+use super::{AnnotationType, HighlightState, Scanner};
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+const PRIMITIVE_TYPES: [&str; 17] = [
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32",
+    "f64", "bool", "char", "str",
+];
+
+pub fn highlight(scanner: &mut Scanner, mut state: HighlightState) -> HighlightState {
+    loop {
+        state = match state {
+            HighlightState::BlockComment(depth) => block_comment(scanner, depth),
+            HighlightState::String(quote) => {
+                let start = scanner.pos();
+                let closed = scanner.skip_quoted(quote, true);
+                scanner.highlight_from(start, AnnotationType::String);
+                if closed {
+                    HighlightState::Normal
+                } else {
+                    state
+                }
+            }
+            HighlightState::RawString(hashes) => {
+                let start = scanner.pos();
+                let closed = scanner.skip_past(&format!("\"{}", "#".repeat(hashes)));
+                scanner.highlight_from(start, AnnotationType::String);
+                if closed {
+                    HighlightState::Normal
+                } else {
+                    state
+                }
+            }
+            _ => token(scanner),
+        };
+        if scanner.is_done() {
+            return state;
+        }
+    }
+}
+
+// Highlights a (possibly nested) block comment until it's closed or the line ends.
+fn block_comment(scanner: &mut Scanner, mut depth: usize) -> HighlightState {
+    let start = scanner.pos();
+    while depth > 0 && !scanner.is_done() {
+        if scanner.eat("*/") {
+            depth = depth.saturating_sub(1);
+        } else if scanner.eat("/*") {
+            depth = depth.saturating_add(1);
+        } else {
+            scanner.bump();
+        }
+    }
+    scanner.highlight_from(start, AnnotationType::Comment);
+    if depth == 0 {
+        HighlightState::Normal
+    } else {
+        HighlightState::BlockComment(depth)
+    }
+}
+
+// Highlights the next token, returning the state it leaves open.
+fn token(scanner: &mut Scanner) -> HighlightState {
+    let Some(character) = scanner.peek() else {
+        return HighlightState::Normal;
+    };
+    let start = scanner.pos();
+    if scanner.starts_with("//") {
+        scanner.skip_to_end();
+        scanner.highlight_from(start, AnnotationType::Comment);
+    } else if scanner.eat("/*") {
+        scanner.highlight_from(start, AnnotationType::Comment);
+        return HighlightState::BlockComment(1);
+    } else if let Some(hashes) = raw_string_start(scanner) {
+        scanner.highlight_from(start, AnnotationType::String);
+        return HighlightState::RawString(hashes);
+    } else if scanner.eat("b\"") || scanner.eat("\"") {
+        scanner.highlight_from(start, AnnotationType::String);
+        return HighlightState::String('"');
+    } else if character == '\'' || scanner.starts_with("b'") {
+        char_or_lifetime(scanner);
+    } else if character.is_ascii_digit() {
+        scanner.skip_number();
+        scanner.highlight_from(start, AnnotationType::Number);
+    } else if is_identifier_start(character) {
+        let word = scanner.eat_while(is_identifier_char);
+        if KEYWORDS.contains(&word) {
+            scanner.highlight_from(start, AnnotationType::Keyword);
+        } else if PRIMITIVE_TYPES.contains(&word) || word.starts_with(char::is_uppercase) {
+            scanner.highlight_from(start, AnnotationType::Type);
+        }
+    } else if character.is_ascii_punctuation() {
+        scanner.highlight_char(AnnotationType::Punctuation);
+    } else {
+        scanner.bump();
+    }
+    HighlightState::Normal
+}
+
+// Skips the start of a raw string like `r#"` or `br"` and returns its number of hashes.
+fn raw_string_start(scanner: &mut Scanner) -> Option<usize> {
+    let rest = scanner.rest();
+    let after_prefix = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r'))?;
+    let hashes = after_prefix.len().saturating_sub(after_prefix.trim_start_matches('#').len());
+    let prefix_len = rest.len().saturating_sub(after_prefix.len());
+    if after_prefix.get(hashes..)?.starts_with('"') {
+        scanner.advance(prefix_len.saturating_add(hashes).saturating_add(1));
+        Some(hashes)
+    } else {
+        None
+    }
+}
+
+// A quote starts either a character literal like `'a'` or `'\n'`, or a lifetime like `'a`.
+fn char_or_lifetime(scanner: &mut Scanner) {
+    let start = scanner.pos();
+    scanner.eat("b");
+    scanner.bump();
+    let is_char = scanner.peek() == Some('\\') || scanner.peek_nth(1) == Some('\'');
+    if is_char {
+        scanner.skip_quoted('\'', true);
+        scanner.highlight_from(start, AnnotationType::String);
+    } else {
+        scanner.eat_while(is_identifier_char);
+        scanner.highlight_from(start, AnnotationType::Type);
+    }
+}
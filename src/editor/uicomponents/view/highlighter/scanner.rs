@@ -0,0 +1,125 @@
+use super::{AnnotationType, Highlight};
+
+// Walks through a line of text for the tokenizers, collecting the highlights they find.
+pub struct Scanner<'a> {
+    text: &'a str,
+    // Byte index of the next character
+    pos: usize,
+    highlights: &'a mut Vec<Highlight>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(text: &'a str, highlights: &'a mut Vec<Highlight>) -> Self {
+        Self {
+            text,
+            pos: 0,
+            highlights,
+        }
+    }
+    pub const fn pos(&self) -> usize {
+        self.pos
+    }
+    pub fn is_done(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+    pub fn rest(&self) -> &'a str {
+        self.text.get(self.pos..).unwrap_or_default()
+    }
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+    pub fn starts_with(&self, pattern: &str) -> bool {
+        self.rest().starts_with(pattern)
+    }
+    pub fn bump(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.pos = self.pos.saturating_add(character.len_utf8());
+        Some(character)
+    }
+    // Skips the pattern if the text continues with it.
+    pub fn eat(&mut self, pattern: &str) -> bool {
+        if self.starts_with(pattern) {
+            self.pos = self.pos.saturating_add(pattern.len());
+            true
+        } else {
+            false
+        }
+    }
+    pub fn eat_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.bump();
+        }
+        self.text.get(start..self.pos).unwrap_or_default()
+    }
+    // Skips the given number of bytes, which must end on a character boundary.
+    pub fn advance(&mut self, byte_len: usize) {
+        self.pos = self.pos.saturating_add(byte_len).min(self.text.len());
+    }
+    pub fn skip_to_end(&mut self) {
+        self.pos = self.text.len();
+    }
+
+    // Skips everything up to and including the closing quote, ignoring escaped characters if the quote
+    // knows them. Returns false if the line ends first.
+    pub fn skip_quoted(&mut self, quote: char, with_escapes: bool) -> bool {
+        while let Some(character) = self.bump() {
+            if character == quote {
+                return true;
+            }
+            if with_escapes && character == '\\' {
+                self.bump();
+            }
+        }
+        false
+    }
+    // Skips up to and including the pattern. Returns false if the line ends first.
+    pub fn skip_past(&mut self, pattern: &str) -> bool {
+        if let Some(idx) = self.rest().find(pattern) {
+            self.pos = self.pos.saturating_add(idx).saturating_add(pattern.len());
+            true
+        } else {
+            self.skip_to_end();
+            false
+        }
+    }
+    // Skips digits along with prefixes, suffixes, separators and a fractional part, e.g. `0xFF_u8` or `1.5e3`.
+    pub fn skip_number(&mut self) {
+        loop {
+            self.eat_while(|character| character.is_ascii_alphanumeric() || character == '_');
+            // `1.5` but not `1..2` nor `1.max(2)`
+            if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|next| next.is_ascii_digit()) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Highlights everything from the given byte index up to the current position.
+    pub fn highlight_from(&mut self, start_byte_idx: usize, annotation_type: AnnotationType) {
+        if start_byte_idx < self.pos {
+            self.highlights.push(Highlight {
+                annotation_type,
+                start_byte_idx,
+                end_byte_idx: self.pos,
+            });
+        }
+    }
+    // Highlights the next character.
+    pub fn highlight_char(&mut self, annotation_type: AnnotationType) {
+        let start = self.pos;
+        self.bump();
+        self.highlight_from(start, annotation_type);
+    }
+}
+
+pub fn is_identifier_start(character: char) -> bool {
+    character.is_alphabetic() || character == '_'
+}
+pub fn is_identifier_char(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
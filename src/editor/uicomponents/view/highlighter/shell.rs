@@ -0,0 +1,88 @@
+use super::scanner::{is_identifier_char, is_identifier_start};
+use super::{AnnotationType, HighlightState, Scanner};
+
+const KEYWORDS: [&str; 27] = [
+    "if", "then", "else", "elif", "fi", "case", "esac", "for", "while", "until", "do", "done", "in",
+    "function", "select", "time", "return", "break", "continue", "local", "export", "readonly",
+    "declare", "unset", "shift", "exit", "source",
+];
+// Characters with a meaning of their own, which end words
+const OPERATORS: &str = "|&;()<>{}[]=!";
+
+pub fn highlight(scanner: &mut Scanner, mut state: HighlightState) -> HighlightState {
+    loop {
+        state = match state {
+            // Single quoted strings know no escapes, double quoted ones do
+            HighlightState::String(quote) => {
+                let start = scanner.pos();
+                let closed = scanner.skip_quoted(quote, quote == '"');
+                scanner.highlight_from(start, AnnotationType::String);
+                if closed {
+                    HighlightState::Normal
+                } else {
+                    state
+                }
+            }
+            _ => token(scanner),
+        };
+        if scanner.is_done() {
+            return state;
+        }
+    }
+}
+
+// Highlights the next token, returning the state it leaves open.
+fn token(scanner: &mut Scanner) -> HighlightState {
+    let Some(character) = scanner.peek() else {
+        return HighlightState::Normal;
+    };
+    let start = scanner.pos();
+    if character == '#' {
+        // Words are skipped as a whole below, so this starts a word: `a#b` isn't a comment
+        scanner.skip_to_end();
+        scanner.highlight_from(start, AnnotationType::Comment);
+    } else if character == '"' || character == '\'' {
+        scanner.highlight_char(AnnotationType::String);
+        return HighlightState::String(character);
+    } else if character == '\\' {
+        scanner.bump();
+        scanner.bump();
+    } else if character == '$' {
+        variable(scanner);
+    } else if OPERATORS.contains(character) {
+        scanner.highlight_char(AnnotationType::Punctuation);
+    } else if character.is_whitespace() {
+        scanner.bump();
+    } else {
+        let word = scanner.eat_while(|character| {
+            !character.is_whitespace() && !OPERATORS.contains(character) && !"\"'$\\".contains(character)
+        });
+        if KEYWORDS.contains(&word) {
+            scanner.highlight_from(start, AnnotationType::Keyword);
+        } else if word.chars().all(|character| character.is_ascii_digit()) {
+            scanner.highlight_from(start, AnnotationType::Number);
+        }
+    }
+    HighlightState::Normal
+}
+
+// Highlights variables like `$name`, `${name}`, `$1` or `$?`. Command substitutions only get their `$` highlighted.
+fn variable(scanner: &mut Scanner) {
+    let start = scanner.pos();
+    scanner.bump();
+    match scanner.peek() {
+        Some('{') => {
+            scanner.skip_past("}");
+            scanner.highlight_from(start, AnnotationType::Type);
+        }
+        Some(character) if is_identifier_start(character) => {
+            scanner.eat_while(is_identifier_char);
+            scanner.highlight_from(start, AnnotationType::Type);
+        }
+        Some(character) if character.is_ascii_digit() || "?#@*!$-".contains(character) => {
+            scanner.bump();
+            scanner.highlight_from(start, AnnotationType::Type);
+        }
+        _ => scanner.highlight_from(start, AnnotationType::Punctuation),
+    }
+}
@@ -0,0 +1,100 @@
+use super::{AnnotationType, HighlightState, Scanner};
+
+pub fn highlight(scanner: &mut Scanner, mut state: HighlightState) -> HighlightState {
+    if state == HighlightState::Normal {
+        line_start(scanner);
+    }
+    loop {
+        state = match state {
+            HighlightState::TripleQuoted(quote) => {
+                let start = scanner.pos();
+                let closed = skip_multi_line_string(scanner, quote);
+                scanner.highlight_from(start, AnnotationType::String);
+                if closed {
+                    HighlightState::Normal
+                } else {
+                    state
+                }
+            }
+            _ => value(scanner),
+        };
+        if scanner.is_done() {
+            return state;
+        }
+    }
+}
+
+// Highlights a table header like `[package]` or the key of a `key = value` line.
+fn line_start(scanner: &mut Scanner) {
+    scanner.eat_while(char::is_whitespace);
+    let start = scanner.pos();
+    if scanner.peek() == Some('[') {
+        scanner.skip_past("]");
+        scanner.eat("]");
+        scanner.highlight_from(start, AnnotationType::Type);
+    } else if let Some(equal_idx) = scanner.rest().find('=') {
+        let key = scanner.rest().get(..equal_idx).unwrap_or_default();
+        if !key.contains(['"', '\'', '#', '[', '{', ',']) || key.starts_with(['"', '\'']) {
+            scanner.advance(key.trim_end().len());
+            scanner.highlight_from(start, AnnotationType::Type);
+        }
+    }
+}
+
+// Skips to the end of a multi-line string, where the quotes may be followed by up to two more.
+fn skip_multi_line_string(scanner: &mut Scanner, quote: char) -> bool {
+    let closing: String = [quote; 3].iter().collect();
+    while !scanner.is_done() {
+        if scanner.eat(&closing) {
+            scanner.eat_while(|character| character == quote);
+            return true;
+        }
+        if quote == '"' && scanner.peek() == Some('\\') {
+            scanner.bump();
+        }
+        scanner.bump();
+    }
+    false
+}
+
+// Highlights the next token of a value, returning the state it leaves open.
+fn value(scanner: &mut Scanner) -> HighlightState {
+    let Some(character) = scanner.peek() else {
+        return HighlightState::Normal;
+    };
+    let start = scanner.pos();
+    if character == '#' {
+        scanner.skip_to_end();
+        scanner.highlight_from(start, AnnotationType::Comment);
+    } else if scanner.eat("\"\"\"") || scanner.eat("'''") {
+        scanner.highlight_from(start, AnnotationType::String);
+        return HighlightState::TripleQuoted(character);
+    } else if character == '"' || character == '\'' {
+        scanner.bump();
+        scanner.skip_quoted(character, character == '"');
+        scanner.highlight_from(start, AnnotationType::String);
+    } else if character.is_ascii_digit()
+        || (matches!(character, '+' | '-')
+            && scanner.peek_nth(1).is_some_and(|next| next.is_ascii_digit() || next == 'i' || next == 'n'))
+    {
+        // Also covers dates and times like `1979-05-27T07:32:00Z`
+        scanner.bump();
+        scanner.eat_while(|character| {
+            character.is_ascii_alphanumeric() || matches!(character, '_' | '.' | ':' | '-' | '+')
+        });
+        scanner.highlight_from(start, AnnotationType::Number);
+    } else if character.is_alphabetic() {
+        let word = scanner.eat_while(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '-'));
+        match word {
+            "true" | "false" => scanner.highlight_from(start, AnnotationType::Keyword),
+            "inf" | "nan" => scanner.highlight_from(start, AnnotationType::Number),
+            // A key within an inline table
+            _ => scanner.highlight_from(start, AnnotationType::Type),
+        }
+    } else if character.is_ascii_punctuation() {
+        scanner.highlight_char(AnnotationType::Punctuation);
+    } else {
+        scanner.bump();
+    }
+    HighlightState::Normal
+}
@@ -0,0 +1,63 @@
+use crate::editor::config::Config;
+
+// How lines are indented: with tabs or spaces, and how wide an indentation level is.
+#[derive(Copy, Clone)]
+pub struct Indentation {
+    use_tabs: bool,
+    width: usize,
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self::from(&Config::default())
+    }
+}
+
+impl Indentation {
+    pub fn from(config: &Config) -> Self {
+        Self {
+            use_tabs: config.indent_with_tabs,
+            width: config.indent_width.max(1),
+        }
+    }
+
+    // Returns the leading whitespace shifted one level to the right (or to the left when outdenting).
+    // The new whitespace snaps to a multiple of the indentation width and only uses the configured
+    // character, so lines mixing tabs and spaces are normalised along the way.
+    pub fn shift(self, leading_whitespace: &str, outdent: bool) -> String {
+        let current_width = leading_whitespace.chars().fold(0, |width: usize, ch| {
+            if ch == '\t' {
+                self.next_level(width)
+            } else {
+                width.saturating_add(1)
+            }
+        });
+        let new_width = if outdent {
+            self.previous_level(current_width)
+        } else {
+            self.next_level(current_width)
+        };
+        if self.use_tabs {
+            let tabs = new_width.checked_div(self.width).unwrap_or(0);
+            "\t".repeat(tabs)
+        } else {
+            " ".repeat(new_width)
+        }
+    }
+
+    fn next_level(self, width: usize) -> usize {
+        width
+            .checked_div(self.width)
+            .unwrap_or(0)
+            .saturating_add(1)
+            .saturating_mul(self.width)
+    }
+
+    fn previous_level(self, width: usize) -> usize {
+        width
+            .saturating_sub(1)
+            .checked_div(self.width)
+            .unwrap_or(0)
+            .saturating_mul(self.width)
+    }
+}
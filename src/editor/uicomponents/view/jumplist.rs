@@ -0,0 +1,51 @@
+use super::{BufferEdit, Location};
+
+const MAX_JUMPS: usize = 100;
+
+// The locations the caret jumped away from, to travel back (and forward again) through the caret history.
+// Like bookmarks, the locations move along with their text as the buffer is edited.
+#[derive(Default)]
+pub struct JumpList {
+    jumps: Vec<Location>,
+    // Index of the jump the caret is at while travelling through the list, jumps.len() otherwise
+    current: usize,
+}
+
+impl JumpList {
+    // Records the location the caret is about to jump away from. Jumps ahead of the current one are forgotten,
+    // and so is any older jump to the same line.
+    pub fn record(&mut self, location: Location) {
+        self.jumps.truncate(self.current);
+        self.jumps.retain(|jump| jump.line_idx != location.line_idx);
+        self.jumps.push(location);
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.current = self.jumps.len();
+    }
+
+    // Returns the location to go back to from the caret location, if any.
+    pub fn back(&mut self, from: Location) -> Option<Location> {
+        if self.current >= self.jumps.len() {
+            // Remember where we started, so that going forward again brings us back here
+            self.record(from);
+            self.current = self.jumps.len().saturating_sub(1);
+        }
+        self.current = self.current.checked_sub(1)?;
+        self.jumps.get(self.current).copied()
+    }
+
+    // Returns the location to go forward to, if we went back before.
+    pub fn forward(&mut self) -> Option<Location> {
+        let next = self.current.saturating_add(1);
+        let location = self.jumps.get(next).copied()?;
+        self.current = next;
+        Some(location)
+    }
+
+    pub fn adjust(&mut self, edit: BufferEdit) {
+        for jump in &mut self.jumps {
+            *jump = edit.adjust(*jump);
+        }
+    }
+}
@@ -1,5 +1,22 @@
-#[derive(Copy, Clone, Default)]
+use std::cmp::Ordering;
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
 pub struct Location {
     pub grapheme_idx: usize,
     pub line_idx: usize,
 }
+
+// Locations are ordered as they appear in the text: by line first, then by grapheme
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.line_idx
+            .cmp(&other.line_idx)
+            .then(self.grapheme_idx.cmp(&other.grapheme_idx))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
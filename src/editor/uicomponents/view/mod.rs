@@ -1,12 +1,23 @@
-use std::{cmp::min, io::Error};
+use std::{
+    cell::{Ref, RefCell},
+    cmp::min,
+    io::Error,
+    ops::{Range, RangeInclusive},
+};
+
+use unicode_segmentation::UnicodeSegmentation;
 
 use super::super::{
-    command::{Edit, Move},
-    Col, Row, DocumentStatus, Line, Position, Size, Terminal, NAME, VERSION,
+    command::{Edit, Move, Select},
+    config::{Config, FoldMethod, LineNumbers},
+    AnnotatedString, AnnotationType, Col, Row, DocumentStatus, Line, Position, Size, Terminal, NAME,
+    VERSION,
 };
-use super::UIComponent;
+use super::{Gutter, GutterRow, UIComponent, WordRanking};
 mod buffer;
 use buffer::Buffer;
+mod bufferhandle;
+pub use bufferhandle::BufferHandle;
 mod searchdirection;
 use searchdirection::SearchDirection;
 mod location;
@@ -15,32 +26,177 @@ mod fileinfo;
 use fileinfo::FileInfo;
 mod searchinfo;
 use searchinfo::SearchInfo;
+mod filetype;
+pub use filetype::FileType;
+mod snippets;
+use snippets::{ExpandedSnippet, Snippet, SnippetLibrary, SnippetSession, SnippetVariables, TabStop};
+mod numberliteral;
+use numberliteral::NumberLiteral;
+mod selection;
+use selection::{Selection, SelectionMode};
+mod indentation;
+use indentation::Indentation;
+mod gototarget;
+pub use gototarget::GotoTarget;
+mod bufferedit;
+use bufferedit::BufferEdit;
+mod bookmarks;
+use bookmarks::Bookmarks;
+mod jumplist;
+use jumplist::JumpList;
+mod screenrow;
+use screenrow::ScreenRow;
+mod rowcache;
+use rowcache::RowCache;
+mod folds;
+use folds::Folds;
+mod highlighter;
+pub use highlighter::Highlight;
+use highlighter::Highlighter;
+mod diagnostics;
+pub use diagnostics::{DiagnosticReport, Severity};
+use diagnostics::{Diagnostic, Diagnostics};
+mod textedit;
+pub use textedit::TextEdit;
+mod gitdiff;
+use gitdiff::GitDiff;
+mod blame;
+pub use blame::{BlameCommit, BlameLine};
+use blame::Blame;
 
+const ANONYMOUS_BOOKMARK_SIGN: char = '●';
+const FOLD_SIGN: char = '▸';
+// Moving the caret by more than this many lines at once is recorded in the jump list
+const JUMP_LINE_THRESHOLD: usize = 5;
+
+// clippy::struct_excessive_bools: the display settings are independent of each other
 #[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct View {
-    buffer: Buffer,
+    // Shared with the other views showing the same buffer
+    buffer: BufferHandle,
+    // The buffer revision this view has caught up with, see sync_with_buffer
+    buffer_revision: usize,
     needs_redraw: bool,
     size: Size,
     text_location: Location,
     scroll_offset: Position,
     search_info: Option<SearchInfo>,
+    snippets: SnippetLibrary,
+    snippet_session: Option<SnippetSession>,
+    selection: Option<Selection>,
+    indentation: Indentation,
+    // The visual column vertical moves aim for, and the location it was reached at.
+    // Only valid while the caret stays at that location, i.e. until it moves other than vertically.
+    sticky_col: Option<(Location, Col)>,
+    // Number of lines kept visible above and below the caret
+    scroll_off: usize,
+    // When set, long lines continue on the next rows instead of requiring horizontal scrolling
+    soft_wrap: bool,
+    // Draws tabs, spaces and line ends as symbols
+    show_whitespace: bool,
+    // Columns tinted as vertical rulers, counting from 1
+    rulers: Vec<usize>,
+    highlight_current_line: bool,
+    gutter: Gutter,
+    fold_method: FoldMethod,
+    // Caught up with the buffer when used, see folds
+    folds: RefCell<Folds>,
+    // Who last changed each line, when turned on
+    blame: Option<Blame>,
+    // How many rows the lines take, see rows_before
+    row_cache: RefCell<RowCache>,
 }
 
 impl View {
+    pub fn configure(&mut self, config: &Config) {
+        self.indentation = Indentation::from(config);
+        self.scroll_off = config.scroll_off;
+        self.soft_wrap = config.soft_wrap;
+        self.show_whitespace = config.show_whitespace;
+        self.rulers.clone_from(&config.rulers);
+        self.highlight_current_line = config.highlight_current_line;
+        self.gutter.set_line_numbers(config.line_numbers);
+        self.fold_method = config.fold_method;
+    }
 
     pub fn get_status(&self) -> DocumentStatus {
         DocumentStatus {
-            total_lines: self.buffer.height(),
+            total_lines: self.buffer.borrow().height(),
             current_line_idx: self.text_location.line_idx,
-            file_name: format!("{}", self.buffer.file_info),
-            is_modified: self.buffer.dirty,
+            file_name: format!("{}", self.buffer.borrow().file_info),
+            is_modified: self.buffer.borrow().dirty,
         }
     }
 
-    pub const fn is_file_loaded(&self) -> bool {
-        self.buffer.is_file_loaded()
+    pub fn is_file_loaded(&self) -> bool {
+        self.buffer.borrow().is_file_loaded()
     }
 
+    // region: shared buffer
+    // Creates another view on the same buffer, with the same settings and starting out where this one is.
+    pub fn split(&self) -> Self {
+        let mut view = Self {
+            buffer: self.buffer.clone(),
+            buffer_revision: self.buffer_revision,
+            size: self.size,
+            text_location: self.text_location,
+            scroll_offset: self.scroll_offset,
+            indentation: self.indentation,
+            scroll_off: self.scroll_off,
+            soft_wrap: self.soft_wrap,
+            show_whitespace: self.show_whitespace,
+            rulers: self.rulers.clone(),
+            highlight_current_line: self.highlight_current_line,
+            fold_method: self.fold_method,
+            folds: self.folds.clone(),
+            ..Self::default()
+        };
+        view.gutter.set_line_numbers(self.gutter.line_numbers());
+        view.reload_snippets();
+        view.set_needs_redraw(true);
+        view
+    }
+    pub fn shares_buffer_with(&self, other: &Self) -> bool {
+        self.buffer.is_same(&other.buffer)
+    }
+    // To be called after this view changed the buffer: its own locations are up to date already.
+    pub fn acknowledge_buffer_edits(&mut self) {
+        self.buffer_revision = self.buffer.borrow().revision();
+    }
+    // Moves the caret and the selection along with the text another view edited.
+    pub fn sync_with_buffer(&mut self) {
+        let revision = self.buffer.borrow().revision();
+        if revision == self.buffer_revision {
+            return;
+        }
+        let edits = self.buffer.borrow().edits_since(self.buffer_revision);
+        if let Some(edits) = edits {
+            for edit in edits {
+                self.text_location = edit.adjust(self.text_location);
+                if let Some(selection) = &mut self.selection {
+                    selection.anchor = edit.adjust(selection.anchor);
+                }
+                if let Some(search_info) = &mut self.search_info {
+                    search_info.prev_location = edit.adjust(search_info.prev_location);
+                }
+            }
+        } else {
+            self.selection = None;
+        }
+        self.buffer_revision = revision;
+        // Tab stops don't know about edits made elsewhere
+        self.snippet_session = None;
+        self.sticky_col = None;
+        self.blame = None;
+        self.row_cache = RefCell::default();
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    // endregion
+
     // region : search
     pub fn enter_search(&mut self) {
         self.search_info = Some(SearchInfo {
@@ -50,6 +206,11 @@ impl View {
         });
     }
     pub fn exit_search(&mut self) {
+        if let Some(search_info) = &self.search_info {
+            if search_info.prev_location != self.text_location {
+                self.buffer.borrow_mut().jump_list.record(search_info.prev_location);
+            }
+        }
         self.search_info = None;
         self.set_needs_redraw(true);
     }
@@ -91,14 +252,14 @@ impl View {
             if query.is_empty() {
                 None
             } else if direction == SearchDirection::Forward {
-                self.buffer.search_forward(query, from)
+                self.buffer.borrow().search_forward(query, from)
             } else {
-                self.buffer.search_backward(query, from)
+                self.buffer.borrow().search_backward(query, from)
             }
         }) {
             self.text_location = location;
             self.center_text_location();
-        };
+        }
     }
 
     pub fn search_next(&mut self) {
@@ -119,17 +280,516 @@ impl View {
     }
     // endregion
 
+    // region: go to line
+    pub fn goto(&mut self, target: GotoTarget) {
+        let last_line_idx = self.buffer.borrow().height().saturating_sub(1);
+        let current_line_idx = self.text_location.line_idx;
+        let (line_idx, col) = match target {
+            GotoTarget::Line { line, col } => (line.saturating_sub(1), col),
+            GotoTarget::Relative(offset) => (current_line_idx.saturating_add_signed(offset), None),
+            GotoTarget::Percent(percent) => (
+                last_line_idx
+                    .saturating_mul(percent)
+                    .checked_div(100)
+                    .unwrap_or(0),
+                None,
+            ),
+        };
+        self.jump_to(Location {
+            grapheme_idx: col.map_or(0, |col| col.saturating_sub(1)),
+            line_idx: min(line_idx, last_line_idx),
+        });
+    }
+    // Jumps to a line and character column, as other tools report them
+    pub fn goto_char_location(&mut self, (line_idx, char_idx): (usize, usize)) {
+        let location = {
+            let buffer = self.buffer.borrow();
+            let line_idx = min(line_idx, buffer.height().saturating_sub(1));
+            Location {
+                line_idx,
+                grapheme_idx: buffer
+                    .lines
+                    .get(line_idx)
+                    .map_or(0, |line| line.char_idx_to_grapheme_idx(char_idx)),
+            }
+        };
+        self.jump_to(location);
+    }
+    // The identifier the caret is on or right behind
+    pub fn word_at_caret(&self) -> Option<String> {
+        let buffer = self.buffer.borrow();
+        let line = buffer.lines.get(self.text_location.line_idx)?;
+        let before = line.get_substr(0..self.text_location.grapheme_idx);
+        let after = line.get_substr(self.text_location.grapheme_idx..line.grapheme_count());
+        let word_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| WordRanking::is_word_char(*ch))
+            .last()
+            .map_or(before.len(), |(idx, _)| idx);
+        let word_end = after
+            .char_indices()
+            .find(|(_, ch)| !WordRanking::is_word_char(*ch))
+            .map_or(after.len(), |(idx, _)| idx);
+        let word = format!(
+            "{}{}",
+            before.get(word_start..).unwrap_or_default(),
+            after.get(..word_end).unwrap_or_default()
+        );
+        (!word.is_empty()).then_some(word)
+    }
+    // The caret's line and character column, for other tools
+    pub fn caret_char_location(&self) -> (usize, usize) {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let char_idx = self
+            .buffer
+            .borrow()
+            .lines
+            .get(line_idx)
+            .map_or(0, |line| line.get_substr(0..grapheme_idx).chars().count());
+        (line_idx, char_idx)
+    }
+    // endregion
+
+    // region: bookmarks
+    // Returns true if a bookmark was added, false if one was removed.
+    pub fn toggle_bookmark(&mut self) -> bool {
+        let added = self.buffer.borrow_mut().bookmarks.toggle_anonymous(self.text_location);
+        // The sign column may have appeared or disappeared
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        added
+    }
+    pub fn set_bookmark(&mut self, name: char) {
+        self.buffer.borrow_mut().bookmarks.set_named(name, self.text_location);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    // Returns false if there is no bookmark with this name.
+    pub fn jump_to_bookmark(&mut self, name: char) -> bool {
+        let target = self.buffer.borrow().bookmarks.get_named(name);
+        target.map(|location| self.jump_to(location)).is_some()
+    }
+    // Jumps to the next (or previous) bookmark. Returns false if there are no bookmarks.
+    pub fn cycle_bookmarks(&mut self, forward: bool) -> bool {
+        let target = if forward {
+            self.buffer.borrow().bookmarks.next_after(self.text_location)
+        } else {
+            self.buffer.borrow().bookmarks.prev_before(self.text_location)
+        };
+        target.map(|location| self.jump_to(location)).is_some()
+    }
+    // Describes each bookmark, in text order, for the bookmark picker.
+    pub fn bookmark_list(&self) -> Vec<String> {
+        let buffer = self.buffer.borrow();
+        buffer
+            .bookmarks
+            .sorted()
+            .iter()
+            .map(|mark| {
+                let Location {
+                    grapheme_idx,
+                    line_idx,
+                } = mark.location;
+                let text = buffer
+                    .lines
+                    .get(line_idx)
+                    .map_or("", |line| line.trim());
+                format!(
+                    "{} {:>6}:{:<4} {text}",
+                    mark.name.unwrap_or(ANONYMOUS_BOOKMARK_SIGN),
+                    line_idx.saturating_add(1),
+                    grapheme_idx.saturating_add(1),
+                )
+            })
+            .collect()
+    }
+    // Jumps to the n-th bookmark of bookmark_list.
+    pub fn jump_to_bookmark_at(&mut self, idx: usize) {
+        let target = self
+            .buffer
+            .borrow()
+            .bookmarks
+            .sorted()
+            .get(idx)
+            .map(|mark| mark.location);
+        if let Some(location) = target {
+            self.jump_to(location);
+        }
+    }
+    // endregion
+
+    // region: diagnostics
+    // Jumps to the next (or previous) diagnostic. Returns false if there are none.
+    pub fn cycle_diagnostics(&mut self, forward: bool) -> bool {
+        let target = if forward {
+            self.buffer.borrow().diagnostics.next_after(self.text_location)
+        } else {
+            self.buffer.borrow().diagnostics.prev_before(self.text_location)
+        };
+        target.map(|location| self.jump_to(location)).is_some()
+    }
+    // The message of the diagnostic under the caret, to show in the message bar
+    pub fn diagnostic_at_caret(&self) -> Option<String> {
+        self.buffer
+            .borrow()
+            .diagnostics
+            .at(self.text_location)
+            .map(|diagnostic| {
+                let first_line = diagnostic.message.lines().next().unwrap_or_default();
+                format!("{}: {first_line}", diagnostic.severity)
+            })
+    }
+    // endregion
+
+    // region: git changes
+    // Jumps to the next (or previous) changed lines. Returns false if nothing changed since the last commit.
+    pub fn cycle_hunks(&mut self, forward: bool) -> bool {
+        let line_idx = self.text_location.line_idx;
+        let target = {
+            let mut buffer = self.buffer.borrow_mut();
+            buffer.update_git_diff();
+            if forward {
+                buffer.git_diff.next_after(line_idx)
+            } else {
+                buffer.git_diff.prev_before(line_idx)
+            }
+        };
+        target
+            .map(|line_idx| {
+                self.jump_to(Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                });
+            })
+            .is_some()
+    }
+    // Restores the changed lines under the caret as they are in the last commit. Returns false if there are none.
+    pub fn revert_hunk_at_caret(&mut self) -> bool {
+        let line_idx = self.buffer.borrow_mut().revert_hunk(self.text_location.line_idx);
+        let Some(line_idx) = line_idx else {
+            return false;
+        };
+        self.exit_snippet();
+        // The selection follows the text like after another view's edits
+        self.sync_with_buffer();
+        self.text_location = Location {
+            line_idx: line_idx.min(self.buffer.borrow().height().saturating_sub(1)),
+            grapheme_idx: 0,
+        };
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        true
+    }
+    // endregion
+
+    // region: jump list
+    // Both return false if there is nowhere to go.
+    pub fn jump_back(&mut self) -> bool {
+        let target = self.buffer.borrow_mut().jump_list.back(self.text_location);
+        target.map(|location| self.show_location(location)).is_some()
+    }
+    pub fn jump_forward(&mut self) -> bool {
+        let target = self.buffer.borrow_mut().jump_list.forward();
+        target.map(|location| self.show_location(location)).is_some()
+    }
+    // Moves the caret to the given location, remembering where it came from in the jump list.
+    fn jump_to(&mut self, location: Location) {
+        self.buffer.borrow_mut().jump_list.record(self.text_location);
+        self.show_location(location);
+    }
+    // endregion
+
+    // Moves the caret to the given location, centering it if it was off screen.
+    fn show_location(&mut self, location: Location) {
+        self.clear_selection();
+        self.text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.reveal_caret();
+        let Position { row, .. } = self.text_location_to_position();
+        let is_visible = row >= self.scroll_offset.row
+            && row < self.scroll_offset.row.saturating_add(self.size.height);
+        if is_visible {
+            self.scroll_text_location_into_view();
+        } else {
+            self.center_text_location();
+        }
+    }
+
+    // region: soft wrap
+    // Returns whether soft wrap is now enabled.
+    pub fn toggle_soft_wrap(&mut self) -> bool {
+        self.soft_wrap = !self.soft_wrap;
+        self.scroll_offset.col = 0;
+        self.center_text_location();
+        self.soft_wrap
+    }
+    // endregion
+
+    // region: whitespace
+    // Returns whether whitespace is now shown.
+    pub fn toggle_whitespace(&mut self) -> bool {
+        self.show_whitespace = !self.show_whitespace;
+        self.set_needs_redraw(true);
+        self.show_whitespace
+    }
+    // endregion
+
+    // region: blame
+    pub const fn is_blame_shown(&self) -> bool {
+        self.blame.is_some()
+    }
+    pub fn show_blame(&mut self, lines: Vec<BlameLine>) {
+        self.blame = Some(Blame::new(lines));
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    pub fn hide_blame(&mut self) {
+        self.blame = None;
+        self.set_needs_redraw(true);
+    }
+    // The commit of the caret's line, to show in the message bar while the blame is
+    pub fn blame_at_caret(&mut self) -> Option<String> {
+        let line_idx = self.text_location.line_idx;
+        let blame = self.blame.as_mut()?;
+        let buffer = self.buffer.borrow();
+        blame.update(&buffer.lines, buffer.revision());
+        Some(blame.summary(line_idx))
+    }
+    // endregion
+
+    // region: folding
+    // Folds the region around the caret. Returns false if there is nothing to fold.
+    pub fn fold_at_caret(&mut self) -> bool {
+        self.highlight_all();
+        let Some(region) = folds::region_at(&self.buffer.borrow(), self.text_location.line_idx, self.fold_method)
+        else {
+            return false;
+        };
+        let header_line_idx = *region.start();
+        self.fold(region);
+        self.clear_selection();
+        self.text_location.line_idx = header_line_idx;
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        true
+    }
+    // Opens the folds on the caret line. Returns false if there were none.
+    pub fn unfold_at_caret(&mut self) -> bool {
+        let line_idx = self.text_location.line_idx;
+        let unfolded = self.folds_mut().remove_at(line_idx);
+        self.set_needs_redraw(true);
+        unfolded
+    }
+    // Opens all folds if there are any, folds all outermost regions otherwise. Returns whether things are folded now.
+    pub fn toggle_all_folds(&mut self) -> bool {
+        if self.folds().is_empty() {
+            self.highlight_all();
+            let regions = folds::top_level_regions(&self.buffer.borrow(), self.fold_method);
+            for region in regions {
+                if region.contains(&self.text_location.line_idx) {
+                    self.text_location.line_idx = *region.start();
+                    self.snap_to_valid_grapheme();
+                }
+                self.fold(region);
+            }
+        } else {
+            self.folds_mut().clear();
+        }
+        self.clear_selection();
+        self.center_text_location();
+        !self.folds().is_empty()
+    }
+    // Opens the folds hiding the caret, e.g. after jumping into one.
+    fn reveal_caret(&mut self) {
+        let line_idx = self.text_location.line_idx;
+        if self.folds_mut().reveal(line_idx) {
+            self.set_needs_redraw(true);
+        }
+    }
+    // The folds, brought up to date with the buffer
+    fn folds(&self) -> Ref<'_, Folds> {
+        self.folds.borrow_mut().follow(&self.buffer.borrow());
+        self.folds.borrow()
+    }
+    // Brace regions are found from the highlights, which tell the braces of the code from those in strings
+    fn highlight_all(&self) {
+        let mut buffer = self.buffer.borrow_mut();
+        let last_line_idx = buffer.height().saturating_sub(1);
+        buffer.highlight_until(last_line_idx);
+    }
+    fn fold(&mut self, region: RangeInclusive<usize>) {
+        let buffer = self.buffer.borrow();
+        let folds = self.folds.get_mut();
+        folds.follow(&buffer);
+        folds.add(region, &buffer.lines);
+    }
+    fn folds_mut(&mut self) -> &mut Folds {
+        let folds = self.folds.get_mut();
+        folds.follow(&self.buffer.borrow());
+        folds
+    }
+    // endregion
+
+    // region: line numbers
+    // Switches between no, absolute and relative line numbers. Returns the new setting.
+    pub fn cycle_line_numbers(&mut self) -> LineNumbers {
+        let line_numbers = self.gutter.line_numbers().next();
+        self.gutter.set_line_numbers(line_numbers);
+        // The text area got narrower or wider
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        line_numbers
+    }
+    // endregion
+
+    // region: mouse
+    // Positions are relative to the view.
+    // Moves the caret to the clicked text. When extending, the selection runs from the previous caret location.
+    pub fn click(&mut self, position: Position, extend_selection: bool) {
+        if let Some(session) = &mut self.snippet_session {
+            session.clear_overwrite_pending();
+        }
+        if !extend_selection {
+            self.clear_selection();
+        } else if self.selection.is_none() {
+            self.selection = Some(Selection {
+                anchor: self.text_location,
+                mode: SelectionMode::Linear,
+            });
+        }
+        self.text_location = self.location_at(position);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    pub fn drag(&mut self, position: Position) {
+        self.click(position, true);
+    }
+    // Selects the word under the given position, or just moves the caret there if there is no word.
+    pub fn select_word_at(&mut self, position: Position) {
+        let location = self.location_at(position);
+        let word = {
+            let buffer = self.buffer.borrow();
+            let Some(line) = buffer.lines.get(location.line_idx) else {
+                return;
+            };
+            let is_word = |idx: usize| {
+                let grapheme = line.get_substr(idx..idx.saturating_add(1));
+                !grapheme.is_empty() && grapheme.chars().all(WordRanking::is_word_char)
+            };
+            is_word(location.grapheme_idx).then(|| {
+                let mut start = location.grapheme_idx;
+                while start > 0 && is_word(start.saturating_sub(1)) {
+                    start = start.saturating_sub(1);
+                }
+                let mut end = location.grapheme_idx;
+                while is_word(end) {
+                    end = end.saturating_add(1);
+                }
+                (start, end)
+            })
+        };
+        let Some((start, end)) = word else {
+            self.click(position, false);
+            return;
+        };
+        self.selection = Some(Selection {
+            anchor: Location {
+                grapheme_idx: start,
+                line_idx: location.line_idx,
+            },
+            mode: SelectionMode::Linear,
+        });
+        self.text_location = Location {
+            grapheme_idx: end,
+            line_idx: location.line_idx,
+        };
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    pub fn scroll(&mut self, up: bool, lines: usize) {
+        for _ in 0..lines {
+            self.scroll_by_one_line(up);
+        }
+        self.scroll_text_location_into_view();
+    }
+    // Maps a position relative to the view to the location of the text shown there. Clicking the right half
+    // of a wide grapheme still yields that grapheme. Positions on the gutter map to the first visible column,
+    // positions below the text to the last line.
+    fn location_at(&self, position: Position) -> Location {
+        self.position_to_location(Position {
+            col: position
+                .col
+                .saturating_sub(self.gutter_width())
+                .saturating_add(self.scroll_offset.col),
+            row: min(
+                position.row.saturating_add(self.scroll_offset.row),
+                self.row_count().saturating_sub(1),
+            ),
+        })
+    }
+    // endregion
+
     // region: Command handling
     pub fn handle_edit_command (&mut self, command: Edit) {
         match command {
-            Edit::Insert(character) => self.insert_char(character),
+            Edit::Increment(delta) => self.increment_numbers(delta, false),
+            Edit::IncrementSequence(delta) => self.increment_numbers(delta, true),
+            Edit::Delete | Edit::DeleteBackward if self.selection.is_some() => {
+                self.delete_selection();
+            }
+            Edit::Insert('\t') if self.selection_spans_lines() => self.shift_lines(false),
+            Edit::Indent => self.shift_lines(false),
+            Edit::Outdent => self.shift_lines(true),
+            Edit::Insert('\t') => {
+                self.replace_selection();
+                self.handle_tab();
+            }
+            Edit::BackTab => self.handle_back_tab(),
+            Edit::Insert(character) => {
+                self.replace_selection();
+                self.insert_char(character);
+            }
             Edit::Delete => self.delete(),
             Edit::DeleteBackward => self.delete_backward(),
-            Edit::InsertNewline => self.insert_newline(),
+            Edit::InsertNewline => {
+                self.replace_selection();
+                self.insert_newline();
+            }
         }
     }
     pub fn handle_move_command(&mut self, command: Move) {
+        self.clear_selection();
+        self.move_text_location(command);
+    }
+    pub fn handle_select_command(&mut self, command: Select) {
+        let (movement, mode) = match command {
+            Select::Linear(movement) => (movement, SelectionMode::Linear),
+            Select::Block(movement) => (movement, SelectionMode::Block),
+        };
+        match &mut self.selection {
+            Some(selection) => selection.mode = mode,
+            None => {
+                self.selection = Some(Selection {
+                    anchor: self.text_location,
+                    mode,
+                });
+            }
+        }
+        self.move_text_location(movement);
+        self.set_needs_redraw(true);
+    }
+    fn move_text_location(&mut self, command: Move) {
         let Size { height, .. } = self.size;
+        let old_location = self.text_location;
+        if let Some(session) = &mut self.snippet_session {
+            session.clear_overwrite_pending();
+        }
 
         match command {
             Move::Up => self.move_up(1),
@@ -140,70 +800,813 @@ impl View {
             Move::PageDown => self.move_down(height.saturating_sub(1)),
             Move::StartOfLine => self.move_to_start_of_line(),
             Move::EndOfLine => self.move_to_end_of_line(),
+            Move::StartOfDocument => self.text_location = Location::default(),
+            Move::EndOfDocument => {
+                self.text_location.line_idx = self.buffer.borrow().height().saturating_sub(1);
+                self.move_to_end_of_line();
+            }
+            Move::ScreenTop => self.move_to_row(self.screen_top_row()),
+            Move::ScreenMiddle => {
+                let top = self.scroll_offset.row;
+                let middle = self.last_visible_row().saturating_sub(top).div_ceil(2);
+                self.move_to_row(top.saturating_add(middle));
+            }
+            Move::ScreenBottom => self.move_to_row(self.screen_bottom_row()),
+            Move::ScrollUp => self.scroll_by_one_line(true),
+            Move::ScrollDown => self.scroll_by_one_line(false),
         }
 
+        if self.text_location.line_idx.abs_diff(old_location.line_idx) > JUMP_LINE_THRESHOLD {
+            self.buffer.borrow_mut().jump_list.record(old_location);
+        }
         self.scroll_text_location_into_view();
     }
     // endregion
     
     // region: handling file
-    pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
-        let buffer = Buffer::load(file_name)?;
+    pub fn buffer(&self) -> &BufferHandle {
+        &self.buffer
+    }
+    // Shows another buffer, where the caret was when it was last shown.
+    pub fn show_buffer(&mut self, buffer: BufferHandle) {
+        self.buffer.borrow_mut().last_location = self.text_location;
+        self.text_location = buffer.borrow().last_location;
+        self.buffer_revision = buffer.borrow().revision();
         self.buffer = buffer;
+        self.folds = RefCell::default();
+        self.selection = None;
+        self.search_info = None;
+        self.snippet_session = None;
+        self.sticky_col = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.reload_snippets();
+        self.center_text_location();
         self.set_needs_redraw(true);
-        Ok(())
     }
     pub fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save()
+        self.buffer.borrow_mut().save()
     }
     pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)
+        self.buffer.borrow_mut().save_as(file_name)?;
+        self.reload_snippets();
+        Ok(())
     }
     // endregion
 
     // region: Text editing
     fn insert_newline(&mut self) {
-        self.buffer.insert_newline(self.text_location);
-        self.handle_move_command(Move::Right);
+        self.exit_snippet();
+        self.buffer.borrow_mut().insert_newline(self.text_location);
+        self.move_text_location(Move::Right);
         self.set_needs_redraw(true);
     }
     fn delete_backward(&mut self) {
+        if self.consume_snippet_overwrite() {
+            return;
+        }
         if self.text_location.line_idx != 0 || self.text_location.grapheme_idx != 0 {
-            self.handle_move_command(Move::Left);
+            self.move_text_location(Move::Left);
             self.delete();
         }
     }
     fn delete(&mut self) {
-        self.buffer.delete(self.text_location);
+        if self.consume_snippet_overwrite() {
+            return;
+        }
+        let at = self.text_location;
+        let is_joining_lines = self
+            .buffer.borrow()
+            .lines
+            .get(at.line_idx)
+            .is_some_and(|line| at.grapheme_idx >= line.grapheme_count());
+        if is_joining_lines {
+            self.exit_snippet();
+        }
+        self.buffer.borrow_mut().delete(at);
+        if let Some(session) = &mut self.snippet_session {
+            session.adjust_for_delete(at, 1);
+        }
+        self.sync_snippet_mirrors();
         self.set_needs_redraw(true);
     }
     fn insert_char(&mut self, character: char) {
+        self.consume_snippet_overwrite();
+        let at = self.text_location;
         let old_len = self
-            .buffer
+            .buffer.borrow()
             .lines
             .get(self.text_location.line_idx)
             .map_or(0, Line::grapheme_count);
 
-        self.buffer.insert_char(character, self.text_location);
+        self.buffer.borrow_mut().insert_char(character, self.text_location);
         
         let new_len = self
-            .buffer
+            .buffer.borrow()
             .lines
             .get(self.text_location.line_idx)
             .map_or(0, Line::grapheme_count);
 
         let grapheme_delta = new_len.saturating_sub(old_len);
+        if let Some(session) = &mut self.snippet_session {
+            let owner = session
+                .is_in_current_span(at)
+                .then(|| session.current_owner());
+            session.adjust_for_insert(at, grapheme_delta, owner);
+        }
         if grapheme_delta > 0 {
             // si la nouvelle longueur est plus grande
-            self.handle_move_command(Move::Right);
+            self.move_text_location(Move::Right);
+        }
+        self.sync_snippet_mirrors();
+        self.set_needs_redraw(true);
+    }
+    // endregion
+
+    // region: selection
+    fn clear_selection(&mut self) {
+        if self.selection.take().is_some() {
+            self.set_needs_redraw(true);
         }
+    }
+    // Returns the start and the end of the selection, in text order.
+    fn selection_bounds(&self) -> Option<(Location, Location)> {
+        self.selection.map(|selection| {
+            let anchor = selection.anchor;
+            let caret = self.text_location;
+            if anchor <= caret {
+                (anchor, caret)
+            } else {
+                (caret, anchor)
+            }
+        })
+    }
+    // Returns the leftmost and the rightmost column of a block selection. Both columns are part of the block.
+    fn block_columns(&self) -> Option<(Col, Col)> {
+        let anchor = self.selection?.anchor;
+        let anchor_col = self
+            .buffer.borrow()
+            .lines
+            .get(anchor.line_idx)
+            .map_or(0, |line| line.width_until(anchor.grapheme_idx));
+        let caret_col = self.caret_line_col();
+        Some((min(anchor_col, caret_col), anchor_col.max(caret_col)))
+    }
+    // Returns the range of graphemes of the given line which are selected, if any.
+    fn selected_range_on_line(&self, line_idx: usize) -> Option<Range<usize>> {
+        let selection = self.selection?;
+        let (start, end) = self.selection_bounds()?;
+        if line_idx < start.line_idx || line_idx > end.line_idx {
+            return None;
+        }
+        let buffer = self.buffer.borrow();
+        let line = buffer.lines.get(line_idx)?;
+        match selection.mode {
+            SelectionMode::Linear => {
+                let from = if line_idx == start.line_idx {
+                    start.grapheme_idx
+                } else {
+                    0
+                };
+                let to = if line_idx == end.line_idx {
+                    end.grapheme_idx
+                } else {
+                    line.grapheme_count()
+                };
+                Some(from..to)
+            }
+            SelectionMode::Block => {
+                let (left, right) = self.block_columns()?;
+                let from = line.grapheme_idx_at_col(left);
+                let to = min(
+                    line.grapheme_idx_at_col(right).saturating_add(1),
+                    line.grapheme_count(),
+                );
+                Some(from..to.max(from))
+            }
+        }
+    }
+    // Deletes the selected text and places the caret where it started. Returns false if there is no selection.
+    fn delete_selection(&mut self) -> bool {
+        let (Some(selection), Some((start, end))) = (self.selection, self.selection_bounds()) else {
+            return false;
+        };
+        self.exit_snippet();
+        match selection.mode {
+            SelectionMode::Linear => {
+                self.buffer.borrow_mut().delete_range(start, end);
+                self.text_location = start;
+            }
+            SelectionMode::Block => {
+                let ranges: Vec<(usize, Range<usize>)> = (start.line_idx..=end.line_idx)
+                    .filter_map(|line_idx| {
+                        self.selected_range_on_line(line_idx)
+                            .map(|range| (line_idx, range))
+                    })
+                    .collect();
+                for (line_idx, range) in &ranges {
+                    self.buffer.borrow_mut().delete_range(
+                        Location {
+                            line_idx: *line_idx,
+                            grapheme_idx: range.start,
+                        },
+                        Location {
+                            line_idx: *line_idx,
+                            grapheme_idx: range.end,
+                        },
+                    );
+                }
+                self.text_location = Location {
+                    line_idx: start.line_idx,
+                    grapheme_idx: ranges.first().map_or(0, |(_, range)| range.start),
+                };
+            }
+        }
+        self.selection = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
+        true
+    }
+    // Typing over a linear selection replaces it, typing with a block selection simply ends it.
+    fn replace_selection(&mut self) {
+        match self.selection.map(|selection| selection.mode) {
+            Some(SelectionMode::Linear) => {
+                self.delete_selection();
+            }
+            Some(SelectionMode::Block) => self.clear_selection(),
+            None => {}
+        }
+    }
+    // endregion
+
+    // region: indentation
+    fn selection_spans_lines(&self) -> bool {
+        self.selection_bounds()
+            .is_some_and(|(start, end)| start.line_idx != end.line_idx)
+    }
+    // Returns the lines touched by the selection, or the caret line if there is no selection.
+    // A linear selection ending at the very start of a line doesn't touch that line.
+    fn touched_lines(&self) -> RangeInclusive<usize> {
+        let Some((start, end)) = self.selection_bounds() else {
+            return self.text_location.line_idx..=self.text_location.line_idx;
+        };
+        let is_linear = self
+            .selection
+            .is_some_and(|selection| selection.mode == SelectionMode::Linear);
+        if is_linear && end.grapheme_idx == 0 && end.line_idx > start.line_idx {
+            start.line_idx..=end.line_idx.saturating_sub(1)
+        } else {
+            start.line_idx..=end.line_idx
+        }
+    }
+    // Indents (or outdents) every touched line by one level. The caret and the selection stay on the same text.
+    fn shift_lines(&mut self, outdent: bool) {
+        self.exit_snippet();
+        let lines = self.touched_lines();
+        // When shifting a block of lines, empty lines stay empty
+        let skip_empty_lines = lines.start() != lines.end();
+        for line_idx in lines {
+            let leading_whitespace: String = {
+                let buffer = self.buffer.borrow();
+                let Some(line) = buffer.lines.get(line_idx) else {
+                    continue;
+                };
+                if skip_empty_lines && line.is_empty() {
+                    continue;
+                }
+                line.chars()
+                    .take_while(|ch| *ch == ' ' || *ch == '\t')
+                    .collect()
+            };
+            let new_leading_whitespace = self.indentation.shift(&leading_whitespace, outdent);
+            if new_leading_whitespace == leading_whitespace {
+                continue;
+            }
+            let old_len = leading_whitespace.chars().count();
+            let new_len = new_leading_whitespace.chars().count();
+            self.buffer.borrow_mut().delete_range(
+                Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                },
+                Location {
+                    line_idx,
+                    grapheme_idx: old_len,
+                },
+            );
+            self.buffer.borrow_mut().insert_text(
+                &new_leading_whitespace,
+                Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                },
+            );
+            self.text_location =
+                Self::shift_location(self.text_location, line_idx, old_len, new_len);
+            if let Some(selection) = &mut self.selection {
+                selection.anchor = Self::shift_location(selection.anchor, line_idx, old_len, new_len);
+            }
+        }
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+    // Moves a location on the given line along with the text after its leading whitespace changed length.
+    fn shift_location(location: Location, line_idx: usize, old_len: usize, new_len: usize) -> Location {
+        if location.line_idx != line_idx {
+            return location;
+        }
+        let grapheme_idx = if location.grapheme_idx >= old_len {
+            location
+                .grapheme_idx
+                .saturating_sub(old_len)
+                .saturating_add(new_len)
+        } else {
+            min(location.grapheme_idx, new_len)
+        };
+        Location {
+            grapheme_idx,
+            line_idx,
+        }
+    }
+    // endregion
+
+    // region: number increments
+    // Adds `delta` to the number at or after the caret. With a selection, the first number from the start of the
+    // selection on each selected line is incremented instead. As a sequence, the n-th number gets n times `delta`.
+    fn increment_numbers(&mut self, delta: i64, as_sequence: bool) {
+        self.exit_snippet();
+        let Some((start, end)) = self.selection_bounds() else {
+            let Location {
+                line_idx,
+                grapheme_idx,
+            } = self.text_location;
+            if let Some(number_end) = self.add_to_number(line_idx, grapheme_idx, delta) {
+                // Place the caret on the last digit
+                self.text_location.grapheme_idx = number_end.saturating_sub(1);
+                self.scroll_text_location_into_view();
+            }
+            return;
+        };
+
+        let mut multiplier: i64 = 1;
+        for line_idx in start.line_idx..=end.line_idx {
+            let Some(from) = self.selected_range_on_line(line_idx).map(|range| range.start) else {
+                continue;
+            };
+            let line_delta = if as_sequence {
+                delta.saturating_mul(multiplier)
+            } else {
+                delta
+            };
+            if self.add_to_number(line_idx, from, line_delta).is_some() {
+                multiplier = multiplier.saturating_add(1);
+            }
+        }
+        self.selection = None;
+        self.text_location = start;
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+    }
+    // Adds `delta` to the first number on the given line which contains or starts behind `from`.
+    // Returns the grapheme index right behind the updated number, or None if there was no number.
+    fn add_to_number(&mut self, line_idx: usize, from: usize, delta: i64) -> Option<usize> {
+        let (replacement, start, end) = {
+            let buffer = self.buffer.borrow();
+            let line = buffer.lines.get(line_idx)?;
+            let literal = NumberLiteral::find(line, line.get_substr(0..from).len())?;
+            let replacement = literal.add(delta)?;
+            let start = line.get(..literal.range.start)?.graphemes(true).count();
+            let end = start.saturating_add(line.get(literal.range.clone())?.graphemes(true).count());
+            (replacement, start, end)
+        };
+
+        self.buffer.borrow_mut().delete_range(
+            Location {
+                line_idx,
+                grapheme_idx: start,
+            },
+            Location {
+                line_idx,
+                grapheme_idx: end,
+            },
+        );
+        let new_end = self.buffer.borrow_mut().insert_text(
+            &replacement,
+            Location {
+                line_idx,
+                grapheme_idx: start,
+            },
+        );
+        self.set_needs_redraw(true);
+        Some(new_end.grapheme_idx)
+    }
+    // endregion
+
+    // region: completion
+    // Returns the word characters right in front of the caret, which are the prefix of the word to complete.
+    pub fn completion_prefix(&self) -> String {
+        let buffer = self.buffer.borrow();
+        let Some(line) = buffer.lines.get(self.text_location.line_idx) else {
+            return String::new();
+        };
+        let before_caret = line.get_substr(0..self.text_location.grapheme_idx);
+        let prefix_start = before_caret
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| WordRanking::is_word_char(*ch))
+            .last()
+            .map_or(before_caret.len(), |(idx, _)| idx);
+        before_caret
+            .get(prefix_start..)
+            .unwrap_or_default()
+            .to_string()
+    }
+    pub fn add_completion_words(&self, ranking: &mut WordRanking) {
+        let caret_line_idx = self.text_location.line_idx;
+        for (line_idx, line) in self.buffer.borrow().lines.iter().enumerate() {
+            ranking.add_line(line, line_idx.abs_diff(caret_line_idx));
+        }
+    }
+    // Completes the prefix in front of the caret to the given word.
+    pub fn complete_word(&mut self, prefix: &str, word: &str) {
+        debug_assert!(word.starts_with(prefix));
+        for character in word.get(prefix.len()..).unwrap_or_default().chars() {
+            self.insert_char(character);
+        }
+    }
+    // Replaces the prefix in front of the caret with a completion which doesn't necessarily start with it,
+    // and which may be a snippet.
+    pub fn complete_with(&mut self, prefix: &str, text: &str, is_snippet: bool) {
+        if !is_snippet && text.starts_with(prefix) {
+            self.complete_word(prefix, text);
+            return;
+        }
+        let start = Location {
+            line_idx: self.text_location.line_idx,
+            grapheme_idx: self
+                .text_location
+                .grapheme_idx
+                .saturating_sub(prefix.graphemes(true).count()),
+        };
+        if is_snippet {
+            let expanded = {
+                let buffer = self.buffer.borrow();
+                let indent: String = buffer
+                    .lines
+                    .get(start.line_idx)
+                    .map(|line| line.chars().take_while(|ch| ch.is_whitespace()).collect())
+                    .unwrap_or_default();
+                Snippet::from(text).expand(&SnippetVariables::from(&buffer.file_info), &indent)
+            };
+            self.insert_expanded_snippet(start, expanded);
+        } else {
+            self.buffer.borrow_mut().delete_range(start, self.text_location);
+            self.text_location = self.buffer.borrow_mut().insert_text(text, start);
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+    // endregion
+
+    // region: snippets
+    fn reload_snippets(&mut self) {
+        self.snippets = SnippetLibrary::load(self.buffer.borrow().file_info.get_file_type());
+    }
+    pub fn exit_snippet(&mut self) {
+        self.snippet_session = None;
+    }
+    fn handle_tab(&mut self) {
+        if let Some(session) = &mut self.snippet_session {
+            session.next();
+            self.move_to_current_tab_stop();
+        } else if !self.expand_snippet() {
+            self.insert_char('\t');
+        }
+    }
+    fn handle_back_tab(&mut self) {
+        if let Some(session) = &mut self.snippet_session {
+            session.prev();
+            self.move_to_current_tab_stop();
+        } else {
+            self.shift_lines(true);
+        }
+    }
+
+    // Expands the snippet whose trigger word is right in front of the caret, if any.
+    // Returns false if there is no such snippet.
+    fn expand_snippet(&mut self) -> bool {
+        let (trigger_len, expanded) = {
+            let buffer = self.buffer.borrow();
+            let Some(line) = buffer.lines.get(self.text_location.line_idx) else {
+                return false;
+            };
+            let Some((trigger_len, snippet)) = self
+                .snippets
+                .find_trigger(line.get_substr(0..self.text_location.grapheme_idx))
+            else {
+                return false;
+            };
+            let indent: String = line.chars().take_while(|ch| ch.is_whitespace()).collect();
+            (
+                trigger_len,
+                snippet.expand(&SnippetVariables::from(&buffer.file_info), &indent),
+            )
+        };
+
+        let start = Location {
+            line_idx: self.text_location.line_idx,
+            grapheme_idx: self.text_location.grapheme_idx.saturating_sub(trigger_len),
+        };
+        self.insert_expanded_snippet(start, expanded);
+        true
+    }
+    // Replaces the text between start and the caret with the expanded snippet, and starts cycling through its tab stops.
+    fn insert_expanded_snippet(&mut self, start: Location, expanded: ExpandedSnippet) {
+        self.buffer.borrow_mut().delete_range(start, self.text_location);
+        self.buffer.borrow_mut().insert_text(&expanded.text, start);
+
+        // Tab stops are relative to the insertion point, make them absolute.
+        let tab_stops: Vec<TabStop> = expanded
+            .tab_stops
+            .into_iter()
+            .map(|mut tab_stop| {
+                for span in &mut tab_stop.spans {
+                    if span.line_idx == 0 {
+                        span.start = span.start.saturating_add(start.grapheme_idx);
+                        span.end = span.end.saturating_add(start.grapheme_idx);
+                    }
+                    span.line_idx = span.line_idx.saturating_add(start.line_idx);
+                }
+                tab_stop
+            })
+            .collect();
+        let final_location = tab_stops.last().and_then(TabStop::primary).map(|span| Location {
+            line_idx: span.line_idx,
+            grapheme_idx: span.start,
+        });
+        self.snippet_session = SnippetSession::new(tab_stops);
+        if self.snippet_session.is_some() {
+            self.move_to_current_tab_stop();
+        } else if let Some(location) = final_location {
+            self.text_location = location;
+            self.scroll_text_location_into_view();
+        }
+        self.set_needs_redraw(true);
+    }
+
+    // Moves the caret to the start of the current tab stop. Ends the session when reaching the final tab stop.
+    fn move_to_current_tab_stop(&mut self) {
+        if let Some(session) = &self.snippet_session {
+            if let Some(span) = session.current_span() {
+                self.text_location = Location {
+                    line_idx: span.line_idx,
+                    grapheme_idx: span.start,
+                };
+            }
+            if session.is_at_end() {
+                self.snippet_session = None;
+            }
+        }
+        self.scroll_text_location_into_view();
+    }
+
+    // If the caret sits on a placeholder whose default value has not been touched yet, deletes that value.
+    // Returns true if something was deleted.
+    fn consume_snippet_overwrite(&mut self) -> bool {
+        let Some(session) = &mut self.snippet_session else {
+            return false;
+        };
+        if !session.is_overwrite_pending() {
+            return false;
+        }
+        session.clear_overwrite_pending();
+        let Some(span) = session.current_span() else {
+            return false;
+        };
+        if !session.is_in_current_span(self.text_location) {
+            return false;
+        }
+        let start = Location {
+            line_idx: span.line_idx,
+            grapheme_idx: span.start,
+        };
+        self.buffer.borrow_mut().delete_range(
+            start,
+            Location {
+                line_idx: span.line_idx,
+                grapheme_idx: span.end,
+            },
+        );
+        session.adjust_for_delete(start, span.end.saturating_sub(span.start));
+        self.text_location = start;
+        self.sync_snippet_mirrors();
+        self.set_needs_redraw(true);
+        true
+    }
+
+    // Copies the content of the placeholder being edited into its mirrors.
+    fn sync_snippet_mirrors(&mut self) {
+        let Some(session) = &mut self.snippet_session else {
+            return;
+        };
+        let Some(primary) = session.current_span() else {
+            return;
+        };
+        let caret_in_primary = session.is_in_current_span(self.text_location);
+        let caret_offset = self.text_location.grapheme_idx.saturating_sub(primary.start);
+        let Some(text) = self
+            .buffer.borrow()
+            .lines
+            .get(primary.line_idx)
+            .map(|line| line.get_substr(primary.start..primary.end).to_string())
+        else {
+            return;
+        };
+        let text_len = primary.end.saturating_sub(primary.start);
+
+        for mirror_idx in 0..session.current_mirrors().len() {
+            let Some(mirror) = session.current_mirrors().get(mirror_idx).copied() else {
+                continue;
+            };
+            let from = Location {
+                line_idx: mirror.line_idx,
+                grapheme_idx: mirror.start,
+            };
+            let to = Location {
+                line_idx: mirror.line_idx,
+                grapheme_idx: mirror.end,
+            };
+            let is_in_sync = self
+                .buffer.borrow()
+                .lines
+                .get(mirror.line_idx)
+                .is_some_and(|line| line.get_substr(mirror.start..mirror.end) == text);
+            if is_in_sync {
+                continue;
+            }
+            self.buffer.borrow_mut().delete_range(from, to);
+            session.adjust_for_delete(from, mirror.end.saturating_sub(mirror.start));
+            self.buffer.borrow_mut().insert_text(&text, from);
+            session.adjust_for_insert(from, text_len, Some(session.mirror_owner(mirror_idx)));
+        }
+
+        // Mirrors in front of the placeholder on the same line may have moved it
+        if caret_in_primary {
+            if let Some(primary) = session.current_span() {
+                self.text_location = Location {
+                    line_idx: primary.line_idx,
+                    grapheme_idx: primary.start.saturating_add(caret_offset),
+                };
+            }
+        }
     }
     // endregion
 
     // region: Rendering
-    fn render_line(at: usize, line_text: &str) -> Result<(), Error> {
-        Terminal::print_row(at, line_text)
+    // The sign column only shows up when there is something to show in it
+    fn has_signs(&self) -> bool {
+        let buffer = self.buffer.borrow();
+        !buffer.bookmarks.is_empty()
+            || !self.folds().is_empty()
+            || !buffer.diagnostics.is_empty()
+            || !buffer.git_diff.is_empty()
+    }
+    // Diagnostics take precedence over bookmarks, which take precedence over folds
+    fn sign_on_line(&self, line_idx: usize) -> Option<(char, AnnotationType)> {
+        let buffer = self.buffer.borrow();
+        buffer
+            .diagnostics
+            .worst_on_line(line_idx)
+            .map(|severity| (severity.sign(), severity.annotation_type()))
+            .or_else(|| {
+                buffer
+                    .bookmarks
+                    .on_line(line_idx)
+                    .map(|mark| (mark.name.unwrap_or(ANONYMOUS_BOOKMARK_SIGN), AnnotationType::Gutter))
+            })
+            .or_else(|| {
+                self.folds()
+                    .hidden_after(line_idx)
+                    .map(|_| (FOLD_SIGN, AnnotationType::Gutter))
+            })
+    }
+    fn change_on_line(&self, line_idx: usize) -> Option<(char, AnnotationType)> {
+        self.buffer
+            .borrow()
+            .git_diff
+            .hunk_on_line(line_idx)
+            .map(|hunk| (hunk.marker(), hunk.kind().annotation_type()))
+    }
+    // The diagnostics on the given line as highlights, clamped to the line. Empty ranges cover one grapheme
+    // so that they can be seen.
+    fn diagnostic_highlights(buffer: &Buffer, line_idx: usize, line: &Line) -> Vec<Highlight> {
+        buffer
+            .diagnostics
+            .on_line(line_idx)
+            .map(|diagnostic| {
+                let start_grapheme_idx = if diagnostic.start.line_idx < line_idx {
+                    0
+                } else {
+                    diagnostic.start.grapheme_idx
+                };
+                let end_grapheme_idx = if diagnostic.end.line_idx > line_idx {
+                    line.grapheme_count()
+                } else {
+                    diagnostic.end.grapheme_idx
+                }
+                .max(start_grapheme_idx.saturating_add(1));
+                Highlight {
+                    annotation_type: diagnostic.severity.annotation_type(),
+                    start_byte_idx: line.grapheme_idx_to_byte_idx_or_end(start_grapheme_idx),
+                    end_byte_idx: line.grapheme_idx_to_byte_idx_or_end(end_grapheme_idx),
+                }
+            })
+            .collect()
+    }
+    fn gutter_width(&self) -> usize {
+        self.gutter
+            .width(self.buffer.borrow().height(), self.has_signs(), self.blame.is_some())
+    }
+    fn text_width(&self) -> usize {
+        self.size.width.saturating_sub(self.gutter_width())
+    }
+    // The text of the row with everything drawn over it, from the syntax highlighting to the rulers
+    fn annotated_row(
+        &self,
+        buffer: &Buffer,
+        row: &ScreenRow,
+        line: &Line,
+        query: Option<&str>,
+        text_width: usize,
+    ) -> AnnotatedString {
+        let line_idx = row.line_idx;
+        let selected_match = (self.text_location.line_idx == line_idx && query.is_some())
+                                                .then_some(self.text_location.grapheme_idx);
+        let selection = self.selected_range_on_line(line_idx);
+        let mut highlights = buffer.highlights(line_idx).to_vec();
+        highlights.extend(Self::diagnostic_highlights(buffer, line_idx, line));
+        let mut annotated_string =
+            line.get_annotated_visible_substr(
+                row.cols.clone(),
+                &highlights,
+                query,
+                selected_match,
+                selection,
+                self.show_whitespace,
+            );
+        self.add_row_tints(&mut annotated_string, row, text_width);
+        annotated_string
+    }
+    // Adds the rulers and the current line highlight beneath the other annotations of the row,
+    // padding it so they also show behind the end of the line.
+    fn add_row_tints(&self, annotated_string: &mut AnnotatedString, row: &ScreenRow, width: usize) {
+        let is_current_line =
+            self.highlight_current_line && row.line_idx == self.text_location.line_idx;
+        if !is_current_line && self.rulers.is_empty() {
+            return;
+        }
+        annotated_string.pad_to_width(width);
+        for ruler in &self.rulers {
+            // Rulers count columns from 1, like the status bar does for lines
+            let Some(col) = ruler
+                .checked_sub(1)
+                .and_then(|col| col.checked_sub(row.cols.start))
+            else {
+                continue;
+            };
+            if let Some(bytes) = annotated_string.byte_range_at_col(col) {
+                annotated_string.add_annotation_beneath(AnnotationType::Ruler, bytes.start, bytes.end);
+            }
+        }
+        if is_current_line {
+            let len = annotated_string.to_string().len();
+            annotated_string.add_annotation_beneath(AnnotationType::CurrentLine, 0, len);
+        }
+    }
+    fn render_gutter(&mut self, origin: Position, screen_rows: &[ScreenRow]) -> Result<(), Error> {
+        let gutter_rows = screen_rows
+            .iter()
+            .map(|row| GutterRow {
+                line_idx: row.line_idx,
+                is_continuation: row.is_continuation,
+                sign: self.sign_on_line(row.line_idx),
+                change: self.change_on_line(row.line_idx),
+                blame: self.blame.as_ref().map(|blame| blame.column_text(row.line_idx)),
+            })
+            .collect();
+        self.gutter.resize(Size {
+            height: self.size.height,
+            width: self.gutter_width(),
+        });
+        self.gutter
+            .set_rows(gutter_rows, self.text_location.line_idx, self.has_signs(), self.blame.is_some());
+        self.gutter.draw(origin)
+    }
+    // Prints the text padded to the given width, which overwrites whatever was there before.
+    fn render_line(at: Position, width: usize, line_text: &str) -> Result<(), Error> {
+        Terminal::print_at(at, &format!("{line_text:width$.width$}"))
     }
     fn build_welcome_message(width: usize) -> String {
         if width == 0 {
@@ -222,13 +1625,21 @@ impl View {
     // endregion
 
     // region: Scrolling
+    // The scroll-off margin, reduced on small screens so that the caret can still go somewhere
+    fn scroll_margin(&self) -> usize {
+        min(self.scroll_off, self.size.height.saturating_sub(1).checked_div(2).unwrap_or(0))
+    }
     fn scroll_vertically(&mut self, to: Row) {
         let Size { height, .. } = self.size;
-        let offset_changed = if to < self.scroll_offset.row {
-            self.scroll_offset.row = to;
+        let margin = self.scroll_margin();
+        let offset_changed = if to < self.scroll_offset.row.saturating_add(margin) {
+            self.scroll_offset.row = to.saturating_sub(margin);
             true
-        } else if to >= self.scroll_offset.row.saturating_add(height) {
-            self.scroll_offset.row = to.saturating_sub(height).saturating_add(1);
+        } else if to.saturating_add(margin) >= self.scroll_offset.row.saturating_add(height) {
+            self.scroll_offset.row = to
+                .saturating_add(margin)
+                .saturating_sub(height)
+                .saturating_add(1);
             true
         } else {
             false
@@ -238,7 +1649,10 @@ impl View {
         }
     }
     fn scroll_horizontally(&mut self, to: Col) {
-        let Size { width, .. } = self.size;
+        if self.soft_wrap {
+            return;
+        }
+        let width = self.text_width();
         let offset_changed = if to < self.scroll_offset.col {
             self.scroll_offset.col = to;
             true
@@ -253,15 +1667,66 @@ impl View {
         }
     }
     fn center_text_location(&mut self) {
-        let Size { height, width } = self.size;
+        self.reveal_caret();
+        let Size { height, .. } = self.size;
+        let width = self.text_width();
         let Position { row, col } = self.text_location_to_position();
         let vertical_mid = height.div_ceil(2);
         let horizontal_mid = width.div_ceil(2);
         self.scroll_offset.row = row.saturating_sub(vertical_mid);
-        self.scroll_offset.col = col.saturating_sub(horizontal_mid);
+        self.scroll_offset.col = if self.soft_wrap {
+            0
+        } else {
+            col.saturating_sub(horizontal_mid)
+        };
+        self.set_needs_redraw(true);
+    }
+    // Scrolls the view without moving the caret, unless it would end up off screen (or within the scroll-off margin).
+    fn scroll_by_one_line(&mut self, up: bool) {
+        let row = self.scroll_offset.row;
+        self.scroll_offset.row = if up {
+            row.saturating_sub(1)
+        } else {
+            min(row.saturating_add(1), self.row_count().saturating_sub(1))
+        };
+        if self.scroll_offset.row == row {
+            return;
+        }
         self.set_needs_redraw(true);
+        let (top, bottom) = (self.screen_top_row(), self.screen_bottom_row());
+        let caret_row = self.text_location_to_position().row;
+        if caret_row < top {
+            self.move_to_row(top);
+        } else if caret_row > bottom {
+            self.move_to_row(bottom);
+        }
+    }
+    fn last_visible_row(&self) -> Row {
+        min(
+            self.scroll_offset.row.saturating_add(self.size.height.saturating_sub(1)),
+            self.row_count().saturating_sub(1),
+        )
+    }
+    // The topmost row the caret can be on without scrolling
+    fn screen_top_row(&self) -> Row {
+        let top = self.scroll_offset.row;
+        if top == 0 {
+            top
+        } else {
+            min(top.saturating_add(self.scroll_margin()), self.last_visible_row())
+        }
+    }
+    // The bottommost row the caret can be on without scrolling
+    fn screen_bottom_row(&self) -> Row {
+        let bottom = self.last_visible_row();
+        if bottom.saturating_add(1) >= self.row_count() {
+            bottom
+        } else {
+            bottom.saturating_sub(self.scroll_margin()).max(self.scroll_offset.row)
+        }
     }
     fn scroll_text_location_into_view(&mut self) {
+        self.reveal_caret();
         let Position { row, col } = self.text_location_to_position();
         self.scroll_vertically(row);
         self.scroll_horizontally(col);
@@ -270,35 +1735,195 @@ impl View {
 
     // region: Location et Position
     pub fn caret_position(&self) -> Position {
-        self.text_location_to_position().saturating_sub(self.scroll_offset)
+        let Position { row, col } = self.text_location_to_position().saturating_sub(self.scroll_offset);
+        Position {
+            col: col.saturating_add(self.gutter_width()),
+            row,
+        }
     }
     fn text_location_to_position(&self) -> Position {
-        let row = self.text_location.line_idx;
-        debug_assert!(row.saturating_sub(1) <= self.buffer.lines.len());
-        let col = self
-            .buffer
+        self.location_to_position(self.text_location)
+    }
+    // Returns where the given location is shown in the document, in screen rows and columns (ignoring scrolling).
+    // Rows are lines, unless soft wrap or folds are involved.
+    fn location_to_position(&self, location: Location) -> Position {
+        let row = self.rows_before(location.line_idx);
+        debug_assert!(location.line_idx.saturating_sub(1) <= self.buffer.borrow().lines.len());
+        let buffer = self.buffer.borrow();
+        let Some(line) = buffer.lines.get(location.line_idx) else {
+            return Position { col: 0, row };
+        };
+        let line_rows = self.line_rows(location.line_idx, line);
+        let sub_row = line_rows
+            .iter()
+            .rposition(|start| *start <= location.grapheme_idx)
+            .unwrap_or(0);
+        let row_start = line_rows.get(sub_row).copied().unwrap_or(0);
+        Position {
+            col: line
+                .width_until(location.grapheme_idx)
+                .saturating_sub(line.width_until(row_start)),
+            row: row.saturating_add(sub_row),
+        }
+    }
+    // Returns the location shown at the given position of the document, ignoring scrolling.
+    // Rows past the end of the document map to the line right behind the last one.
+    fn position_to_location(&self, position: Position) -> Location {
+        let Position { col, row } = position;
+        if self.rows_are_lines() {
+            let line_idx = min(row, self.buffer.borrow().height());
+            return Location {
+                grapheme_idx: self
+                    .buffer.borrow()
+                    .lines
+                    .get(line_idx)
+                    .map_or(0, |line| line.grapheme_idx_at_col(col)),
+                line_idx,
+            };
+        }
+        let (line_idx, first_row) = self.row_cache().line_at_row(row);
+        let buffer = self.buffer.borrow();
+        let Some(line) = buffer.lines.get(line_idx) else {
+            return Location {
+                grapheme_idx: 0,
+                line_idx: buffer.height(),
+            };
+        };
+        let line_rows = self.line_rows(line_idx, line);
+        let sub_row = row.saturating_sub(first_row);
+        let row_start = line_rows.get(sub_row).copied().unwrap_or(0);
+        let grapheme_idx = line.grapheme_idx_at_col(line.width_until(row_start).saturating_add(col));
+        // Behind the end of a wrapped row is the start of the next one, so stay on the last grapheme
+        let grapheme_idx = line_rows
+            .get(sub_row.saturating_add(1))
+            .map_or(grapheme_idx, |next_start| {
+                min(grapheme_idx, next_start.saturating_sub(1).max(row_start))
+            });
+        Location {
+            grapheme_idx,
+            line_idx,
+        }
+    }
+    // Whether every line takes exactly one row, which allows for shortcuts
+    fn rows_are_lines(&self) -> bool {
+        !self.soft_wrap && self.folds().is_empty()
+    }
+    // The rows the given line is shown on, as the index of the grapheme each of them starts at.
+    // Lines hidden in a fold have no rows, fold summaries always take a single row.
+    fn line_rows(&self, line_idx: usize, line: &Line) -> Vec<usize> {
+        if self.folds().is_hidden(line_idx) {
+            Vec::new()
+        } else if !self.soft_wrap || self.folds().hidden_after(line_idx).is_some() {
+            vec![0]
+        } else {
+            line.wrap_points(self.text_width())
+        }
+    }
+    // The row counts of the lines, brought up to date with the buffer and the text width
+    fn row_cache(&self) -> Ref<'_, RowCache> {
+        let width = self.text_width();
+        self.row_cache
+            .borrow_mut()
+            .update(&self.buffer.borrow(), &self.folds(), width, self.soft_wrap);
+        self.row_cache.borrow()
+    }
+    // Number of rows taken by the lines before the given one.
+    fn rows_before(&self, line_idx: usize) -> Row {
+        if self.rows_are_lines() {
+            return line_idx;
+        }
+        self.row_cache().rows_before(line_idx)
+    }
+    fn row_count(&self) -> Row {
+        if self.rows_are_lines() {
+            return self.buffer.borrow().height();
+        }
+        self.row_cache().row_count()
+    }
+    // The column of the caret within its line, regardless of soft wrap
+    fn caret_line_col(&self) -> Col {
+        self.buffer.borrow()
             .lines
-            .get(row)
-            .map_or(0, |line| line.width_until(self.text_location.grapheme_idx));
-        Position {col, row}
+            .get(self.text_location.line_idx)
+            .map_or(0, |line| line.width_until(self.text_location.grapheme_idx))
+    }
+    // The rows to draw, starting from the top of the view
+    fn screen_rows(&self) -> Vec<ScreenRow> {
+        let Size { height, .. } = self.size;
+        let width = self.text_width();
+        let left = self.scroll_offset.col;
+        if self.rows_are_lines() {
+            return (self.scroll_offset.row..self.buffer.borrow().height())
+                .take(height)
+                .map(|line_idx| ScreenRow {
+                    line_idx,
+                    cols: left..left.saturating_add(width),
+                    is_continuation: false,
+                })
+                .collect();
+        }
+        let mut rows = Vec::new();
+        // From the line at the top of the view on
+        let (top_line_idx, mut first_row) = self.row_cache().line_at_row(self.scroll_offset.row);
+        for (line_idx, line) in self.buffer.borrow().lines.iter().enumerate().skip(top_line_idx) {
+            if rows.len() >= height {
+                break;
+            }
+            let line_rows = self.line_rows(line_idx, line);
+            let row_count = line_rows.len();
+            if first_row.saturating_add(row_count) > self.scroll_offset.row {
+                for (sub_row, row_start) in line_rows.iter().enumerate() {
+                    if first_row.saturating_add(sub_row) < self.scroll_offset.row {
+                        continue;
+                    }
+                    let cols = match line_rows.get(sub_row.saturating_add(1)) {
+                        Some(row_end) => line.width_until(*row_start)..line.width_until(*row_end),
+                        None if self.soft_wrap && row_count > 1 => {
+                            line.width_until(*row_start)..line.width()
+                        }
+                        None => left..left.saturating_add(width),
+                    };
+                    rows.push(ScreenRow {
+                        line_idx,
+                        cols,
+                        is_continuation: sub_row > 0,
+                    });
+                }
+            }
+            first_row = first_row.saturating_add(row_count);
+        }
+        rows.truncate(height);
+        rows
     }
     // endregion
 
     // region: text location movement
     fn move_up(&mut self, step: usize) {
-        self.text_location.line_idx = self.text_location.line_idx.saturating_sub(step);
-        self.snap_to_valid_grapheme();
+        let row = self.text_location_to_position().row;
+        self.move_to_row(row.saturating_sub(step));
     }
     fn move_down(&mut self, step: usize) {
-        self.text_location.line_idx = self.text_location.line_idx.saturating_add(step);
-        self.snap_to_valid_grapheme();
-        self.snap_to_valid_line();
+        let row = self.text_location_to_position().row;
+        self.move_to_row(row.saturating_add(step));
+    }
+    // Moves vertically to the given row, keeping the desired column.
+    fn move_to_row(&mut self, row: Row) {
+        let col = self.desired_col();
+        self.text_location = self.position_to_location(Position { col, row });
+        self.sticky_col = Some((self.text_location, col));
+    }
+    // The column to keep while moving vertically: the one we started from, even if we went through shorter lines since.
+    fn desired_col(&self) -> Col {
+        match self.sticky_col {
+            Some((location, col)) if location == self.text_location => col,
+            _ => self.text_location_to_position().col,
+        }
     }
 
     #[allow(clippy::arithmetic_side_effects)]
     fn move_right(&mut self) {
         let line_width = self
-        .buffer
+        .buffer.borrow()
         .lines
         .get(self.text_location.line_idx)
         .map_or(0, Line::grapheme_count);
@@ -328,7 +1953,7 @@ impl View {
     }
     fn move_to_end_of_line(&mut self) {
         self.text_location.grapheme_idx = self
-            .buffer
+            .buffer.borrow()
             .lines
             .get(self.text_location.line_idx)
             .map_or(0, Line::grapheme_count);
@@ -338,7 +1963,7 @@ impl View {
     // Doesn't trigger scrolling.
     fn snap_to_valid_grapheme(&mut self) {
         self.text_location.grapheme_idx = self
-            .buffer
+            .buffer.borrow()
             .lines
             .get(self.text_location.line_idx)
             .map_or(0, |line| {
@@ -348,7 +1973,7 @@ impl View {
     // Ensures self.location.line_idx points to a valid line idx by snapping it to the bottom most line if appropriate.
     // Doesn't trigger scrolling.
     fn snap_to_valid_line(&mut self) {
-        self.text_location.line_idx = min(self.text_location.line_idx, self.buffer.height());
+        self.text_location.line_idx = min(self.text_location.line_idx, self.buffer.borrow().height());
     }
     // endregion
 }
@@ -364,46 +1989,165 @@ impl UIComponent for View {
         self.size = size;
         self.scroll_text_location_into_view();
     }
-    fn draw(&mut self, origin_row: usize) -> Result<(), Error> {
-
+    fn draw(&mut self, origin: Position) -> Result<(), Error> {
+        // Before the gutter width is known, as the first change may make room for the signs
+        self.buffer.borrow_mut().update_git_diff();
+        if let Some(blame) = &mut self.blame {
+            let buffer = self.buffer.borrow();
+            blame.update(&buffer.lines, buffer.revision());
+        }
         let Size { height, width } = self.size;
+        let origin_row = origin.row;
         let end_y = origin_row.saturating_add(height);
+        let gutter_width = self.gutter_width();
+        let text_col = origin.col.saturating_add(gutter_width);
+        let text_width = width.saturating_sub(gutter_width);
 
         let top_third = height.div_ceil(3);
-        let scroll_top = self.scroll_offset.row;
+        let screen_rows = self.screen_rows();
+        if gutter_width > 0 {
+            self.render_gutter(origin, &screen_rows)?;
+        }
+        if let Some(last_row) = screen_rows.last() {
+            self.buffer.borrow_mut().highlight_until(last_row.line_idx);
+        }
+        let query = self
+            .search_info
+            .as_ref()
+            .and_then(|search_info| search_info.query.as_deref());
+        let buffer = self.buffer.borrow();
         for current_row in origin_row..end_y {
-            // to get the correct line idx, we have to take current_row (the absolute row on screen),
-            // subtract origin_row to get the current row relative to the view (ranging from 0 to self.size.height)
-            // and add the scroll offset.
-            let line_idx = current_row
-                .saturating_sub(origin_row)
-                .saturating_add(scroll_top);
-            
-            if let Some(line) = self.buffer.lines.get(line_idx) {
-                let left = self.scroll_offset.col;
-                let right = self.scroll_offset.col.saturating_add(width);
-
-                let query = self
-                            .search_info
-                            .as_ref()
-                            .and_then(|search_info| search_info.query.as_deref());
-                let selected_match = (self.text_location.line_idx == line_idx && query.is_some())
-                                                        .then_some(self.text_location.grapheme_idx);
-                Terminal::print_annotated_row(
-                    current_row,
-                    &line.get_annotated_visible_substr(left..right, query, selected_match),
-                )?;
+            // to get the correct screen row, we have to take current_row (the absolute row on screen)
+            // and subtract origin_row to get the current row relative to the view (ranging from 0 to self.size.height)
+            let screen_row = screen_rows.get(current_row.saturating_sub(origin_row));
+
+            if let Some((row, line)) = screen_row
+                .and_then(|row| buffer.lines.get(row.line_idx).map(|line| (row, line)))
+            {
+                let line_idx = row.line_idx;
+                let annotated_string = self.annotated_row(&buffer, row, line, query, text_width);
+                let text_start = Position {
+                    row: current_row,
+                    col: text_col,
+                };
+                // Other panes may be next to this one: clear only our part of the row
+                Self::render_line(text_start, text_width, "")?;
+                Terminal::print_annotated_at(text_start, &annotated_string)?;
+                if let Some(hidden) = self.folds().hidden_after(line_idx) {
+                    let text_end = line
+                        .width()
+                        .saturating_sub(row.cols.start)
+                        .min(row.cols.len());
+                    // Cut at the edge of the pane, like the text is, to stay out of the pane to the right
+                    let room = text_width.saturating_sub(text_end);
+                    let summary: String = format!(" ⋯ {hidden} lines").chars().take(room).collect();
+                    if summary.is_empty() {
+                        continue;
+                    }
+                    let mut annotated_summary = AnnotatedString::from(&summary);
+                    annotated_summary.add_annotation(AnnotationType::FoldSummary, 0, summary.len());
+                    Terminal::print_annotated_at(
+                        Position {
+                            row: current_row,
+                            col: text_col.saturating_add(text_end),
+                        },
+                        &annotated_summary,
+                    )?;
+                }
 
             }
             // afficher le texte de bienvenue
-            else if current_row == top_third && self.buffer.is_empty() {
-                Self::render_line(current_row, &Self::build_welcome_message(width))?;
+            else if current_row.saturating_sub(origin_row) == top_third && buffer.is_empty() {
+                Self::render_line(
+                    Position {
+                        row: current_row,
+                        col: origin.col,
+                    },
+                    width,
+                    &Self::build_welcome_message(width),
+                )?;
             }
             // afficher une ligne vide
             else {
-                Self::render_line(current_row, "~")?;
+                Self::render_line(
+                    Position {
+                        row: current_row,
+                        col: origin.col,
+                    },
+                    width,
+                    "~",
+                )?;
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::super::super::terminal::Theme;
+    use super::{AnnotationType, BufferHandle, Location, ScreenRow, View};
+
+    // A view on a file with the given name and text, so that its file type is known
+    fn view_of(file_name: &str, text: &str) -> View {
+        let path = env::temp_dir().join(format!("hecto-view-{}-{file_name}", process::id()));
+        fs::write(&path, text).unwrap();
+        let buffer = BufferHandle::load(&path.to_string_lossy());
+        let _ = fs::remove_file(&path);
+        let mut view = View::default();
+        view.show_buffer(buffer.unwrap());
+        view
+    }
+
+    #[test]
+    fn keyword_on_the_caret_line_keeps_the_current_line_tint() {
+        let mut view = view_of("caret-line.rs", "fn main() {}\n");
+        view.highlight_current_line = true;
+        view.buffer.borrow_mut().highlight_until(0);
+
+        let buffer = view.buffer.borrow();
+        let row = ScreenRow {
+            line_idx: 0,
+            cols: 0..20,
+            is_continuation: false,
+        };
+        let annotated_row = view.annotated_row(&buffer, &row, &buffer.lines[0], None, 20);
+        let keyword = annotated_row
+            .into_iter()
+            .find(|part| part.string == "fn")
+            .unwrap();
+        assert_eq!(keyword.annotation_types, [AnnotationType::CurrentLine, AnnotationType::Keyword]);
+
+        let theme = Theme::default();
+        let style = theme.style(&keyword.annotation_types);
+        let current_line = theme.style(&[AnnotationType::CurrentLine]);
+        assert!(current_line.background.is_some());
+        assert_eq!(style.background, current_line.background);
+        assert_eq!(style.foreground, theme.style(&[AnnotationType::Keyword]).foreground);
+    }
+
+    #[test]
+    fn braces_in_strings_and_comments_dont_fold() {
+        let mut view = view_of(
+            "braces.rs",
+            "fn main() {\n    let open = \"{\";\n    let close = '}';\n    // }\n    /* { */\n}\nfn other() {}\n",
+        );
+        assert!(view.fold_at_caret());
+        assert_eq!(view.folds().line_ranges(), [(0, 5)]);
+    }
+
+    #[test]
+    fn folds_stay_in_their_view_and_follow_the_edits() {
+        let mut view = view_of("folds.txt", "a\n  b\n  c\nd\n");
+        let other = view.split();
+        assert!(view.fold_at_caret());
+        assert_eq!(view.folds().line_ranges(), [(0, 2)]);
+        assert!(other.folds().is_empty());
+
+        view.buffer.borrow_mut().insert_text("new\n", Location::default());
+        assert_eq!(view.folds().line_ranges(), [(1, 3)]);
+        assert!(other.folds().is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Radix {
+    Decimal,
+    Hexadecimal,
+    Binary,
+}
+
+impl Radix {
+    const fn value(self) -> u32 {
+        match self {
+            Self::Decimal => 10,
+            Self::Hexadecimal => 16,
+            Self::Binary => 2,
+        }
+    }
+}
+
+// A number found in a line of text, e.g. `-42`, `007`, `0xFF` or `0b1010`.
+#[derive(Debug)]
+pub struct NumberLiteral<'a> {
+    // The byte range of the whole literal within the text, including sign and prefix
+    pub range: Range<usize>,
+    text: &'a str,
+    radix: Radix,
+}
+
+impl<'a> NumberLiteral<'a> {
+    // Finds the first number which contains the given byte index or starts behind it.
+    pub fn find(text: &'a str, from_byte_idx: usize) -> Option<Self> {
+        let bytes = text.as_bytes();
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let Some(&byte) = bytes.get(idx) else {
+                break;
+            };
+            if !byte.is_ascii_digit() {
+                idx = idx.saturating_add(1);
+                continue;
+            }
+            let (radix, digits_start) = match (byte, bytes.get(idx.saturating_add(1))) {
+                (b'0', Some(b'x' | b'X'))
+                    if bytes
+                        .get(idx.saturating_add(2))
+                        .is_some_and(u8::is_ascii_hexdigit) =>
+                {
+                    (Radix::Hexadecimal, idx.saturating_add(2))
+                }
+                (b'0', Some(b'b' | b'B'))
+                    if bytes
+                        .get(idx.saturating_add(2))
+                        .is_some_and(|byte| matches!(byte, b'0' | b'1')) =>
+                {
+                    (Radix::Binary, idx.saturating_add(2))
+                }
+                _ => (Radix::Decimal, idx),
+            };
+            let digits_end = bytes
+                .iter()
+                .enumerate()
+                .skip(digits_start)
+                .find(|(_, byte)| !char::from(**byte).is_digit(radix.value()))
+                .map_or(bytes.len(), |(end, _)| end);
+
+            // A minus sign right in front of a decimal number makes it negative, unless it's part of a word like `a-1`
+            let start = if radix == Radix::Decimal
+                && idx > 0
+                && bytes.get(idx.saturating_sub(1)) == Some(&b'-')
+                && (idx < 2
+                    || !bytes
+                        .get(idx.saturating_sub(2))
+                        .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_'))
+            {
+                idx.saturating_sub(1)
+            } else {
+                idx
+            };
+
+            if digits_end > from_byte_idx {
+                return text.get(start..digits_end).map(|literal| Self {
+                    range: start..digits_end,
+                    text: literal,
+                    radix,
+                });
+            }
+            idx = digits_end;
+        }
+        None
+    }
+
+    // Returns the literal with `delta` added to it. Zero padding and the case of hexadecimal digits are preserved.
+    // Hexadecimal and binary numbers are treated as unsigned 64 bit values which wrap around.
+    pub fn add(&self, delta: i64) -> Option<String> {
+        match self.radix {
+            Radix::Decimal => self.add_decimal(delta),
+            Radix::Hexadecimal | Radix::Binary => self.add_unsigned(delta),
+        }
+    }
+
+    fn add_decimal(&self, delta: i64) -> Option<String> {
+        let (is_negative, digits) = self
+            .text
+            .strip_prefix('-')
+            .map_or((false, self.text), |digits| (true, digits));
+        let magnitude: i128 = digits.parse().ok()?;
+        let value = if is_negative {
+            magnitude.checked_neg()?
+        } else {
+            magnitude
+        };
+        let new_value = value.saturating_add(i128::from(delta));
+        let sign = if new_value < 0 { "-" } else { "" };
+        let width = if digits.len() > 1 && digits.starts_with('0') {
+            digits.len()
+        } else {
+            0
+        };
+        Some(format!("{sign}{:0width$}", new_value.unsigned_abs()))
+    }
+
+    fn add_unsigned(&self, delta: i64) -> Option<String> {
+        let (prefix, digits) = self.text.split_at_checked(2)?;
+        let value = u64::from_str_radix(digits, self.radix.value()).ok()?;
+        let new_value = value.wrapping_add_signed(delta);
+        let width = digits.len();
+        let new_digits = match self.radix {
+            Radix::Binary => format!("{new_value:0width$b}"),
+            _ if Self::is_upper_case(prefix, digits) => format!("{new_value:0width$X}"),
+            _ => format!("{new_value:0width$x}"),
+        };
+        Some(format!("{prefix}{new_digits}"))
+    }
+
+    // Hexadecimal digits keep the case of the last letter, or of the prefix if there is no letter.
+    fn is_upper_case(prefix: &str, digits: &str) -> bool {
+        digits
+            .chars()
+            .rev()
+            .find(char::is_ascii_alphabetic)
+            .map_or(prefix.ends_with('X'), |ch| ch.is_ascii_uppercase())
+    }
+}
@@ -0,0 +1,109 @@
+use super::{Buffer, Folds, Row};
+
+// The number of rows each line takes when soft wrap or folds are on, so that mapping between lines and rows
+// doesn't wrap every line above the caret each time. The wrapped row counts follow the buffer edits, only
+// the edited lines are wrapped again.
+#[derive(Default)]
+pub struct RowCache {
+    // What the counts are for
+    is_valid: bool,
+    revision: usize,
+    width: usize,
+    soft_wrap: bool,
+    folds: Vec<(usize, usize)>,
+    // Rows of each line when wrapped, regardless of folds. None for edited lines until they are wrapped again.
+    wrapped_rows: Vec<Option<usize>>,
+    // Rows before each line, folds included, followed by the total
+    rows_before: Vec<Row>,
+}
+
+impl RowCache {
+    pub fn update(&mut self, buffer: &Buffer, folds: &Folds, width: usize, soft_wrap: bool) {
+        let revision = buffer.revision();
+        let folds = folds.line_ranges();
+        let is_current = self.is_valid && self.width == width && self.soft_wrap == soft_wrap;
+        if is_current && self.revision == revision && self.folds == folds {
+            return;
+        }
+        let edits = is_current.then(|| buffer.edits_since(self.revision)).flatten();
+        let follows_edits = edits.is_some();
+        if let Some(edits) = edits {
+            for edit in edits {
+                let len = self.wrapped_rows.len();
+                let old_lines = edit.from.line_idx.min(len)..edit.old_end.line_idx.saturating_add(1).min(len);
+                let new_line_count = edit
+                    .new_end
+                    .line_idx
+                    .saturating_sub(edit.from.line_idx)
+                    .saturating_add(1);
+                self.wrapped_rows
+                    .splice(old_lines, (0..new_line_count).map(|_| None));
+            }
+        }
+        if !follows_edits || self.wrapped_rows.len() != buffer.lines.len() {
+            self.wrapped_rows = vec![None; buffer.lines.len()];
+        }
+        for (slot, line) in self.wrapped_rows.iter_mut().zip(&buffer.lines) {
+            if slot.is_none() {
+                *slot = Some(if soft_wrap { line.wrap_points(width).len() } else { 1 });
+            }
+        }
+
+        // Lines hidden by how many folds, counted as the difference from the line before
+        let mut hidden_delta = vec![0_isize; buffer.lines.len().saturating_add(1)];
+        for (start, end) in &folds {
+            if let Some(delta) = hidden_delta.get_mut(start.saturating_add(1)) {
+                *delta = delta.saturating_add(1);
+            }
+            if let Some(delta) = hidden_delta.get_mut(end.saturating_add(1)) {
+                *delta = delta.saturating_sub(1);
+            }
+        }
+        let mut headers: Vec<usize> = folds.iter().map(|(start, _)| *start).collect();
+        headers.sort_unstable();
+        let mut hiding_folds: isize = 0;
+        let mut total: Row = 0;
+        self.rows_before.clear();
+        for (line_idx, wrapped_rows) in self.wrapped_rows.iter().enumerate() {
+            hiding_folds = hiding_folds.saturating_add(hidden_delta.get(line_idx).copied().unwrap_or(0));
+            self.rows_before.push(total);
+            // The same as View::line_rows
+            let rows = if hiding_folds > 0 {
+                0
+            } else if headers.binary_search(&line_idx).is_ok() {
+                1
+            } else {
+                wrapped_rows.unwrap_or(1)
+            };
+            total = total.saturating_add(rows);
+        }
+        self.rows_before.push(total);
+
+        self.is_valid = true;
+        self.revision = revision;
+        self.width = width;
+        self.soft_wrap = soft_wrap;
+        self.folds = folds;
+    }
+
+    // Number of rows taken by the lines before the given one
+    pub fn rows_before(&self, line_idx: usize) -> Row {
+        self.rows_before
+            .get(line_idx)
+            .or_else(|| self.rows_before.last())
+            .copied()
+            .unwrap_or(0)
+    }
+    pub fn row_count(&self) -> Row {
+        self.rows_before.last().copied().unwrap_or(0)
+    }
+    // The line shown on the given row and the row it starts at. Rows past the end map to the line right
+    // behind the last one.
+    pub fn line_at_row(&self, row: Row) -> (usize, Row) {
+        let line_idx = self
+            .rows_before
+            .partition_point(|rows_before| *rows_before <= row)
+            .saturating_sub(1);
+        (line_idx, self.rows_before(line_idx))
+    }
+}
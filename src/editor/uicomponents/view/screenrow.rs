@@ -0,0 +1,12 @@
+use std::ops::Range;
+
+use super::super::super::Col;
+
+// A row of the screen showing (part of) a line
+pub struct ScreenRow {
+    pub line_idx: usize,
+    // The columns of the line shown on this row
+    pub cols: Range<Col>,
+    // Whether this row continues a soft-wrapped line rather than showing its start
+    pub is_continuation: bool,
+}
@@ -0,0 +1,16 @@
+use super::Location;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum SelectionMode {
+    // Everything between the anchor and the caret, across lines
+    Linear,
+    // The rectangle spanned by the anchor and the caret, in screen columns
+    Block,
+}
+
+// The caret is the moving end of the selection, the anchor the end where it started.
+#[derive(Copy, Clone)]
+pub struct Selection {
+    pub anchor: Location,
+    pub mode: SelectionMode,
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::editor::config::config_dir;
+use super::FileType;
+
+mod snippet;
+pub use snippet::{ExpandedSnippet, Snippet};
+mod snippetsession;
+pub use snippetsession::SnippetSession;
+mod snippetvariables;
+pub use snippetvariables::SnippetVariables;
+mod tabstop;
+pub use tabstop::{Span, TabStop};
+
+const SNIPPET_DIR_NAME: &str = "snippets";
+// Snippets available regardless of the file type
+const GLOBAL_SNIPPETS_NAME: &str = "all";
+const SNIPPET_KEYWORD: &str = "snippet";
+
+// The snippets available for one file type, keyed by their trigger word.
+// Snippets are read from <config dir>/snippets/<file type>.snippets, using the snipMate format:
+//
+// snippet fn
+// 	fn ${1:name}(${2}) {
+// 		$0
+// 	}
+//
+// Body lines start with a tab, which is stripped. Lines starting with '#' outside of a body are comments.
+#[derive(Default)]
+pub struct SnippetLibrary {
+    snippets: HashMap<String, Snippet>,
+}
+
+impl SnippetLibrary {
+    pub fn load(file_type: FileType) -> Self {
+        let mut library = Self::default();
+        if let Some(dir) = config_dir().map(|dir| dir.join(SNIPPET_DIR_NAME)) {
+            library.load_file(&dir.join(format!("{GLOBAL_SNIPPETS_NAME}.snippets")));
+            library.load_file(&dir.join(format!("{file_type}.snippets")));
+        }
+        library
+    }
+
+    // Missing or unreadable files are ignored: snippets are optional.
+    fn load_file(&mut self, path: &Path) {
+        if let Ok(contents) = read_to_string(path) {
+            self.snippets.extend(Self::parse(&contents));
+        }
+    }
+
+    fn parse(contents: &str) -> Vec<(String, Snippet)> {
+        let mut result = Vec::new();
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for line in contents.lines() {
+            if let Some(body_line) = line.strip_prefix('\t') {
+                if let Some((_, body)) = &mut current {
+                    body.push(body_line);
+                }
+                continue;
+            }
+            if line.is_empty() {
+                if let Some((_, body)) = &mut current {
+                    body.push("");
+                }
+                continue;
+            }
+            if let Some((trigger, body)) = current.take() {
+                result.push((trigger, Self::build_snippet(&body)));
+            }
+            if let Some(definition) = line.strip_prefix(SNIPPET_KEYWORD) {
+                if let Some(trigger) = definition.split_whitespace().next() {
+                    current = Some((trigger.to_string(), Vec::new()));
+                }
+            }
+        }
+        if let Some((trigger, body)) = current {
+            result.push((trigger, Self::build_snippet(&body)));
+        }
+        result
+    }
+
+    fn build_snippet(body: &[&str]) -> Snippet {
+        let body_len = body
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |idx| idx.saturating_add(1));
+        Snippet::from(&body.iter().take(body_len).copied().collect::<Vec<_>>().join("\n"))
+    }
+
+    // Looks for a trigger at the end of the given text, which is the text in front of the caret.
+    // Triggers must start at a word boundary, so that `fn` does not expand within `xfn`.
+    // Returns the trigger length in graphemes, and the matching snippet.
+    pub fn find_trigger(&self, text_before_caret: &str) -> Option<(usize, &Snippet)> {
+        let word_start = text_before_caret
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| !ch.is_whitespace())
+            .last()
+            .map(|(idx, _)| idx)?;
+        let word = text_before_caret.get(word_start..)?;
+        let mut previous: Option<char> = None;
+        for (idx, ch) in word.char_indices() {
+            let at_boundary =
+                previous.is_none_or(|previous| !previous.is_alphanumeric() || !ch.is_alphanumeric());
+            if at_boundary {
+                if let Some((candidate, snippet)) = word
+                    .get(idx..)
+                    .and_then(|candidate| self.snippets.get_key_value(candidate))
+                {
+                    return Some((candidate.graphemes(true).count(), snippet));
+                }
+            }
+            previous = Some(ch);
+        }
+        None
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{SnippetVariables, Span, TabStop};
+
+enum Token {
+    Text(String),
+    TabStop {
+        number: usize,
+        default: Option<String>,
+    },
+    Variable {
+        name: String,
+        default: Option<String>,
+    },
+}
+
+// The result of expanding a snippet: the text to insert, and where its tab stops ended up.
+// Spans are relative to the insertion point: the line index is an offset to the line the snippet was inserted on,
+// and the grapheme indices on that first line are offsets to the insertion column.
+pub struct ExpandedSnippet {
+    pub text: String,
+    pub tab_stops: Vec<TabStop>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Snippet {
+    body: String,
+}
+
+impl Snippet {
+    pub fn from(body: &str) -> Self {
+        Self {
+            body: body.to_string(),
+        }
+    }
+
+    // Expands the snippet body. Every line but the first is prefixed with the given indentation.
+    // Tab stops are ordered $1, $2, ... and end with $0, which is added at the end of the text if the body lacks one.
+    pub fn expand(&self, variables: &SnippetVariables, indent: &str) -> ExpandedSnippet {
+        let tokens = Self::tokenize(&self.body);
+
+        // Mirrored tab stops all show the default of the first occurrence that has one
+        let mut defaults: HashMap<usize, String> = HashMap::new();
+        for token in &tokens {
+            if let Token::TabStop {
+                number,
+                default: Some(default),
+            } = token
+            {
+                defaults.entry(*number).or_insert_with(|| default.clone());
+            }
+        }
+
+        let mut text = String::new();
+        let mut line_offset = 0;
+        let mut current_line = String::new();
+        let mut tab_stops: Vec<TabStop> = Vec::new();
+        for token in tokens {
+            let content = match token {
+                Token::Text(content) => content,
+                Token::Variable { name, default } => variables
+                    .resolve(&name)
+                    .or(default)
+                    .unwrap_or_default(),
+                Token::TabStop { number, .. } => {
+                    let content = defaults.get(&number).cloned().unwrap_or_default();
+                    let start = current_line.graphemes(true).count();
+                    let span = Span {
+                        line_idx: line_offset,
+                        start,
+                        end: start.saturating_add(content.graphemes(true).count()),
+                    };
+                    if let Some(tab_stop) = tab_stops.iter_mut().find(|stop| stop.number == number) {
+                        tab_stop.spans.push(span);
+                    } else {
+                        tab_stops.push(TabStop {
+                            number,
+                            spans: vec![span],
+                        });
+                    }
+                    content.replace('\n', " ")
+                }
+            };
+            for (idx, part) in content.split('\n').enumerate() {
+                if idx > 0 {
+                    text.push('\n');
+                    text.push_str(indent);
+                    line_offset = line_offset.saturating_add(1);
+                    current_line = String::from(indent);
+                }
+                text.push_str(part);
+                current_line.push_str(part);
+            }
+        }
+
+        if !tab_stops.iter().any(|stop| stop.number == 0) {
+            let end = current_line.graphemes(true).count();
+            tab_stops.push(TabStop {
+                number: 0,
+                spans: vec![Span {
+                    line_idx: line_offset,
+                    start: end,
+                    end,
+                }],
+            });
+        }
+        // $0 is the final tab stop
+        tab_stops.sort_by_key(|stop| if stop.number == 0 { usize::MAX } else { stop.number });
+
+        ExpandedSnippet { text, tab_stops }
+    }
+
+    fn tokenize(body: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut text = String::new();
+        let mut chars = body.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.peek() {
+                    Some(&escaped @ ('$' | '}' | '\\')) => {
+                        chars.next();
+                        text.push(escaped);
+                    }
+                    _ => text.push(ch),
+                },
+                '$' => {
+                    if let Some(token) = Self::parse_dollar(&mut chars) {
+                        if !text.is_empty() {
+                            tokens.push(Token::Text(std::mem::take(&mut text)));
+                        }
+                        tokens.push(token);
+                    } else {
+                        text.push(ch);
+                    }
+                }
+                _ => text.push(ch),
+            }
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        tokens
+    }
+
+    // Parses what follows a `$`: `$1`, `${1}`, `${1:default}`, `$NAME`, `${NAME}` or `${NAME:default}`.
+    // Returns None if the `$` should be taken literally.
+    fn parse_dollar(chars: &mut Peekable<Chars>) -> Option<Token> {
+        match chars.peek() {
+            Some(ch) if ch.is_ascii_digit() => {
+                let digits = Self::take_while(chars, |ch| ch.is_ascii_digit());
+                digits.parse().ok().map(|number| Token::TabStop {
+                    number,
+                    default: None,
+                })
+            }
+            Some(ch) if ch.is_ascii_alphabetic() || *ch == '_' => {
+                let name = Self::take_while(chars, |ch| ch.is_ascii_alphanumeric() || ch == '_');
+                Some(Token::Variable {
+                    name,
+                    default: None,
+                })
+            }
+            Some('{') => {
+                chars.next();
+                let mut inner = String::new();
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '}' => break,
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                inner.push(escaped);
+                            }
+                        }
+                        _ => inner.push(ch),
+                    }
+                }
+                let (name, default) = inner
+                    .split_once(':')
+                    .map_or((inner.as_str(), None), |(name, default)| {
+                        (name, Some(default.to_string()))
+                    });
+                if let Ok(number) = name.parse() {
+                    Some(Token::TabStop { number, default })
+                } else {
+                    Some(Token::Variable {
+                        name: name.to_string(),
+                        default,
+                    })
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+        let mut result = String::new();
+        while let Some(&ch) = chars.peek() {
+            if !predicate(ch) {
+                break;
+            }
+            result.push(ch);
+            chars.next();
+        }
+        result
+    }
+}
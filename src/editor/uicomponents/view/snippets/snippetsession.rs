@@ -0,0 +1,129 @@
+use super::super::Location;
+use super::{Span, TabStop};
+
+// An expanded snippet whose tab stops are being cycled through.
+pub struct SnippetSession {
+    tab_stops: Vec<TabStop>,
+    current: usize,
+    // When jumping to a placeholder with a default value, the first edit replaces that value.
+    overwrite_pending: bool,
+}
+
+impl SnippetSession {
+    // Returns None if the snippet only has its final tab stop, since there is nothing to cycle through.
+    pub fn new(tab_stops: Vec<TabStop>) -> Option<Self> {
+        if tab_stops.len() < 2 {
+            return None;
+        }
+        let mut session = Self {
+            tab_stops,
+            current: 0,
+            overwrite_pending: false,
+        };
+        session.update_overwrite_pending();
+        Some(session)
+    }
+
+    pub fn current_span(&self) -> Option<Span> {
+        self.tab_stops.get(self.current).and_then(TabStop::primary)
+    }
+
+    // Returns true if the session reached the final tab stop ($0) and should end.
+    pub fn is_at_end(&self) -> bool {
+        self.current.saturating_add(1) >= self.tab_stops.len()
+    }
+
+    pub fn next(&mut self) {
+        if !self.is_at_end() {
+            self.current = self.current.saturating_add(1);
+            self.update_overwrite_pending();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        self.current = self.current.saturating_sub(1);
+        self.update_overwrite_pending();
+    }
+
+    pub const fn is_overwrite_pending(&self) -> bool {
+        self.overwrite_pending
+    }
+
+    pub fn clear_overwrite_pending(&mut self) {
+        self.overwrite_pending = false;
+    }
+
+    fn update_overwrite_pending(&mut self) {
+        self.overwrite_pending = self
+            .current_span()
+            .is_some_and(|span| span.start < span.end);
+    }
+
+    // Returns true if the given location lies within (or at either edge of) the placeholder being edited.
+    pub fn is_in_current_span(&self, location: Location) -> bool {
+        self.current_span().is_some_and(|span| {
+            span.line_idx == location.line_idx
+                && span.start <= location.grapheme_idx
+                && location.grapheme_idx <= span.end
+        })
+    }
+
+    // The mirrors of the placeholder being edited, i.e. all its spans except the primary one.
+    pub fn current_mirrors(&self) -> Vec<Span> {
+        self.tab_stops
+            .get(self.current)
+            .map_or_else(Vec::new, |stop| stop.spans.iter().skip(1).copied().collect())
+    }
+
+    // Updates all spans after `len` graphemes were inserted at the given location.
+    // The span identified by `owner` (tab stop index, span index) grows to contain the inserted text,
+    // all spans starting at or behind the insertion point are moved.
+    pub fn adjust_for_insert(&mut self, at: Location, len: usize, owner: Option<(usize, usize)>) {
+        for (stop_idx, stop) in self.tab_stops.iter_mut().enumerate() {
+            for (span_idx, span) in stop.spans.iter_mut().enumerate() {
+                if span.line_idx != at.line_idx {
+                    continue;
+                }
+                let is_owner = owner == Some((stop_idx, span_idx));
+                if is_owner && span.start <= at.grapheme_idx && at.grapheme_idx <= span.end {
+                    span.end = span.end.saturating_add(len);
+                } else if span.start >= at.grapheme_idx {
+                    span.start = span.start.saturating_add(len);
+                    span.end = span.end.saturating_add(len);
+                } else if span.end > at.grapheme_idx {
+                    span.end = span.end.saturating_add(len);
+                }
+            }
+        }
+    }
+
+    // Updates all spans after `len` graphemes were deleted at the given location.
+    pub fn adjust_for_delete(&mut self, at: Location, len: usize) {
+        let deleted_end = at.grapheme_idx.saturating_add(len);
+        let shrink = |idx: usize| {
+            if idx >= deleted_end {
+                idx.saturating_sub(len)
+            } else if idx > at.grapheme_idx {
+                at.grapheme_idx
+            } else {
+                idx
+            }
+        };
+        for span in self.tab_stops.iter_mut().flat_map(|stop| stop.spans.iter_mut()) {
+            if span.line_idx == at.line_idx {
+                span.start = shrink(span.start);
+                span.end = shrink(span.end);
+            }
+        }
+    }
+
+    // The owner to pass to adjust_for_insert for edits made in the n-th mirror of the current placeholder.
+    pub const fn mirror_owner(&self, mirror_idx: usize) -> (usize, usize) {
+        (self.current, mirror_idx.saturating_add(1))
+    }
+
+    // The owner to pass to adjust_for_insert for edits made in the current placeholder.
+    pub const fn current_owner(&self) -> (usize, usize) {
+        (self.current, 0)
+    }
+}
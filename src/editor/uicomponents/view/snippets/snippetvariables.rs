@@ -0,0 +1,78 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::super::FileInfo;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+// Values for the variables a snippet may reference, e.g. `$TM_FILENAME` or `${CURRENT_YEAR}`.
+pub struct SnippetVariables {
+    file_path: Option<PathBuf>,
+    year: u64,
+    month: u64,
+    day: u64,
+}
+
+impl SnippetVariables {
+    pub fn from(file_info: &FileInfo) -> Self {
+        let days_since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+            .checked_div(SECONDS_PER_DAY)
+            .unwrap_or_default();
+        let (year, month, day) = Self::civil_from_days(days_since_epoch);
+        Self {
+            file_path: file_info.get_path().map(PathBuf::from),
+            year,
+            month,
+            day,
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let path = self.file_path.as_deref();
+        match name {
+            "TM_FILENAME" => path
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().to_string()),
+            "TM_FILENAME_BASE" => path
+                .and_then(|path| path.file_stem())
+                .map(|name| name.to_string_lossy().to_string()),
+            "TM_DIRECTORY" => path
+                .and_then(|path| path.parent())
+                .map(|dir| dir.to_string_lossy().to_string()),
+            "TM_FILEPATH" => path.map(|path| path.to_string_lossy().to_string()),
+            "CURRENT_YEAR" => Some(self.year.to_string()),
+            "CURRENT_MONTH" => Some(format!("{:02}", self.month)),
+            "CURRENT_DATE" => Some(format!("{:02}", self.day)),
+            "CURRENT_ISO_DATE" => Some(format!("{}-{:02}-{:02}", self.year, self.month, self.day)),
+            _ => None,
+        }
+    }
+
+    // Converts a number of days since 1970-01-01 into a (year, month, day) triple of the proleptic Gregorian calendar.
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    // The date is computed in UTC, since there is no time zone database to rely on.
+    // clippy::arithmetic_side_effects, clippy::integer_division: All values are positive and far from overflowing,
+    // and the algorithm relies on truncating divisions.
+    #[allow(clippy::arithmetic_side_effects, clippy::integer_division)]
+    const fn civil_from_days(days_since_epoch: u64) -> (u64, u64, u64) {
+        let shifted = days_since_epoch + 719_468;
+        let era = shifted / 146_097;
+        let day_of_era = shifted - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+}
@@ -0,0 +1,21 @@
+// A range of graphemes on a single line, occupied by a snippet placeholder.
+#[derive(Copy, Clone, Debug)]
+pub struct Span {
+    pub line_idx: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// A numbered placeholder of a snippet. The first span is the one being edited,
+// the other spans mirror its content.
+#[derive(Clone, Debug)]
+pub struct TabStop {
+    pub number: usize,
+    pub spans: Vec<Span>,
+}
+
+impl TabStop {
+    pub fn primary(&self) -> Option<Span> {
+        self.spans.first().copied()
+    }
+}
@@ -0,0 +1,9 @@
+// A replacement of some text, as a tool like a language server describes it.
+// Lines and columns count from 0, columns in characters.
+#[derive(Clone, Debug)]
+pub struct TextEdit {
+    pub start: (usize, usize),
+    // Exclusive
+    pub end: (usize, usize),
+    pub new_text: String,
+}